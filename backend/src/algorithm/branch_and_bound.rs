@@ -0,0 +1,508 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, ComputeControl, resolve_seed, Area, compute_wait_and_service_time, Coordinate, EndSector, find_mandatory_order, Route, RouteSector, ScoreMap, SearchSpace, Sector, TerminationReason, UserPreferences};
+use crate::algorithm::sa_lin_yu::{build_distance_map, DistanceMap, compute_scores};
+use crate::data::graph::{Graph, Sight};
+
+/// Maximum number of candidate sights, i.e., sights with a non-zero score, the exact search is
+/// run on
+const MAX_CANDIDATES: usize = 15;
+
+/// State of the depth-first search
+///
+/// # Fields
+/// * `best_score` - The best score of a feasible route found so far
+/// * `best_solution` - The candidate indices of the best feasible route found so far
+/// * `path` - The candidate indices of the current partial route
+/// * `departure_times` - The earliest departure time for each explored pair of a set of visited
+///   candidates and the last visited candidate
+struct SearchState {
+    best_score: Option<usize>,
+    best_solution: Vec<usize>,
+    path: Vec<usize>,
+    departure_times: HashMap<(u32, usize), i64>,
+}
+
+/// Implementation of the `Algorithm` trait that computes a route with the maximum score by a
+/// branch-and-bound search over all sequences of candidate sights.
+///
+/// Partial routes are pruned if the sum of their score and the scores of all sights that can still
+/// be visited directly does not exceed the best score found so far, or if the same set of sights
+/// has already been visited with the same last sight and an earlier departure time.
+/// Since the search space grows exponentially, the algorithm is restricted to instances with at
+/// most `MAX_CANDIDATES` candidate sights. It serves as a quality reference for the heuristics.
+/// The route is only guaranteed to be optimal if the search is not stopped by the deadline. The
+/// search starts from a route that visits all mandatory sights, which is returned if no better
+/// route is found before the deadline.
+pub struct BranchAndBound<'a> {
+    graph: &'a Graph,
+    /// Start time of the walk in the local time of the graphs region
+    start_time: DateTime<Tz>,
+    /// End time of the walk in the local time of the graphs region
+    end_time: DateTime<Tz>,
+    /// Walking speed in meters per second
+    walking_speed_mps: f64,
+    /// Sights with a non-zero score
+    candidates: Vec<&'a Sight>,
+    root_id: usize,
+    end_id: usize,
+    scores: ScoreMap,
+    /// Bit mask of the candidate indices of the sights that must be part of the route
+    mandatory_mask: u32,
+    /// Ids of the sights that must be part of the route
+    mandatory: HashSet<usize>,
+    distance_map: DistanceMap,
+    /// Seed reported with the computed route. The algorithm is deterministic and does not use it.
+    seed: u64,
+}
+
+impl<'a> BranchAndBound<'a> {
+    /// Unique string identifier of this algorithm implementation
+    pub const ALGORITHM_NAME: &'static str = "BranchAndBound";
+    /// Short description of this algorithm implementation
+    pub const ALGORITHM_DESCRIPTION: &'static str = "Exact branch-and-bound search that finds the \
+        route with the maximum score. Only available for at most 15 candidate sights";
+
+    /// Get the number of seconds to travel from the node with id `from` to the node with id `to`
+    fn travel_time(&self, from: usize, to: usize) -> Option<i64> {
+        if from == to {
+            return Some(0);
+        }
+        self.distance_map.dist(from, to)
+            .map(|dist| (dist as f64 / self.walking_speed_mps) as i64 + 1)
+    }
+
+    /// Compute travel, wait and service time for visiting the candidate with index `candidate`
+    /// directly after leaving the node with id `curr_node_id` after `used_time_budget` seconds.
+    /// Returns `None` if the visit violates the time budget or the sights opening hours.
+    fn visit(&self, curr_node_id: usize, used_time_budget: i64, candidate: usize) -> Option<(i64, i64, i64)> {
+        let sight = self.candidates[candidate];
+        let travel_time = self.travel_time(curr_node_id, sight.node_id)?;
+        let end_travel_time = self.travel_time(sight.node_id, self.end_id)?;
+        compute_wait_and_service_time(&self.start_time, &self.end_time, sight,
+                                      used_time_budget + travel_time, end_travel_time)
+            .map(|(wait_time, service_time)| (travel_time, wait_time, service_time))
+    }
+
+    /// Recursively extend the current partial route, which visits the candidates in `visited`
+    /// and ends at the node with id `curr_node_id` after `used_time_budget` seconds.
+    /// Stops extending routes as soon as `control` asks to stop.
+    fn search(&self, state: &mut SearchState, control: &ComputeControl, curr_node_id: usize,
+              used_time_budget: i64, visited: u32, score: usize) {
+        let improves = match state.best_score {
+            Some(best_score) => score > best_score,
+            None => true
+        };
+        if improves && visited & self.mandatory_mask == self.mandatory_mask {
+            state.best_score = Some(score);
+            state.best_solution = state.path.clone();
+            control.report_score(score);
+        }
+        if control.should_stop() {
+            return;
+        }
+
+        let extensions = (0..self.candidates.len())
+            .filter(|&candidate| visited & (1 << candidate) == 0)
+            .filter_map(|candidate| self.visit(curr_node_id, used_time_budget, candidate)
+                .map(|(travel_time, wait_time, service_time)|
+                    (candidate, used_time_budget + travel_time + wait_time + service_time)))
+            .collect_vec();
+
+        // Every sight that is added later must also be reachable directly
+        let bound = score + extensions.iter()
+            .map(|&(candidate, _)| self.scores[&self.candidates[candidate].node_id].0)
+            .sum::<usize>();
+        if let Some(best_score) = state.best_score {
+            if bound <= best_score {
+                return;
+            }
+        }
+
+        for (candidate, departure_time) in extensions {
+            let next_visited = visited | (1 << candidate);
+            if let Some(&earliest_departure) = state.departure_times.get(&(next_visited, candidate)) {
+                if earliest_departure <= departure_time {
+                    continue;
+                }
+            }
+            state.departure_times.insert((next_visited, candidate), departure_time);
+            let sight = self.candidates[candidate];
+            state.path.push(candidate);
+            self.search(state, control, sight.node_id, departure_time, next_visited,
+                        score + self.scores[&sight.node_id].0);
+            state.path.pop();
+        }
+    }
+
+    /// Finds the candidate indices of a route that visits all mandatory sights, if there is one,
+    /// see `find_mandatory_order`
+    fn mandatory_solution(&self) -> Option<Vec<usize>> {
+        let mandatory = self.candidates.iter()
+            .filter(|sight| self.mandatory.contains(&sight.node_id))
+            .copied()
+            .collect_vec();
+        let order = find_mandatory_order(&self.start_time, &self.end_time, &mandatory,
+                                         self.root_id, 0, self.end_id,
+                                         |from, to| self.travel_time(from, to))?;
+        Some(order.iter()
+            .map(|sight| self.candidates.iter()
+                .position(|candidate| candidate.node_id == sight.node_id)
+                // unwrap safety: mandatory sights are taken from the candidates
+                .unwrap())
+            .collect())
+    }
+
+    /// Build a walking route that visits the candidates with the given indices in order
+    fn build_route(&self, solution: &[usize]) -> Result<Route<'a>, AlgorithmError> {
+        let mut route = Route::new();
+        let mut used_time_budget = 0;
+        let mut curr_node_id = self.root_id;
+
+        for &candidate in solution {
+            let sight = self.candidates[candidate];
+            let (travel_time, wait_time, service_time) = self.visit(
+                curr_node_id, used_time_budget, candidate)
+                .ok_or(AlgorithmError::NoRouteFound { from: curr_node_id, to: sight.node_id })?;
+            let path = self.distance_map.path(self.graph, curr_node_id, sight.node_id);
+            let sector = Sector::new(&self.start_time, used_time_budget, travel_time, wait_time,
+                                     service_time, sight, path);
+            if route.is_empty() {
+                route.push(RouteSector::Start(sector));
+            } else {
+                route.push(RouteSector::Intermediate(sector));
+            }
+            used_time_budget += travel_time + wait_time + service_time;
+            curr_node_id = sight.node_id;
+        }
+
+        let end_travel_time = self.travel_time(curr_node_id, self.end_id)
+            .ok_or(AlgorithmError::NoRouteFound { from: curr_node_id, to: self.end_id })?;
+        let path = self.distance_map.path(self.graph, curr_node_id, self.end_id);
+        route.push(RouteSector::End(EndSector::new(
+            &self.start_time, used_time_budget, end_travel_time, path)));
+
+        Ok(route)
+    }
+}
+
+impl<'a> _Algorithm<'a> for BranchAndBound<'a> {
+    fn new(graph: &'a Graph,
+           start_time: DateTime<Utc>,
+           end_time: DateTime<Utc>,
+           walking_speed_mps: f64,
+           area: Area,
+           destination: Option<Coordinate>,
+           user_prefs: UserPreferences,
+           _params: AlgorithmParams,
+           seed: Option<u64>) -> Result<Self, AlgorithmError> where Self: Sized {
+        let SearchSpace { edge_area, sights, root_id, end_id } = SearchSpace::new(
+            graph, &start_time, &end_time, walking_speed_mps, &area, destination.as_ref(),
+            &user_prefs.exclude)?;
+
+        let mandatory = user_prefs.get_mandatory_sights(graph, &sights)?;
+        let scores = compute_scores(&sights, user_prefs);
+
+        let candidates = sights.into_iter()
+            .filter(|sight| {
+                let (score, category) = scores[&sight.node_id];
+                score > 0 && sight.category == category
+            })
+            .collect_vec();
+        if candidates.is_empty() {
+            return Err(AlgorithmError::NoPreferencesProvided);
+        }
+        if candidates.len() > MAX_CANDIDATES {
+            return Err(AlgorithmError::TooManyCandidates {
+                num_candidates: candidates.len(),
+                max_candidates: MAX_CANDIDATES,
+            });
+        }
+        let mandatory_mask = candidates.iter().enumerate()
+            .filter(|(_, sight)| mandatory.contains(&sight.node_id))
+            .fold(0, |mask, (candidate, _)| mask | (1 << candidate));
+
+        let distance_map = build_distance_map(
+            graph, &edge_area, &candidates, root_id, end_id, &scores);
+
+        Ok(Self {
+            graph,
+            start_time: start_time.with_timezone(&graph.timezone()),
+            end_time: end_time.with_timezone(&graph.timezone()),
+            walking_speed_mps,
+            candidates,
+            root_id,
+            end_id,
+            scores,
+            mandatory_mask,
+            mandatory,
+            distance_map,
+            seed: resolve_seed(seed),
+        })
+    }
+
+    fn compute_route(&self, control: &ComputeControl) -> Result<Route<'_>, AlgorithmError> {
+        log::debug!("Starting branch-and-bound search on {} candidate sights",
+            self.candidates.len());
+        let start = Instant::now();
+
+        let total_time_budget = self.end_time.signed_duration_since(self.start_time).num_seconds();
+        match self.travel_time(self.root_id, self.end_id) {
+            Some(end_travel_time) if end_travel_time <= total_time_budget => (),
+            _ => return Err(AlgorithmError::NoRouteFound { from: self.root_id, to: self.end_id })
+        }
+
+        let mut state = SearchState {
+            best_score: None,
+            best_solution: vec![],
+            path: vec![],
+            departure_times: HashMap::new(),
+        };
+        // Start from a route that visits all mandatory sights, such that a feasible route is
+        // known even if the search is stopped early
+        if let Some(solution) = self.mandatory_solution() {
+            state.best_score = Some(solution.iter()
+                .map(|&candidate| self.scores[&self.candidates[candidate].node_id].0)
+                .sum());
+            state.best_solution = solution;
+        }
+        self.search(&mut state, control, self.root_id, 0, 0, 0);
+
+        let best_score = match (state.best_score, control.termination_reason()) {
+            (Some(best_score), _) => best_score,
+            (None, TerminationReason::Converged) => {
+                let mandatory = self.mandatory.iter().copied().sorted().collect_vec();
+                return Err(AlgorithmError::mandatory_sights_not_feasible(self.graph, &mandatory));
+            }
+            (None, reason) => return Err(AlgorithmError::StoppedWithoutFeasibleRoute { reason }),
+        };
+        let route = self.build_route(&state.best_solution)?;
+        log::debug!("Finished branch-and-bound search in {} ms after exploring {} states. Computed walking route from node: {} including {} sights with total score: {}.",
+            start.elapsed().as_millis(), state.departure_times.len(), self.root_id,
+            route.len() - 1, best_score);
+
+        Ok(route)
+    }
+
+    fn get_collected_score(&self, route: &Route) -> usize {
+        route.iter()
+            .map(|route_sec| {
+                match route_sec {
+                    // Start and intermediate sectors contain a sight per definition
+                    RouteSector::Start(sector) => self.scores[&sector.sight.node_id].0,
+                    RouteSector::Intermediate(sector) => self.scores[&sector.sight.node_id].0,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    fn get_seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Duration, Utc};
+    use opening_hours_syntax::rules::RuleKind;
+    use crate::algorithm::{_Algorithm, Algorithm, AlgorithmParams, ComputeControl, Area, Coordinate, RouteSector, SightCategoryPref, SightPref, TerminationReason, UserPreferences};
+    use crate::algorithm::branch_and_bound::{BranchAndBound, MAX_CANDIDATES};
+    use crate::algorithm::test::{SEED, START_TIME, WALKING_SPEED_MPS};
+    use crate::data::graph::{Category, Graph, SightExclusions};
+    use crate::init_logging;
+    use crate::utils::test_setup;
+
+    /// Area around the Bremen market square with only a few nightlife sights
+    const MARKET_SQUARE: Area = Area {
+        lat: 53.075833,
+        lon: 8.807222,
+        radius: 250.0,
+    };
+
+    /// Bremen cathedral next to the market square
+    const BREMEN_CATHEDRAL: Coordinate = Coordinate {
+        lat: 53.074900,
+        lon: 8.809200,
+    };
+
+    /// End time of a walk that is too short to visit all sights around the market square
+    const END_TIME: &str = "2022-07-01T16:00:00+01:00";
+
+    /// Create an instance around the market square preferring nightlife. Since branch and bound
+    /// exceeds its candidate limit in the area of the generic algorithm tests, this module checks
+    /// the same properties for it here.
+    fn create_bnb(graph: &'static Graph, destination: Option<Coordinate>, sights: Vec<SightPref>)
+        -> BranchAndBound<'static> {
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        BranchAndBound::new(
+            graph, start_time, end_time, WALKING_SPEED_MPS, MARKET_SQUARE, destination,
+            UserPreferences {
+                categories: vec![SightCategoryPref { category: Category::Nightlife, pref: 5 }],
+                sights,
+                exclude: SightExclusions::default(),
+            }, AlgorithmParams::default(), Some(SEED)).unwrap()
+    }
+
+    #[test]
+    fn test_heuristics_do_not_exceed_exact_score() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let user_prefs = UserPreferences {
+            categories: vec![SightCategoryPref { category: Category::Nightlife, pref: 5 }],
+            sights: vec![],
+            exclude: SightExclusions::default(),
+        };
+        let exact = BranchAndBound::new(
+            &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS, MARKET_SQUARE, None,
+            user_prefs.clone(), AlgorithmParams::default(), Some(SEED)).unwrap();
+        assert!(exact.candidates.len() <= MAX_CANDIDATES);
+        let exact_route = exact.compute_route(&ComputeControl::default()).expect("Error during route computation");
+        let exact_score = exact.get_collected_score(&exact_route);
+        let route_end_time = match exact_route.last().unwrap() {
+            RouteSector::End(end_sector) => end_sector.time_of_arrival,
+            _ => panic!("Last sector must be end sector")
+        };
+        assert!(route_end_time <= end_time, "Route travel time exceeds available budget");
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let algo = Algorithm::from_name(
+                algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+                MARKET_SQUARE, None, user_prefs.clone(), AlgorithmParams::default(), Some(SEED))
+                .unwrap();
+            let route = algo.compute_route(&ComputeControl::default()).expect("Error during route computation");
+            let score = exact.get_collected_score(&route);
+            log::info!("{} collected score {} of {}", algo_name, score, exact_score);
+            assert!(score <= exact_score, "{} route exceeds score {} of exact route", algo_name,
+                    exact_score);
+        }
+    }
+
+    #[test]
+    fn test_mandatory_sight_visited_if_deadline_reached() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let category_prefs = vec![SightCategoryPref { category: Category::Nightlife, pref: 5 }];
+        let candidates = BranchAndBound::new(
+            &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS, MARKET_SQUARE, None,
+            UserPreferences {
+                categories: category_prefs.clone(),
+                sights: vec![],
+                exclude: SightExclusions::default(),
+            }, AlgorithmParams::default(), Some(SEED)).unwrap().candidates;
+        let mandatory_id = candidates.last().expect("No candidates around the market square").node_id;
+
+        let bnb = BranchAndBound::new(
+            &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS, MARKET_SQUARE, None,
+            UserPreferences {
+                categories: category_prefs,
+                sights: vec![SightPref::new_must_visit(mandatory_id)],
+                exclude: SightExclusions::default(),
+            }, AlgorithmParams::default(), Some(SEED)).unwrap();
+        let control = ComputeControl::with_max_compute_ms(0);
+        let route = bnb.compute_route(&control).expect("Error during route computation");
+
+        assert_eq!(TerminationReason::DeadlineReached, control.termination_reason());
+        assert!(route.iter()
+                    .filter_map(|sector| sector.sight_sector())
+                    .any(|sector| sector.sight().node_id == mandatory_id),
+                "Mandatory sight {} is not part of the route", mandatory_id);
+    }
+
+    #[test]
+    fn test_route_sights_with_category_pref_and_open() {
+        init_logging();
+
+        let bnb = create_bnb(&test_setup::GRAPH, None, vec![]);
+        let route = bnb.compute_route(&ComputeControl::default()).expect("Error during route computation");
+        let sectors = route.iter().filter_map(|route_sector| route_sector.sight_sector()).collect::<Vec<_>>();
+        assert!(!sectors.is_empty(), "Route visits no sights");
+        for sector in sectors {
+            assert_eq!(sector.sight.category, Category::Nightlife,
+                       "Route contains sight {} which is not in user preferences", sector.sight.node_id);
+            let opening_hours = sector.sight.opening_hours();
+            let state_at_start = opening_hours.state(sector.service_start_time.naive_local()).unwrap();
+            let state_at_end = opening_hours.state(
+                sector.service_end_time.naive_local() - Duration::seconds(1)).unwrap();
+            assert!(matches!(state_at_start, RuleKind::Open | RuleKind::Unknown)
+                        && matches!(state_at_end, RuleKind::Open | RuleKind::Unknown),
+                    "Sight {} is not open during its service time", sector.sight.node_id);
+        }
+    }
+
+    #[test]
+    fn test_route_ends_at_destination() {
+        init_logging();
+
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_id = test_setup::GRAPH.get_nearest_node(BREMEN_CATHEDRAL.lat, BREMEN_CATHEDRAL.lon);
+        let bnb = create_bnb(&test_setup::GRAPH, Some(BREMEN_CATHEDRAL), vec![]);
+        let route = bnb.compute_route(&ComputeControl::default()).expect("Error during route computation");
+        let end_sector = match route.last().unwrap() {
+            RouteSector::End(end_sector) => end_sector,
+            _ => panic!("Last sector must be end sector")
+        };
+        assert_eq!(end_sector.nodes.last().expect("End sector without nodes").id, end_id,
+                   "Route does not end at destination");
+        assert!(end_sector.time_of_arrival <= end_time, "Route arrives at destination after end time");
+    }
+
+    #[test]
+    fn test_route_with_contraction_hierarchy() {
+        init_logging();
+
+        let graph = &test_setup::CONTRACTED_GRAPH;
+        assert!(graph.contraction_hierarchy().is_some(), "Test graph has no contraction hierarchy");
+        let root_id = graph.get_nearest_node(MARKET_SQUARE.lat, MARKET_SQUARE.lon);
+        let bnb = create_bnb(graph, None, vec![]);
+        let route = bnb.compute_route(&ComputeControl::default()).expect("Error during route computation");
+        // the sectors form a closed walk along edges of the original graph
+        let mut curr_node_id = root_id;
+        for route_sector in &route {
+            let nodes = route_sector.nodes();
+            assert_eq!(nodes.first().unwrap().id, curr_node_id,
+                       "Route sector does not start where the previous one ends");
+            for pair in nodes.windows(2) {
+                assert!(test_setup::GRAPH.get_edge(pair[0].id, pair[1].id).is_some(),
+                        "Route uses missing edge from {} to {}", pair[0].id, pair[1].id);
+            }
+            curr_node_id = nodes.last().unwrap().id;
+        }
+        assert_eq!(curr_node_id, root_id, "Route does not return to the start");
+    }
+
+    #[test]
+    fn test_route_contains_mandatory_sight() {
+        init_logging();
+
+        // Pick a nearby sight whose category the user has no preference for
+        let mandatory_id = test_setup::GRAPH.get_reachable_sights_in_area(
+            MARKET_SQUARE.lat, MARKET_SQUARE.lon, MARKET_SQUARE.radius, MARKET_SQUARE.radius * 2.0,
+            &SightExclusions::default())
+            .into_iter()
+            .find(|sight| sight.category != Category::Nightlife)
+            .expect("No sight without category preference found")
+            .node_id;
+        let bnb = create_bnb(&test_setup::GRAPH, None, vec![SightPref::new_must_visit(mandatory_id)]);
+        let route = bnb.compute_route(&ComputeControl::default()).expect("Error during route computation");
+        assert!(route.iter()
+                    .filter_map(|sector| sector.sight_sector())
+                    .any(|sector| sector.sight().node_id == mandatory_id),
+                "Mandatory sight {} is not part of the route", mandatory_id);
+    }
+}
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use crate::data::graph::{Graph, Sight};
 use itertools::Itertools;
-use crate::algorithm::{_Algorithm, AlgorithmError, Area, Route, RouteSector, ScoreMap, Sector, UserPreferences, USER_PREF_MAX, compute_wait_and_service_time, EndSector};
+use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, ComputeControl, Progress, resolve_seed, Area, Route, RouteSector, ScoreMap, Sector, UserPreferences, USER_PREF_MAX, compute_wait_and_service_time, EndSector, Coordinate, SearchSpace, find_mandatory_order};
 use crate::utils::dijkstra;
+use crate::utils::dijkstra::DijkstraResult;
 
 /// Greedy internal user preference to score mapping
 const USER_PREF_TO_SCORE: [usize; USER_PREF_MAX + 1] = [0, 1, 2, 4, 8, 16];
@@ -53,19 +55,67 @@ fn compute_scores(sights: &Vec<&Sight>, user_prefs: UserPreferences) -> ScoreMap
 /// their score-distance ratio at that time until the time budget is used up.
 pub struct GreedyAlgorithm<'a> {
     graph: &'a Graph,
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    /// Start time of the walk in the local time of the graphs region
+    start_time: DateTime<Tz>,
+    /// End time of the walk in the local time of the graphs region
+    end_time: DateTime<Tz>,
     /// Walking speed in meters per second
     walking_speed_mps: f64,
-    area: Area,
+    /// Area in which the edges of the walking route must lie
+    edge_area: Area,
     sights: Vec<&'a Sight>,
     root_id: usize,
+    end_id: usize,
     scores: ScoreMap,
+    /// Ids of the sights that must be part of the route
+    mandatory: HashSet<usize>,
+    /// Seed reported with the computed route. The algorithm is deterministic and does not use it.
+    seed: u64,
 }
 
-impl GreedyAlgorithm<'_> {
+impl<'a> GreedyAlgorithm<'a> {
     /// Unique string identifier of this algorithm implementation
     pub const ALGORITHM_NAME: &'static str = "Greedy";
+    /// Short description of this algorithm implementation
+    pub const ALGORITHM_DESCRIPTION: &'static str = "Fast greedy search that repeatedly appends \
+        the sight with the best score-distance ratio until the time budget is used up";
+
+    /// Checks whether the `unvisited` mandatory sights can still be visited after the route
+    /// reached the node with id `curr_node_id` with `used_time_budget` seconds spent.
+    /// All visiting orders of the mandatory sights are considered, see `find_mandatory_order`.
+    ///
+    /// # Arguments
+    /// * `curr_node_id` - The id of the node where the route currently ends
+    /// * `used_time_budget` - The number of seconds passed since the start of the trip
+    /// * `unvisited` - The ids of the mandatory sights that have not been visited yet
+    /// * `results_from_mandatory` - The results of one-to-all Dijkstra runs from all mandatory sights
+    /// * `result_from_end` - The result of a one-to-all Dijkstra run from the end node
+    ///
+    /// # Returns
+    /// * an `Ok` containing an order in which all unvisited mandatory sights can be visited, or
+    /// * an `Err` containing the ids of the mandatory sights that cannot be visited, otherwise
+    fn check_mandatory_sights(&self, curr_node_id: usize, used_time_budget: i64,
+                              unvisited: &HashSet<usize>,
+                              results_from_mandatory: &HashMap<usize, DijkstraResult>,
+                              result_from_end: &DijkstraResult) -> Result<Vec<&'a Sight>, Vec<usize>> {
+        let mandatory = self.sights.iter()
+            .filter(|sight| unvisited.contains(&sight.node_id))
+            .copied()
+            .collect_vec();
+        // Works because graph is undirected
+        let travel_time = |from_id: usize, to_id: usize| {
+            let result = if to_id == self.end_id {
+                result_from_end
+            } else {
+                &results_from_mandatory[&to_id]
+            };
+            result.dist_to(from_id)
+                .map(|dist| (dist as f64 / self.walking_speed_mps) as i64 + 1)
+        };
+        find_mandatory_order(&self.start_time, &self.end_time, &mandatory, curr_node_id,
+                             used_time_budget, self.end_id, travel_time)
+            .ok_or_else(|| unvisited.iter().copied().sorted().collect())
+    }
 }
 
 impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
@@ -74,46 +124,37 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
            end_time: DateTime<Utc>,
            walking_speed_mps: f64,
            area: Area,
-           user_prefs: UserPreferences) -> Result<Self, AlgorithmError> {
-        if end_time < start_time {
-            return Err(AlgorithmError::NegativeTimeInterval);
-        }
-
-        let time_budget = end_time.signed_duration_since(start_time).num_seconds() as f64;
-        let edge_radius = walking_speed_mps * time_budget / std::f64::consts::PI / 2.0;
-        let sights_radius = edge_radius.min(area.radius);
-        let sights = graph.get_reachable_sights_in_area(area.lat, area.lon,
-                                                        sights_radius, edge_radius);
-        if sights.is_empty() {
-            return Err(AlgorithmError::NoSightsFound)
-        }
-
-        let root_id = match graph.get_nearest_node_in_area(area.lat, area.lon, sights_radius) {
-            Some(nearest_node) => nearest_node,
-            None => return Err(AlgorithmError::NoNearestNodeFound)
-        };
+           destination: Option<Coordinate>,
+           user_prefs: UserPreferences,
+           _params: AlgorithmParams,
+           seed: Option<u64>) -> Result<Self, AlgorithmError> {
+        let SearchSpace { edge_area, sights, root_id, end_id } = SearchSpace::new(
+            graph, &start_time, &end_time, walking_speed_mps, &area, destination.as_ref(),
+            &user_prefs.exclude)?;
 
+        let mandatory = user_prefs.get_mandatory_sights(graph, &sights)?;
         let scores = compute_scores(&sights, user_prefs);
 
         Ok(Self {
             graph,
-            start_time,
-            end_time,
+            start_time: start_time.with_timezone(&graph.timezone()),
+            end_time: end_time.with_timezone(&graph.timezone()),
             walking_speed_mps,
-            area,
+            edge_area,
             sights,
             root_id,
+            end_id,
             scores,
+            mandatory,
+            seed: resolve_seed(seed),
         })
     }
 
-     fn compute_route(&self) -> Result<Route, AlgorithmError> {
+     fn compute_route(&self, control: &ComputeControl) -> Result<Route, AlgorithmError> {
          let total_time_budget = self.end_time.signed_duration_since(self.start_time)
              .num_seconds();
          let mut time_budget_left = total_time_budget;
 
-         let edge_radius = self.walking_speed_mps * time_budget_left as f64 / std::f64::consts::PI / 2.0;
-
          log::debug!("Starting greedy search");
 
          let mut route: Route = vec![];
@@ -121,7 +162,7 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
          let mut unvisited_sights: HashMap<_, _> = self.sights.iter()
              .filter(|&sight| {
                  let (score, category) = self.scores[&sight.node_id];
-                 score > 0 && sight.category == category
+                 (score > 0 && sight.category == category) || self.mandatory.contains(&sight.node_id)
              })
              .map(|&sight| (sight.node_id, sight))
              .collect();
@@ -130,15 +171,44 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
          }
          let mut curr_node_id = self.root_id;
          let result_from_root = Rc::new(dijkstra::run_ota_dijkstra_in_area(
-             self.graph, curr_node_id, self.area.lat, self.area.lon, edge_radius));
+             self.graph, curr_node_id, self.edge_area.lat, self.edge_area.lon, self.edge_area.radius));
+         let result_from_end = if self.end_id == self.root_id {
+             result_from_root.clone()
+         } else {
+             Rc::new(dijkstra::run_ota_dijkstra_in_area(
+                 self.graph, self.end_id, self.edge_area.lat, self.edge_area.lon, self.edge_area.radius))
+         };
+         if result_from_end.dist_to(self.root_id).is_none() {
+             return Err(AlgorithmError::NoRouteFound { from: self.root_id, to: self.end_id });
+         }
+
+         // Make sure that all mandatory sights fit into the route before adding any other sight
+         let results_from_mandatory: HashMap<_, _> = self.mandatory.iter()
+             .map(|&sight_id| (sight_id, dijkstra::run_ota_dijkstra_in_area(
+                 self.graph, sight_id, self.edge_area.lat, self.edge_area.lon, self.edge_area.radius)))
+             .collect();
+         let mut unvisited_mandatory = self.mandatory.clone();
+         if let Err(infeasible) = self.check_mandatory_sights(
+             self.root_id, 0, &unvisited_mandatory, &results_from_mandatory, &result_from_end) {
+             return Err(AlgorithmError::mandatory_sights_not_feasible(self.graph, &infeasible));
+         }
+
+         let mut collected_score = 0;
          let mut result_to_sights;
          loop {
+             // Only complete the route with the remaining mandatory sights once the deadline has
+             // been reached
+             if control.should_stop() && unvisited_sights.len() > unvisited_mandatory.len() {
+                 log::debug!("Reached deadline, completing route with mandatory sights");
+                 unvisited_sights.retain(|sight_id, _| unvisited_mandatory.contains(sight_id));
+             }
+
              // calculate distances from curr_node to all sight nodes
              if curr_node_id == self.root_id {
                  result_to_sights = result_from_root.clone();
              } else {
                  result_to_sights = Rc::new(dijkstra::run_ota_dijkstra_in_area(
-                     self.graph, curr_node_id, self.area.lat, self.area.lon, edge_radius));
+                     self.graph, curr_node_id, self.edge_area.lat, self.edge_area.lon, self.edge_area.radius));
              }
 
              // sort sight nodes by a metric derived from the sights score and its distance to
@@ -162,18 +232,28 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
                  let sight_travel_time = (dist as f64 / self.walking_speed_mps) as i64 + 1;
 
                  // Works because graph is undirected
-                 match result_from_root.dist_to(sight.node_id) {
-                     Some(dist_to_root) => {
-                         let root_travel_time = (dist_to_root as f64 / self.walking_speed_mps) as i64 + 1;
+                 match result_from_end.dist_to(sight.node_id) {
+                     Some(dist_to_end) => {
+                         let end_travel_time = (dist_to_end as f64 / self.walking_speed_mps) as i64 + 1;
 
                          let used_time_budget = total_time_budget - time_budget_left + sight_travel_time;
                          match compute_wait_and_service_time(
-                             &self.start_time, &self.end_time, sight, used_time_budget, root_travel_time) {
+                             &self.start_time, &self.end_time, sight, used_time_budget, end_travel_time) {
                              Some((wait_time, service_time)) => {
                                  let sight_total_time = sight_travel_time + wait_time + service_time;
 
+                                 // skip sight if the remaining mandatory sights would not fit
+                                 // into the route anymore
+                                 let mut remaining_mandatory = unvisited_mandatory.clone();
+                                 remaining_mandatory.remove(&sight.node_id);
+                                 if self.check_mandatory_sights(
+                                     sight.node_id, total_time_budget - time_budget_left + sight_total_time,
+                                     &remaining_mandatory, &results_from_mandatory, &result_from_end).is_err() {
+                                     continue;
+                                 }
+
                                  log::trace!("Appending sight {} (secs to include sight: {} <= left time budget: {}) with score: {}",
-                                     sight.node_id, sight_total_time + root_travel_time, time_budget_left, self.scores[&sight.node_id].0);
+                                     sight.node_id, sight_total_time + end_travel_time, time_budget_left, self.scores[&sight.node_id].0);
 
                                  // add sector containing sight and all intermediate nodes to route
                                  let path = result_to_sights.build_path(self.graph,
@@ -189,33 +269,46 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
 
                                  time_budget_left -= sight_total_time;
                                  unvisited_sights.remove(&sight.node_id);
+                                 unvisited_mandatory.remove(&sight.node_id);
                                  curr_node_id = sight.node_id;
+                                 collected_score += self.scores[&sight.node_id].0;
+                                 control.report_progress(&Progress {
+                                     temperature: None,
+                                     iteration: route.len(),
+                                     best_score: collected_score,
+                                     route: Some(&route),
+                                 });
                                  break;
                              },
                              None => ()
                          };
                      }
-                     None => () // No path from sight to root found
+                     None => () // No path from sight to end found
                  };
              }
 
-             // check whether any sight has been included in route and if not, go back to root
+             // check whether any sight has been included in route and if not, go to the end node
              let len_route_after = route.len();
-             if len_route_after == len_route_before && len_route_after > 0 {
-                 log::trace!("Traveling back to root");
+             if len_route_after == len_route_before {
+                 if !unvisited_mandatory.is_empty() {
+                     let infeasible = unvisited_mandatory.into_iter().collect_vec();
+                     return Err(AlgorithmError::mandatory_sights_not_feasible(self.graph, &infeasible));
+                 }
+
+                 log::trace!("Traveling to end node");
 
-                 // Path from sight to root must exist because otherwise, we would have skipped sight
+                 // Path from sight to end must exist because otherwise, we would have skipped sight
                  // Works because graph is undirected
-                 let result_to_root = result_from_root.result_of(
+                 let result_to_end = result_from_end.result_of(
                      self.graph, curr_node_id).unwrap();
 
-                 let secs_to_root = (result_to_root.dist() as f64 / self.walking_speed_mps) as i64;
-                 let mut path = result_to_root.consume_path();
+                 let secs_to_end = (result_to_end.dist() as f64 / self.walking_speed_mps) as i64;
+                 let mut path = result_to_end.consume_path();
                  // Reverse because path is in reverse direction
                  path.reverse();
                  route.push(RouteSector::End(EndSector::new(
                      &self.start_time, total_time_budget - time_budget_left,
-                     secs_to_root, path)));
+                     secs_to_end, path)));
                  break;
              }
          }
@@ -239,4 +332,8 @@ impl<'a> _Algorithm<'a> for GreedyAlgorithm<'a> {
             })
             .sum()
     }
+
+    fn get_seed(&self) -> u64 {
+        self.seed
+    }
 }
\ No newline at end of file
@@ -0,0 +1,533 @@
+use std::collections::HashSet;
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, ComputeControl, Progress, resolve_seed, Area, check_param_range, compute_wait_and_service_time, Coordinate, EndSector, find_mandatory_order, Route, RouteSector, ScoreMap, SearchSpace, Sector, UserPreferences};
+use crate::algorithm::sa_lin_yu::{build_distance_map, DistanceMap, compute_scores, select_best_sights};
+use crate::data::graph::{Graph, Sight};
+
+/// Parameters of the iterated local search. Missing fields are set to their default values when
+/// deserialized.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct IlsParams {
+    /// Number of consecutive iterations without improvement after which the search stops
+    pub max_non_improving: usize,
+    /// Maximum allowed computation time in milliseconds
+    pub max_time_ms: u64,
+}
+
+impl Default for IlsParams {
+    fn default() -> Self {
+        Self {
+            max_non_improving: 150,
+            max_time_ms: 10_000,
+        }
+    }
+}
+
+impl IlsParams {
+    /// Checks that all parameters lie within their safe ranges
+    ///
+    /// # Returns
+    /// * an empty `Ok`, if all parameters are valid, or
+    /// * an `Err` containing `AlgorithmError::InvalidParameter` for the first invalid parameter,
+    ///   otherwise
+    pub fn validate(&self) -> Result<(), AlgorithmError> {
+        check_param_range("max_non_improving", self.max_non_improving as f64, 1., 10_000.)?;
+        check_param_range("max_time_ms", self.max_time_ms as f64, 1., 120_000.)
+    }
+
+    /// Returns a copy of these parameters in which every parameter that is set in `overrides`
+    /// is replaced by its overriding value
+    pub fn with_overrides(&self, overrides: &IlsParamsOverrides) -> Self {
+        Self {
+            max_non_improving: overrides.max_non_improving.unwrap_or(self.max_non_improving),
+            max_time_ms: overrides.max_time_ms.unwrap_or(self.max_time_ms),
+        }
+    }
+}
+
+/// Overrides for the parameters of the iterated local search, see `IlsParams`.
+/// Parameters that are not set keep their configured value.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct IlsParamsOverrides {
+    pub max_non_improving: Option<usize>,
+    pub max_time_ms: Option<u64>,
+}
+
+/// A scheduled visit of a sight
+///
+/// # Fields
+/// * `sight` - The visited sight
+/// * `departure_time` - The number of seconds passed since the start of the trip when leaving
+///   the previous node
+/// * `travel_time` - The number of seconds to travel from the previous node to the sight
+/// * `wait_time` - The number of seconds to wait until the sight opens
+/// * `service_time` - The number of seconds to spend at the sight
+struct Visit<'a> {
+    sight: &'a Sight,
+    departure_time: i64,
+    travel_time: i64,
+    wait_time: i64,
+    service_time: i64,
+}
+
+/// Feasible schedule of a solution, i.e., of a sequence of sights
+///
+/// # Fields
+/// * `visits` - The scheduled visits in the order of the solution
+/// * `end_departure_time` - The number of seconds passed since the start of the trip when leaving
+///   the last sight
+/// * `end_travel_time` - The number of seconds to travel from the last sight to the end node
+struct Schedule<'a> {
+    visits: Vec<Visit<'a>>,
+    end_departure_time: i64,
+    end_travel_time: i64,
+}
+
+impl Schedule<'_> {
+    /// The total number of seconds needed for the trip
+    fn duration(&self) -> i64 {
+        self.end_departure_time + self.end_travel_time
+    }
+}
+
+/// Implementation of the `Algorithm` trait based on the Iterated Local Search of Vansteenwegen
+/// et al. (2009) for the team orienteering problem with time windows.
+///
+/// The algorithm alternates between an insertion step, which greedily inserts the sight with the
+/// best ratio of squared score and added travel, wait and service time at its best position, and a
+/// shake step, which removes a sequence of consecutive sights from the route to escape from local
+/// optima. The shake step starts at a random position drawn from a generator seeded with `seed`.
+/// It stops after `max_non_improving` iterations without improvement, after `max_time_ms`
+/// milliseconds or when the deadline of the computation is reached. All parameters are given by
+/// `IlsParams`.
+pub struct IteratedLocalSearch<'a> {
+    graph: &'a Graph,
+    /// Start time of the walk in the local time of the graphs region
+    start_time: DateTime<Tz>,
+    /// End time of the walk in the local time of the graphs region
+    end_time: DateTime<Tz>,
+    /// Walking speed in meters per second
+    walking_speed_mps: f64,
+    sights: Vec<&'a Sight>,
+    root_id: usize,
+    end_id: usize,
+    scores: ScoreMap,
+    /// Ids of the sights that must be part of the route
+    mandatory: HashSet<usize>,
+    distance_map: DistanceMap,
+    params: IlsParams,
+    /// Seed of the random number generator
+    seed: u64,
+}
+
+impl<'a> IteratedLocalSearch<'a> {
+    /// Unique string identifier of this algorithm implementation
+    pub const ALGORITHM_NAME: &'static str = "IteratedLocalSearch";
+    /// Short description of this algorithm implementation
+    pub const ALGORITHM_DESCRIPTION: &'static str = "Iterated local search after Vansteenwegen \
+        et al. (2009) that alternates between inserting the sights with the best score-time ratio \
+        and removing consecutive sights from the route to escape from local optima";
+
+    /// Get the number of seconds to travel from the node with id `from` to the node with id `to`
+    fn travel_time(&self, from: usize, to: usize) -> Option<i64> {
+        if from == to {
+            return Some(0);
+        }
+        self.distance_map.dist(from, to)
+            .map(|dist| (dist as f64 / self.walking_speed_mps) as i64 + 1)
+    }
+
+    /// Schedule the visits of all sights in `solution` in the given order.
+    /// Returns `None` if the schedule violates the time budget or opening hours of any sight.
+    fn schedule(&self, solution: &[&'a Sight]) -> Option<Schedule<'a>> {
+        let total_time_budget = self.end_time.signed_duration_since(self.start_time).num_seconds();
+        let mut used_time_budget = 0;
+        let mut curr_node_id = self.root_id;
+        let mut visits = Vec::with_capacity(solution.len());
+
+        for &sight in solution {
+            let travel_time = self.travel_time(curr_node_id, sight.node_id)?;
+            let end_travel_time = self.travel_time(sight.node_id, self.end_id)?;
+            let (wait_time, service_time) = compute_wait_and_service_time(
+                &self.start_time, &self.end_time, sight, used_time_budget + travel_time,
+                end_travel_time)?;
+            visits.push(Visit {
+                sight,
+                departure_time: used_time_budget,
+                travel_time,
+                wait_time,
+                service_time,
+            });
+            used_time_budget += travel_time + wait_time + service_time;
+            curr_node_id = sight.node_id;
+        }
+
+        let end_travel_time = self.travel_time(curr_node_id, self.end_id)?;
+        if used_time_budget + end_travel_time > total_time_budget {
+            return None;
+        }
+        Some(Schedule {
+            visits,
+            end_departure_time: used_time_budget,
+            end_travel_time,
+        })
+    }
+
+    /// Get the total score of all sights in `solution`
+    fn get_total_score(&self, solution: &[&'a Sight]) -> usize {
+        solution.iter()
+            .map(|sight| self.scores[&sight.node_id].0)
+            .sum()
+    }
+
+    /// Repeatedly insert the sight with the highest ratio of squared score and added time at its
+    /// best position into `solution` until no more sight can be inserted without violating the
+    /// time budget or opening hours.
+    fn insert(&self, solution: &mut Vec<&'a Sight>) {
+        let mut visited: HashSet<usize> = solution.iter().map(|sight| sight.node_id).collect();
+        loop {
+            let curr_duration = match self.schedule(solution) {
+                Some(schedule) => schedule.duration(),
+                None => return
+            };
+
+            let mut best_insert: Option<(f64, usize, &'a Sight)> = None;
+            for &sight in &self.sights {
+                let (score, category) = self.scores[&sight.node_id];
+                if score == 0 || sight.category != category || visited.contains(&sight.node_id) {
+                    continue;
+                }
+                for pos in 0..=solution.len() {
+                    solution.insert(pos, sight);
+                    if let Some(schedule) = self.schedule(solution) {
+                        let added_time = (schedule.duration() - curr_duration).max(1);
+                        let ratio = (score * score) as f64 / added_time as f64;
+                        let is_better = match best_insert {
+                            Some((best_ratio, _, _)) => ratio > best_ratio,
+                            None => true
+                        };
+                        if is_better {
+                            best_insert = Some((ratio, pos, sight));
+                        }
+                    }
+                    solution.remove(pos);
+                }
+            }
+
+            match best_insert {
+                Some((_, pos, sight)) => {
+                    log::trace!("Inserting sight {} at position {}", sight.node_id, pos);
+                    solution.insert(pos, sight);
+                    visited.insert(sight.node_id);
+                }
+                None => return
+            }
+        }
+    }
+
+    /// Remove `num_removals` consecutive sights starting at position `start_pos` from `solution`.
+    /// Mandatory sights are never removed.
+    fn shake(&self, solution: &mut Vec<&'a Sight>, start_pos: usize, num_removals: usize) {
+        let mut pos = 0;
+        solution.retain(|sight| {
+            let remove = pos >= start_pos && pos < start_pos + num_removals
+                && !self.mandatory.contains(&sight.node_id);
+            pos += 1;
+            !remove
+        });
+    }
+
+    /// Reports the progress of the search after `iteration` iterations with the best solution
+    /// found so far, which collects `best_score`
+    fn report_progress(&self, control: &ComputeControl, iteration: usize, best_score: usize,
+                       best_solution: &[&'a Sight]) {
+        // intermediate routes are only built if anybody listens
+        let route = if control.wants_progress() {
+            self.schedule(best_solution).map(|schedule| self.build_route(schedule))
+        } else {
+            None
+        };
+        control.report_progress(&Progress {
+            temperature: None,
+            iteration,
+            best_score,
+            route: route.as_deref(),
+        });
+    }
+
+    /// Build a walking route from the schedule of the best solution found
+    fn build_route(&self, schedule: Schedule<'a>) -> Route<'a> {
+        let mut route = Route::new();
+        let mut curr_node_id = self.root_id;
+
+        for visit in schedule.visits {
+            let path = self.distance_map.path(self.graph, curr_node_id, visit.sight.node_id);
+            let sector = Sector::new(
+                &self.start_time, visit.departure_time, visit.travel_time, visit.wait_time,
+                visit.service_time, visit.sight, path);
+            if route.is_empty() {
+                route.push(RouteSector::Start(sector));
+            } else {
+                route.push(RouteSector::Intermediate(sector));
+            }
+            curr_node_id = visit.sight.node_id;
+        }
+
+        let path = self.distance_map.path(self.graph, curr_node_id, self.end_id);
+        route.push(RouteSector::End(EndSector::new(
+            &self.start_time, schedule.end_departure_time, schedule.end_travel_time, path)));
+
+        route
+    }
+}
+
+impl<'a> _Algorithm<'a> for IteratedLocalSearch<'a> {
+    fn new(graph: &'a Graph,
+           start_time: DateTime<Utc>,
+           end_time: DateTime<Utc>,
+           walking_speed_mps: f64,
+           area: Area,
+           destination: Option<Coordinate>,
+           user_prefs: UserPreferences,
+           params: AlgorithmParams,
+           seed: Option<u64>) -> Result<Self, AlgorithmError> where Self: Sized {
+        let SearchSpace { edge_area, mut sights, root_id, end_id } = SearchSpace::new(
+            graph, &start_time, &end_time, walking_speed_mps, &area, destination.as_ref(),
+            &user_prefs.exclude)?;
+
+        let mandatory = user_prefs.get_mandatory_sights(graph, &sights)?;
+        let scores = compute_scores(&sights, user_prefs);
+        select_best_sights(graph, &edge_area, &mut sights, root_id, &scores, &mandatory,
+                           &params.sim_annealing);
+
+        let distance_map = build_distance_map(
+            graph, &edge_area, &sights, root_id, end_id, &scores);
+
+        Ok(Self {
+            graph,
+            start_time: start_time.with_timezone(&graph.timezone()),
+            end_time: end_time.with_timezone(&graph.timezone()),
+            walking_speed_mps,
+            sights,
+            root_id,
+            end_id,
+            scores,
+            mandatory,
+            distance_map,
+            params: params.ils,
+            seed: resolve_seed(seed),
+        })
+    }
+
+    fn compute_route(&self, control: &ComputeControl) -> Result<Route<'_>, AlgorithmError> {
+        log::debug!("Starting iterated local search (seed: {}, {:?})", self.seed, self.params);
+        let start = Instant::now();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let num_candidates = self.sights.iter()
+            .filter(|sight| {
+                let (score, category) = self.scores[&sight.node_id];
+                score > 0 && sight.category == category
+            })
+            .count();
+        if num_candidates == 0 {
+            return Err(AlgorithmError::NoPreferencesProvided);
+        }
+        if self.schedule(&[]).is_none() {
+            return Err(AlgorithmError::NoRouteFound { from: self.root_id, to: self.end_id });
+        }
+
+        // Start from an order in which all mandatory sights can be visited
+        let mandatory = self.sights.iter()
+            .filter(|sight| self.mandatory.contains(&sight.node_id))
+            .copied()
+            .collect_vec();
+        let mut solution = find_mandatory_order(
+            &self.start_time, &self.end_time, &mandatory, self.root_id, 0, self.end_id,
+            |from, to| self.travel_time(from, to))
+            .ok_or_else(|| {
+                let infeasible = self.mandatory.iter().copied().sorted().collect_vec();
+                AlgorithmError::mandatory_sights_not_feasible(self.graph, &infeasible)
+            })?;
+        self.insert(&mut solution);
+
+        let mut best_solution = solution.clone();
+        let mut best_score = self.get_total_score(&best_solution);
+        log::debug!("Score of initial solution: {}", best_score);
+        self.report_progress(control, 0, best_score, &best_solution);
+
+        let max_num_removals = (num_candidates / 3).max(1);
+        let mut start_pos = rng.gen_range(0..solution.len().max(1));
+        let mut num_removals = 1;
+        let mut non_improving_count = 0;
+        let mut iteration = 0;
+        while non_improving_count < self.params.max_non_improving {
+            if control.time_limit_reached(start, self.params.max_time_ms) {
+                log::debug!("Reached time limit (elapsed: {} ms)", start.elapsed().as_millis());
+                break;
+            }
+            if control.should_stop() {
+                log::debug!("Reached deadline (elapsed: {} ms)", start.elapsed().as_millis());
+                break;
+            }
+
+            self.shake(&mut solution, start_pos, num_removals);
+            if self.schedule(&solution).is_none() {
+                // Removing sights may shift the remaining visits into closed time windows
+                solution = best_solution.clone();
+            }
+            self.insert(&mut solution);
+
+            iteration += 1;
+
+            let score = self.get_total_score(&solution);
+            if score > best_score {
+                log::trace!("Updating best score (new score: {} > old score: {})",
+                    score, best_score);
+                best_score = score;
+                best_solution = solution.clone();
+                self.report_progress(control, iteration, best_score, &best_solution);
+                num_removals = 1;
+                non_improving_count = 0;
+            } else {
+                non_improving_count += 1;
+            }
+
+            start_pos += num_removals;
+            num_removals += 1;
+            if num_removals > max_num_removals {
+                // Restart shaking at a random position
+                num_removals = 1;
+                start_pos = rng.gen_range(0..solution.len().max(1));
+            }
+            if solution.is_empty() {
+                start_pos = 0;
+            } else {
+                start_pos %= solution.len();
+            }
+        }
+
+        // unwrap safety: the best solution has been checked for feasibility
+        let route = self.build_route(self.schedule(&best_solution).unwrap());
+        log::debug!("Finished iterated local search in {} ms. Computed walking route from node: {} including {} sights with total score: {}.",
+            start.elapsed().as_millis(), self.root_id, route.len() - 1, best_score);
+
+        Ok(route)
+    }
+
+    fn get_collected_score(&self, route: &Route) -> usize {
+        route.iter()
+            .map(|route_sec| {
+                match route_sec {
+                    // Start and intermediate sectors contain a sight per definition
+                    RouteSector::Start(sector) => self.scores[&sector.sight.node_id].0,
+                    RouteSector::Intermediate(sector) => self.scores[&sector.sight.node_id].0,
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    fn get_seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use chrono::{DateTime, Utc};
+    use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, AlgorithmParamsOverrides, Area, ComputeControl, RouteSector, SightCategoryPref, UserPreferences};
+    use crate::algorithm::ils::{IlsParams, IlsParamsOverrides, IteratedLocalSearch};
+    use crate::algorithm::test::{END_TIME, SEED, START_TIME, WALKING_SPEED_MPS};
+    use crate::data::graph::{Category, SightExclusions};
+    use crate::init_logging;
+    use crate::utils::test_setup;
+
+    fn create_algo(seed: Option<u64>) -> IteratedLocalSearch<'static> {
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        IteratedLocalSearch::new(
+            &test_setup::GRAPH,
+            start_time,
+            end_time,
+            WALKING_SPEED_MPS,
+            Area {
+                lat: 53.074448,
+                lon: 8.805105,
+                radius: 300.0,
+            },
+            None,
+            UserPreferences {
+                categories: vec![SightCategoryPref { category: Category::Sightseeing, pref: 5 },
+                                 SightCategoryPref { category: Category::Restaurants, pref: 2 }],
+                sights: vec![],
+                exclude: SightExclusions::default(),
+            },
+            AlgorithmParams::default(),
+            seed).unwrap()
+    }
+
+    #[test]
+    fn test_params_overrides_within_safe_ranges() {
+        let params = AlgorithmParams::default();
+        let overrides = AlgorithmParamsOverrides {
+            ils: IlsParamsOverrides {
+                max_non_improving: Some(50),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let overridden = params.with_overrides(&overrides).unwrap();
+        assert_eq!(overridden.ils, IlsParams {
+            max_non_improving: 50,
+            ..IlsParams::default()
+        });
+
+        let overrides = AlgorithmParamsOverrides {
+            ils: IlsParamsOverrides {
+                max_time_ms: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        match params.with_overrides(&overrides) {
+            Err(AlgorithmError::InvalidParameter { name, .. }) => assert_eq!(name, "max_time_ms"),
+            _ => panic!("Accepted maximum computation time outside of safe range"),
+        }
+    }
+
+    #[test]
+    fn test_progress_reported_on_improvement() {
+        init_logging();
+
+        let algo = create_algo(Some(SEED));
+        // (iteration, best score, whether the intermediate route is complete)
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let listener_reports = reports.clone();
+        let control = ComputeControl::default().with_progress_listener(move |progress| {
+            let route_complete = matches!(progress.route.and_then(|route| route.last()),
+                Some(RouteSector::End(_)));
+            listener_reports.lock().unwrap().push(
+                (progress.iteration, progress.best_score, route_complete));
+        });
+        let route = algo.compute_route(&control).expect("Error during route computation");
+
+        let reports = reports.lock().unwrap();
+        assert!(!reports.is_empty(), "No progress reported");
+        for window in reports.windows(2) {
+            assert!(window[1].0 > window[0].0, "Iteration did not increase");
+            assert!(window[1].1 > window[0].1, "Best score did not improve");
+        }
+        assert!(reports.iter().all(|&(_, _, route_complete)| route_complete));
+        assert_eq!(reports.last().unwrap().1, algo.get_collected_score(&route));
+    }
+}
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use crate::data::graph::{EdgeType, Graph, Node};
+
+/// Maximum deviation in degrees from the current direction that still counts as going straight
+const STRAIGHT_MAX_ANGLE: f64 = 20.0;
+/// Maximum deviation in degrees from the current direction for a slight turn
+const SLIGHT_TURN_MAX_ANGLE: f64 = 45.0;
+/// Maximum deviation in degrees from the current direction for a regular turn
+const TURN_MAX_ANGLE: f64 = 135.0;
+/// Maximum deviation in degrees from the current direction for a sharp turn. Larger deviations
+/// are u-turns.
+const SHARP_TURN_MAX_ANGLE: f64 = 170.0;
+
+/// Language of walking instructions
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    De,
+    En,
+}
+
+/// Compass direction in which a walk starts
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Heading {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Heading {
+    /// Heading closest to `bearing`, given in degrees clockwise from north
+    fn from_bearing(bearing: f64) -> Self {
+        const HEADINGS: [Heading; 8] = [Heading::North, Heading::NorthEast, Heading::East,
+            Heading::SouthEast, Heading::South, Heading::SouthWest, Heading::West, Heading::NorthWest];
+        HEADINGS[((bearing.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+    }
+
+    fn name(&self, language: Language) -> &'static str {
+        match (language, self) {
+            (Language::De, Heading::North) => "Norden",
+            (Language::De, Heading::NorthEast) => "Nordosten",
+            (Language::De, Heading::East) => "Osten",
+            (Language::De, Heading::SouthEast) => "Südosten",
+            (Language::De, Heading::South) => "Süden",
+            (Language::De, Heading::SouthWest) => "Südwesten",
+            (Language::De, Heading::West) => "Westen",
+            (Language::De, Heading::NorthWest) => "Nordwesten",
+            (Language::En, Heading::North) => "north",
+            (Language::En, Heading::NorthEast) => "northeast",
+            (Language::En, Heading::East) => "east",
+            (Language::En, Heading::SouthEast) => "southeast",
+            (Language::En, Heading::South) => "south",
+            (Language::En, Heading::SouthWest) => "southwest",
+            (Language::En, Heading::West) => "west",
+            (Language::En, Heading::NorthWest) => "northwest",
+        }
+    }
+}
+
+/// Maneuver at the start of a walking instruction
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum Maneuver {
+    /// Start walking in the given direction
+    Depart { heading: Heading },
+    /// Go straight on into another street
+    Continue,
+    SlightLeft,
+    Left,
+    SharpLeft,
+    SlightRight,
+    Right,
+    SharpRight,
+    UTurn,
+    /// Arrive at the target of the sector
+    Arrive,
+}
+
+impl Maneuver {
+    /// Maneuver for a change of direction by `angle` degrees, where positive angles are turns
+    /// to the right
+    fn from_turn_angle(angle: f64) -> Self {
+        let abs_angle = angle.abs();
+        let right = angle > 0.0;
+        if abs_angle <= STRAIGHT_MAX_ANGLE {
+            Maneuver::Continue
+        } else if abs_angle <= SLIGHT_TURN_MAX_ANGLE {
+            if right { Maneuver::SlightRight } else { Maneuver::SlightLeft }
+        } else if abs_angle <= TURN_MAX_ANGLE {
+            if right { Maneuver::Right } else { Maneuver::Left }
+        } else if abs_angle <= SHARP_TURN_MAX_ANGLE {
+            if right { Maneuver::SharpRight } else { Maneuver::SharpLeft }
+        } else {
+            Maneuver::UTurn
+        }
+    }
+}
+
+/// A single walking instruction, e.g., "Turn left onto Obernstraße, walk 230 m"
+///
+/// # Fields
+/// * `maneuver` - The maneuver at the start of the instruction
+/// * `street_name` - The name of the street to walk along, if known
+/// * `distance_meters` - The distance to walk after the maneuver
+/// * `text` - The instruction as text in the requested language
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub maneuver: Maneuver,
+    pub street_name: Option<String>,
+    pub distance_meters: usize,
+    pub text: String,
+}
+
+/// Consecutive edges along the same street
+struct Leg<'a> {
+    street_name: Option<&'a str>,
+    edge_type: EdgeType,
+    /// Bearing of the first edge of the leg
+    start_bearing: f64,
+    /// Bearing of the last edge of the leg
+    end_bearing: f64,
+    distance_meters: usize,
+}
+
+/// Builds walking instructions for a path through `graph`.
+///
+/// A new instruction starts whenever the street name changes, or the edge type changes along
+/// unnamed streets. The turn is derived from the bearings of the edges before and after the
+/// change. The last instruction announces the arrival at `target_name`, or at the destination if
+/// `None`.
+///
+/// # Arguments
+/// * `graph` - The graph containing the path
+/// * `nodes` - The nodes of the path in walking order
+/// * `target_name` - The name of the target of the path
+/// * `language` - The language of the instruction texts
+pub fn build_instructions(graph: &Graph, nodes: &[&Node], target_name: Option<&str>,
+                          language: Language) -> Vec<Instruction> {
+    let mut legs: Vec<Leg> = Vec::new();
+    // edges to sights have no street, so their distance is added to the adjacent leg
+    let mut pending_distance = 0;
+    // index of the source of the current edge. Shape points of contracted edges lie between the
+    // source and the target of an edge in `nodes`.
+    let mut src_index = 0;
+    for tgt_index in 1..nodes.len() {
+        if graph.get_degree(nodes[tgt_index].id) == 0 {
+            continue;
+        }
+        let edge_nodes = &nodes[src_index..=tgt_index];
+        src_index = tgt_index;
+        let edge = match graph.get_edge(edge_nodes[0].id, nodes[tgt_index].id) {
+            Some(edge) => edge,
+            None => continue,
+        };
+        if edge.edge_type == EdgeType::SightEdge {
+            match legs.last_mut() {
+                Some(leg) => leg.distance_meters += edge.dist,
+                None => pending_distance += edge.dist,
+            }
+            continue;
+        }
+
+        let street_name = graph.get_street_name(edge);
+        let start_bearing = bearing(edge_nodes[0], edge_nodes[1]);
+        let end_bearing = bearing(edge_nodes[edge_nodes.len() - 2], edge_nodes[edge_nodes.len() - 1]);
+        match legs.last_mut() {
+            Some(leg) if leg.street_name == street_name
+                && (street_name.is_some() || leg.edge_type == edge.edge_type) => {
+                leg.end_bearing = end_bearing;
+                leg.distance_meters += edge.dist;
+            }
+            _ => {
+                legs.push(Leg {
+                    street_name,
+                    edge_type: edge.edge_type,
+                    start_bearing,
+                    end_bearing,
+                    distance_meters: edge.dist + pending_distance,
+                });
+                pending_distance = 0;
+            }
+        }
+    }
+
+    let mut instructions = Vec::with_capacity(legs.len() + 1);
+    let mut prev_bearing = None;
+    for leg in &legs {
+        let maneuver = match prev_bearing {
+            None => Maneuver::Depart { heading: Heading::from_bearing(leg.start_bearing) },
+            Some(prev_bearing) => Maneuver::from_turn_angle(turn_angle(prev_bearing, leg.start_bearing)),
+        };
+        instructions.push(Instruction {
+            maneuver,
+            street_name: leg.street_name.map(str::to_string),
+            distance_meters: leg.distance_meters,
+            text: instruction_text(maneuver, leg.street_name, leg.distance_meters, language),
+        });
+        prev_bearing = Some(leg.end_bearing);
+    }
+    instructions.push(Instruction {
+        maneuver: Maneuver::Arrive,
+        street_name: None,
+        distance_meters: 0,
+        text: arrival_text(target_name, language),
+    });
+    instructions
+}
+
+/// Initial bearing in degrees clockwise from north when walking from `src` to `tgt`
+fn bearing(src: &Node, tgt: &Node) -> f64 {
+    let (lat1, lat2) = (src.lat.to_radians(), tgt.lat.to_radians());
+    let delta_lon = (tgt.lon - src.lon).to_radians();
+    let y = delta_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Change of direction in degrees from `from_bearing` to `to_bearing` within (-180, 180], where
+/// positive angles are turns to the right
+fn turn_angle(from_bearing: f64, to_bearing: f64) -> f64 {
+    let angle = (to_bearing - from_bearing).rem_euclid(360.0);
+    if angle > 180.0 { angle - 360.0 } else { angle }
+}
+
+/// Describes walking `distance_meters` along `street_name` after `maneuver`
+fn instruction_text(maneuver: Maneuver, street_name: Option<&str>, distance_meters: usize,
+                    language: Language) -> String {
+    match language {
+        Language::De => {
+            let action = match maneuver {
+                Maneuver::Depart { heading } => format!("Richtung {}", heading.name(language)),
+                Maneuver::Continue => "Geradeaus".to_string(),
+                Maneuver::SlightLeft => "Leicht links".to_string(),
+                Maneuver::Left => "Links".to_string(),
+                Maneuver::SharpLeft => "Scharf links".to_string(),
+                Maneuver::SlightRight => "Leicht rechts".to_string(),
+                Maneuver::Right => "Rechts".to_string(),
+                Maneuver::SharpRight => "Scharf rechts".to_string(),
+                Maneuver::UTurn => "Wenden".to_string(),
+                Maneuver::Arrive => unreachable!("Arrivals are described by arrival_text"),
+            };
+            match street_name {
+                Some(street_name) => format!("{} auf {}, {} m gehen", action, street_name, distance_meters),
+                None => format!("{}, {} m gehen", action, distance_meters),
+            }
+        }
+        Language::En => {
+            let action = match maneuver {
+                Maneuver::Depart { heading } => format!("Head {}", heading.name(language)),
+                Maneuver::Continue => "Continue".to_string(),
+                Maneuver::SlightLeft => "Turn slightly left".to_string(),
+                Maneuver::Left => "Turn left".to_string(),
+                Maneuver::SharpLeft => "Turn sharply left".to_string(),
+                Maneuver::SlightRight => "Turn slightly right".to_string(),
+                Maneuver::Right => "Turn right".to_string(),
+                Maneuver::SharpRight => "Turn sharply right".to_string(),
+                Maneuver::UTurn => "Make a U-turn".to_string(),
+                Maneuver::Arrive => unreachable!("Arrivals are described by arrival_text"),
+            };
+            let preposition = match maneuver {
+                Maneuver::Depart { .. } => "on",
+                _ => "onto",
+            };
+            match street_name {
+                Some(street_name) => format!("{} {} {}, walk {} m", action, preposition, street_name, distance_meters),
+                None => format!("{}, walk {} m", action, distance_meters),
+            }
+        }
+    }
+}
+
+/// Describes the arrival at `target_name`, or at the destination if `None`
+fn arrival_text(target_name: Option<&str>, language: Language) -> String {
+    match (language, target_name) {
+        (Language::De, Some(target_name)) => format!("Ankunft bei {}", target_name),
+        (Language::De, None) => "Ankunft am Ziel".to_string(),
+        (Language::En, Some(target_name)) => format!("Arrive at {}", target_name),
+        (Language::En, None) => "Arrive at your destination".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algorithm::instructions::{Heading, instruction_text, Language, Maneuver, turn_angle};
+    use crate::algorithm::test::greedy_route_around_hotel;
+    use crate::init_logging;
+    use crate::utils::test_setup;
+
+    #[test]
+    fn test_maneuver_from_bearings() {
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(350.0, 10.0)), Maneuver::Continue);
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(0.0, 90.0)), Maneuver::Right);
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(0.0, 270.0)), Maneuver::Left);
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(90.0, 60.0)), Maneuver::SlightLeft);
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(10.0, 160.0)), Maneuver::SharpRight);
+        assert_eq!(Maneuver::from_turn_angle(turn_angle(0.0, 180.0)), Maneuver::UTurn);
+        assert_eq!(Heading::from_bearing(350.0), Heading::North);
+        assert_eq!(Heading::from_bearing(100.0), Heading::East);
+        assert_eq!(Heading::from_bearing(200.0), Heading::South);
+    }
+
+    #[test]
+    fn test_instruction_texts() {
+        assert_eq!(instruction_text(Maneuver::Left, Some("Obernstraße"), 230, Language::En),
+                   "Turn left onto Obernstraße, walk 230 m");
+        assert_eq!(instruction_text(Maneuver::Left, Some("Obernstraße"), 230, Language::De),
+                   "Links auf Obernstraße, 230 m gehen");
+        assert_eq!(instruction_text(Maneuver::Depart { heading: Heading::North }, None, 50, Language::En),
+                   "Head north, walk 50 m");
+        assert_eq!(instruction_text(Maneuver::Depart { heading: Heading::North }, None, 50, Language::De),
+                   "Richtung Norden, 50 m gehen");
+    }
+
+    #[test]
+    fn test_instructions_cover_each_sector() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let route = greedy_route_around_hotel();
+
+        let mut num_named_instructions = 0;
+        for route_sector in &route {
+            let instructions = route_sector.instructions(graph, Language::En);
+            let path_distance: usize = route_sector.nodes().windows(2)
+                .map(|pair| graph.get_edge(pair[0].id, pair[1].id).expect("Path edge missing").dist)
+                .sum();
+            assert_eq!(instructions.last().unwrap().maneuver, Maneuver::Arrive);
+            assert_eq!(instructions.iter().map(|instruction| instruction.distance_meters).sum::<usize>(),
+                       path_distance);
+            if instructions.len() > 1 {
+                assert!(matches!(instructions[0].maneuver, Maneuver::Depart { .. }));
+            }
+            num_named_instructions += instructions.iter()
+                .filter(|instruction| instruction.street_name.is_some())
+                .count();
+        }
+        assert!(num_named_instructions > 0, "No street names in instructions");
+    }
+}
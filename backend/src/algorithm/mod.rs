@@ -1,14 +1,28 @@
+pub mod branch_and_bound;
 pub mod greedy;
+pub mod ils;
+pub mod instructions;
 pub mod sa_lin_yu;
 
-use std::collections::HashMap;
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
-use crate::data::graph::{Category, Graph, Node, Sight};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::data::graph::{Category, Graph, Node, Sight, SightExclusions};
 use serde::{Serialize, Deserialize, Serializer};
 use derive_more::{Display, Error};
+use geoutils::Location;
+use itertools::Itertools;
 use opening_hours_syntax::rules::RuleKind;
+use rand::{Rng, thread_rng};
+use crate::algorithm::branch_and_bound::BranchAndBound;
 use crate::algorithm::greedy::GreedyAlgorithm;
-use crate::algorithm::sa_lin_yu::SimAnnealingLinYu;
+use crate::algorithm::ils::{IlsParams, IlsParamsOverrides, IteratedLocalSearch};
+use crate::algorithm::instructions::{build_instructions, Instruction, Language};
+use crate::algorithm::sa_lin_yu::{SimAnnealingLinYu, SimAnnealingParams, SimAnnealingParamsOverrides};
+use crate::utils::isochrone::reachable_radius;
 
 /// Type alias for a mapping from node id's to scores, where the nodes represent sights / tourist
 /// attractions
@@ -38,6 +52,128 @@ impl Area {
     }
 }
 
+/// Geographic coordinate
+#[derive(Deserialize, Clone)]
+pub struct Coordinate {
+    lat: f64,
+    lon: f64,
+}
+
+impl Coordinate {
+    /// Creates a new coordinate instance
+    ///
+    /// # Arguments
+    /// * `lat` - The latitude coordinate
+    /// * `lon` - The longitude coordinate
+    pub fn from_lat_lon(lat: f64, lon: f64) -> Self {
+        Self {
+            lat,
+            lon,
+        }
+    }
+
+    /// Returns the latitude of the coordinate
+    pub fn lat(&self) -> f64 {
+        self.lat
+    }
+
+    /// Returns the longitude of the coordinate
+    pub fn lon(&self) -> f64 {
+        self.lon
+    }
+}
+
+/// Search space of an algorithm instance
+///
+/// # Fields
+/// * `edge_area` - The area in which the edges of a walking route must lie
+/// * `sights` - The reachable sights that can potentially be visited
+/// * `root_id` - The id of the node where the walking route starts
+/// * `end_id` - The id of the node where the walking route ends. Equals `root_id` for round trips.
+struct SearchSpace<'a> {
+    edge_area: Area,
+    sights: Vec<&'a Sight>,
+    root_id: usize,
+    end_id: usize,
+}
+
+impl<'a> SearchSpace<'a> {
+    /// Determines the search space for a walking route that starts at the center of `area` and
+    /// ends at `destination`, if given, or at its start, otherwise.
+    ///
+    /// For round trips, sights are searched in a circle around the center of `area`. Otherwise,
+    /// sights are searched in a corridor around the straight line between start and destination.
+    /// Sights excluded by `exclusions` are never part of the search space.
+    fn new(graph: &'a Graph, start_time: &DateTime<Utc>, end_time: &DateTime<Utc>,
+           walking_speed_mps: f64, area: &Area, destination: Option<&Coordinate>,
+           exclusions: &SightExclusions) -> Result<Self, AlgorithmError> {
+        if end_time < start_time {
+            return Err(AlgorithmError::NegativeTimeInterval);
+        }
+
+        // Holiday rules of opening hours are only evaluated within the holiday calendar
+        [start_time, end_time].iter()
+            .map(|time| time.with_timezone(&graph.timezone()).date_naive())
+            .dedup()
+            .for_each(|date| graph.holidays().warn_if_not_covered(date));
+
+        let time_budget = end_time.signed_duration_since(*start_time).num_seconds() as f64;
+        // Each node on the route can be reached within half of the walking distance
+        let max_walking_dist = walking_speed_mps * time_budget / 2.0;
+
+        let root_id = match graph.get_nearest_node_in_area(area.lat, area.lon,
+                                                           max_walking_dist.min(area.radius)) {
+            Some(nearest_node) => nearest_node,
+            None => return Err(AlgorithmError::NoNearestNodeFound)
+        };
+        // The edges of the route lie within the straight-line distance of the farthest reachable
+        // node along the street network
+        let edge_radius = reachable_radius(graph, root_id, max_walking_dist as usize);
+        let sights_radius = edge_radius.min(area.radius);
+
+        let (edge_area, sights, end_id) = match destination {
+            None => {
+                let sights = graph.get_reachable_sights_in_area(area.lat, area.lon,
+                                                                sights_radius, edge_radius, exclusions);
+                (Area::from_coords_and_radius(area.lat, area.lon, edge_radius), sights, root_id)
+            }
+            Some(destination) => {
+                let end_id = match graph.get_nearest_node_in_area(
+                    destination.lat, destination.lon, sights_radius) {
+                    Some(nearest_node) => nearest_node,
+                    None => return Err(AlgorithmError::NoNearestNodeFound)
+                };
+
+                // Let the edge area cover both start and destination
+                let half_dist = Location::new(area.lat, area.lon)
+                    .haversine_distance_to(&Location::new(destination.lat, destination.lon))
+                    .meters() / 2.0;
+                let edge_area = Area::from_coords_and_radius(
+                    (area.lat + destination.lat) / 2.0, (area.lon + destination.lon) / 2.0,
+                    half_dist + edge_radius);
+                if half_dist * 2.0 > walking_speed_mps * time_budget {
+                    return Err(AlgorithmError::DestinationNotReachable);
+                }
+
+                let sights = graph.get_reachable_sights_in_corridor(
+                    area.lat, area.lon, destination.lat, destination.lon,
+                    sights_radius, edge_area.radius, exclusions);
+                (edge_area, sights, end_id)
+            }
+        };
+        if sights.is_empty() {
+            return Err(AlgorithmError::NoSightsFound);
+        }
+
+        Ok(Self {
+            edge_area,
+            sights,
+            root_id,
+            end_id,
+        })
+    }
+}
+
 /// Maximum value for user sight preferences
 const USER_PREF_MAX: usize = 5;
 
@@ -73,6 +209,9 @@ impl SightCategoryPref {
 pub struct SightPref {
     id: usize,
     pref: usize,
+    /// Whether the sight must be part of the route
+    #[serde(default)]
+    must_visit: bool,
 }
 
 impl SightPref {
@@ -86,11 +225,28 @@ impl SightPref {
         Self {
             id,
             pref,
+            must_visit: false,
         }
     }
 
-    /// Returns a valid preference value for this sight
+    /// Creates a new preference for a sight that must be part of the route
+    ///
+    /// # Arguments
+    /// * `id` - The sight node id
+    pub fn new_must_visit(id: usize) -> Self {
+        Self {
+            id,
+            pref: USER_PREF_MAX,
+            must_visit: true,
+        }
+    }
+
+    /// Returns a valid preference value for this sight. Sights that must be visited always have
+    /// the maximum preference.
     fn get_valid_pref(&self) -> usize {
+        if self.must_visit {
+            return USER_PREF_MAX;
+        }
         self.pref.min(USER_PREF_MAX)
     }
 }
@@ -100,6 +256,9 @@ impl SightPref {
 pub struct UserPreferences {
     categories: Vec<SightCategoryPref>,
     sights: Vec<SightPref>,
+    /// Sights that must not be part of the route
+    #[serde(default)]
+    exclude: SightExclusions,
 }
 
 impl UserPreferences {
@@ -112,10 +271,252 @@ impl UserPreferences {
         Self {
             categories: category_prefs,
             sights: sight_prefs,
+            exclude: SightExclusions::default(),
+        }
+    }
+
+    /// Excludes the sights specified by `exclusions` from the route
+    pub fn with_exclusions(mut self, exclusions: SightExclusions) -> Self {
+        self.exclude = exclusions;
+        self
+    }
+
+    /// Determines the ids of the sights that must be part of the route.
+    ///
+    /// # Returns
+    /// * an `Ok` containing the ids of all mandatory sights, if all of them are among the
+    ///   reachable `sights`, or
+    /// * an `Err` containing `AlgorithmError::MandatorySightsNotFeasible`, otherwise
+    fn get_mandatory_sights(&self, graph: &Graph, sights: &[&Sight]) -> Result<HashSet<usize>, AlgorithmError> {
+        let mandatory: HashSet<usize> = self.sights.iter()
+            .filter(|sight_pref| sight_pref.must_visit)
+            .map(|sight_pref| sight_pref.id)
+            .collect();
+        let unreachable = mandatory.iter()
+            .filter(|&sight_id| !sights.iter().any(|sight| sight.node_id == *sight_id))
+            .copied()
+            .collect_vec();
+        if !unreachable.is_empty() {
+            return Err(AlgorithmError::mandatory_sights_not_feasible(graph, &unreachable));
         }
+        Ok(mandatory)
+    }
+}
+
+/// Tuning parameters of the algorithms. Missing fields are set to their default values when
+/// deserialized.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AlgorithmParams {
+    /// Parameters of the simulated annealing, which also determine the sights considered by the
+    /// iterated local search
+    pub sim_annealing: SimAnnealingParams,
+    /// Parameters of the iterated local search
+    pub ils: IlsParams,
+}
+
+impl AlgorithmParams {
+    /// Checks that all parameters lie within their safe ranges
+    pub fn validate(&self) -> Result<(), AlgorithmError> {
+        self.sim_annealing.validate()?;
+        self.ils.validate()
+    }
+
+    /// Returns a copy of these parameters with given `overrides` applied
+    ///
+    /// # Returns
+    /// * an `Ok` containing the resulting parameters, if they lie within their safe ranges, or
+    /// * an `Err` containing `AlgorithmError::InvalidParameter`, otherwise
+    pub fn with_overrides(&self, overrides: &AlgorithmParamsOverrides) -> Result<Self, AlgorithmError> {
+        let params = Self {
+            sim_annealing: self.sim_annealing.with_overrides(&overrides.sim_annealing),
+            ils: self.ils.with_overrides(&overrides.ils),
+        };
+        params.validate()?;
+        Ok(params)
     }
 }
 
+/// Per-request overrides for `AlgorithmParams`
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AlgorithmParamsOverrides {
+    pub sim_annealing: SimAnnealingParamsOverrides,
+    pub ils: IlsParamsOverrides,
+}
+
+/// Reason why a route computation ended
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// The algorithm finished its search on its own
+    Converged,
+    /// The algorithm stopped early because the deadline was reached and returned the best route
+    /// found so far
+    DeadlineReached,
+    /// The algorithm stopped early because the computation was cancelled and returned the best
+    /// route found so far
+    Cancelled,
+}
+
+/// Progress of a route computation, as reported by an algorithm
+#[derive(Serialize, Debug)]
+pub struct Progress<'a, 'r> {
+    /// Current temperature, if the algorithm anneals
+    pub temperature: Option<f64>,
+    /// Number of iterations performed so far
+    pub iteration: usize,
+    /// Score of the best route found so far
+    pub best_score: usize,
+    /// Best route found so far, if the algorithm has built one. A route that is still under
+    /// construction lacks the end sector.
+    pub route: Option<&'r [RouteSector<'a>]>,
+}
+
+/// Listener that is called with each progress report of a route computation
+pub type ProgressListener = Box<dyn Fn(&Progress) + Send + Sync>;
+
+/// Controls how long a route computation may run and tracks its progress.
+///
+/// Algorithms check `should_stop` regularly and return the best feasible route found so far as
+/// soon as it returns `true`. Afterwards, `termination_reason` tells whether they were stopped.
+/// The control can be shared between threads, e.g., to `cancel` a running computation.
+#[derive(Default)]
+pub struct ComputeControl {
+    /// Point in time after which the computation should stop. No limit if `None`.
+    deadline: Option<Instant>,
+    /// Whether an algorithm has been told to stop because of the deadline or its own time limit
+    deadline_reached: AtomicBool,
+    /// Whether the computation has been cancelled
+    cancelled: AtomicBool,
+    /// Score of the best route found so far
+    best_score: AtomicUsize,
+    /// Listener for the progress reports of the computation
+    progress_listener: Option<ProgressListener>,
+}
+
+impl fmt::Debug for ComputeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComputeControl")
+            .field("deadline", &self.deadline)
+            .field("deadline_reached", &self.deadline_reached)
+            .field("cancelled", &self.cancelled)
+            .field("best_score", &self.best_score)
+            .field("progress_listener", &self.progress_listener.is_some())
+            .finish()
+    }
+}
+
+impl ComputeControl {
+    /// Creates a new compute control that asks algorithms to stop at the given `deadline`
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a new compute control that asks algorithms to stop `max_compute_ms` milliseconds
+    /// from now
+    pub fn with_max_compute_ms(max_compute_ms: u64) -> Self {
+        Self::with_deadline(Instant::now() + std::time::Duration::from_millis(max_compute_ms))
+    }
+
+    /// Calls `listener` with each progress report of the computation
+    pub fn with_progress_listener<F>(mut self, listener: F) -> Self
+        where F: Fn(&Progress) + Send + Sync + 'static {
+        self.progress_listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Whether anybody listens to progress reports. Algorithms may skip building intermediate
+    /// routes otherwise.
+    pub fn wants_progress(&self) -> bool {
+        self.progress_listener.is_some()
+    }
+
+    /// Asks the computation to stop and return the best route found so far
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the computation has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Reports the score of a route found by the computation
+    pub fn report_score(&self, score: usize) {
+        self.best_score.fetch_max(score, Ordering::Relaxed);
+    }
+
+    /// Reports the progress of the computation, including the score of its best route
+    pub fn report_progress(&self, progress: &Progress) {
+        self.report_score(progress.best_score);
+        if let Some(listener) = &self.progress_listener {
+            listener(progress);
+        }
+    }
+
+    /// Outputs the score of the best route reported so far
+    pub fn best_score(&self) -> usize {
+        self.best_score.load(Ordering::Relaxed)
+    }
+
+    /// Whether the computation should stop and return the best route found so far
+    pub fn should_stop(&self) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.deadline_reached.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false
+        }
+    }
+
+    /// Whether an algorithm that started at `start` exceeded its own time limit of
+    /// `max_time_ms` milliseconds. The computation then ends like at the deadline.
+    pub fn time_limit_reached(&self, start: Instant, max_time_ms: u64) -> bool {
+        if start.elapsed().as_millis() > max_time_ms as u128 {
+            self.deadline_reached.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+
+    /// Outputs why the computation ended
+    pub fn termination_reason(&self) -> TerminationReason {
+        if self.is_cancelled() {
+            TerminationReason::Cancelled
+        } else if self.deadline_reached.load(Ordering::Relaxed) {
+            TerminationReason::DeadlineReached
+        } else {
+            TerminationReason::Converged
+        }
+    }
+}
+
+/// Returns the given `seed` or a random seed if `None`.
+/// Random seeds are at most 2^53, such that they can be represented exactly by JavaScript numbers.
+fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| thread_rng().gen_range(0..1 << 53))
+}
+
+/// Check that the value of the algorithm parameter with given `name` lies within `min` and `max`
+fn check_param_range(name: &str, value: f64, min: f64, max: f64) -> Result<(), AlgorithmError> {
+    if value.is_nan() || value < min || value > max {
+        return Err(AlgorithmError::InvalidParameter {
+            name: name.to_string(),
+            value,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
 /// A sector within a route
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
@@ -128,8 +529,10 @@ pub enum RouteSector<'a> {
     End(EndSector<'a>),
 }
 
-/// Helper function to serialize date times as RFC 3339 (ISO 8601) date time strings
-fn serialize_date_time<S>(date_time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+/// Helper function to serialize date times as RFC 3339 (ISO 8601) date time strings with the
+/// offset of their timezone
+fn serialize_date_time<S, T>(date_time: &DateTime<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer, T: TimeZone {
     serializer.serialize_str(date_time.to_rfc3339().as_str())
 }
 
@@ -147,11 +550,11 @@ fn serialize_date_time<S>(date_time: &DateTime<Utc>, serializer: S) -> Result<S:
 #[derive(Serialize, Debug)]
 pub struct Sector<'a> {
     #[serde(serialize_with = "serialize_date_time")]
-    time_of_arrival: DateTime<Utc>,
+    time_of_arrival: DateTime<Tz>,
     #[serde(serialize_with = "serialize_date_time")]
-    service_start_time: DateTime<Utc>,
+    service_start_time: DateTime<Tz>,
     #[serde(serialize_with = "serialize_date_time")]
-    service_end_time: DateTime<Utc>,
+    service_end_time: DateTime<Tz>,
     sight: &'a Sight,
     nodes: Vec<&'a Node>,
 }
@@ -160,13 +563,13 @@ impl<'a> Sector<'a> {
     /// Creates a new route sector with a target sight
     ///
     /// # Arguments
-    /// * `start_time` - The start time of the trip or hike
+    /// * `start_time` - The start time of the trip or hike in the local time of the graphs region
     /// * `used_time_budget` - The number of seconds passed since the start of the trip
     /// * `sight_travel_time` - The number of seconds to travel from the sectors source node to its
     /// target sight
     /// * `wait_time` - The number of seconds to wait until the service at the sight can start
     /// * `service_time` - The number of seconds to spend at the sectors target sight
-    fn new(start_time: &DateTime<Utc>, used_time_budget: i64, sight_travel_time: i64, wait_time: i64,
+    fn new(start_time: &DateTime<Tz>, used_time_budget: i64, sight_travel_time: i64, wait_time: i64,
            service_time: i64, sight: &'a Sight, nodes: Vec<&'a Node>) -> Self {
         let curr_time = *start_time + Duration::seconds(used_time_budget);
         let time_of_arrival = curr_time + Duration::seconds(sight_travel_time);
@@ -180,6 +583,36 @@ impl<'a> Sector<'a> {
             nodes,
         }
     }
+
+    /// Outputs the time of arrival at the sight
+    pub fn time_of_arrival(&self) -> &DateTime<Tz> {
+        &self.time_of_arrival
+    }
+
+    /// Outputs the time at which the service at the sight starts
+    pub fn service_start_time(&self) -> &DateTime<Tz> {
+        &self.service_start_time
+    }
+
+    /// Outputs the time at which the service at the sight ends
+    pub fn service_end_time(&self) -> &DateTime<Tz> {
+        &self.service_end_time
+    }
+
+    /// Outputs the target sight of this sector
+    pub fn sight(&self) -> &'a Sight {
+        self.sight
+    }
+
+    /// Outputs the nodes from the sectors source to its target sight (both inclusive)
+    pub fn nodes(&self) -> &[&'a Node] {
+        &self.nodes
+    }
+
+    /// Builds walking instructions from the sectors source to its target sight
+    pub fn instructions(&self, graph: &Graph, language: Language) -> Vec<Instruction> {
+        build_instructions(graph, &self.nodes, Some(&self.sight.name), language)
+    }
 }
 
 /// Concrete representation of a route end sector
@@ -191,7 +624,7 @@ impl<'a> Sector<'a> {
 #[derive(Serialize, Debug)]
 pub struct EndSector<'a> {
     #[serde(serialize_with = "serialize_date_time")]
-    time_of_arrival: DateTime<Utc>,
+    time_of_arrival: DateTime<Tz>,
     nodes: Vec<&'a Node>,
 }
 
@@ -199,11 +632,11 @@ impl<'a> EndSector<'a> {
     /// Creates a new route end sector
     ///
     /// # Arguments
-    /// * `start_time` - The start time of the trip or hike
+    /// * `start_time` - The start time of the trip or hike in the local time of the graphs region
     /// * `used_time_budget` - The number of seconds passed since the start of the trip
     /// * `tgt_travel_time` - The number of seconds to travel from the sectors source node to its
     /// target node
-    fn new(start_time: &DateTime<Utc>, used_time_budget: i64, tgt_travel_time: i64,
+    fn new(start_time: &DateTime<Tz>, used_time_budget: i64, tgt_travel_time: i64,
            nodes: Vec<&'a Node>) -> Self {
         let curr_time = *start_time + Duration::seconds(used_time_budget);
         let time_of_arrival = curr_time + Duration::seconds(tgt_travel_time);
@@ -212,11 +645,142 @@ impl<'a> EndSector<'a> {
             nodes,
         }
     }
+
+    /// Outputs the time of arrival at the target node
+    pub fn time_of_arrival(&self) -> &DateTime<Tz> {
+        &self.time_of_arrival
+    }
+
+    /// Outputs the nodes from the sectors source to its target node (both inclusive)
+    pub fn nodes(&self) -> &[&'a Node] {
+        &self.nodes
+    }
+
+    /// Builds walking instructions from the sectors source to its target node
+    pub fn instructions(&self, graph: &Graph, language: Language) -> Vec<Instruction> {
+        build_instructions(graph, &self.nodes, None, language)
+    }
+}
+
+impl<'a> RouteSector<'a> {
+    /// Outputs the nodes from the sectors source to its target (both inclusive)
+    pub fn nodes(&self) -> &[&'a Node] {
+        match self {
+            RouteSector::Start(sector) | RouteSector::Intermediate(sector) => sector.nodes(),
+            RouteSector::End(sector) => sector.nodes(),
+        }
+    }
+
+    /// Builds walking instructions from the sectors source to its target
+    pub fn instructions(&self, graph: &Graph, language: Language) -> Vec<Instruction> {
+        match self {
+            RouteSector::Start(sector) | RouteSector::Intermediate(sector) =>
+                sector.instructions(graph, language),
+            RouteSector::End(sector) => sector.instructions(graph, language),
+        }
+    }
+
+    /// Outputs the sector, if it leads to a sight
+    pub fn sight_sector(&self) -> Option<&Sector<'a>> {
+        match self {
+            RouteSector::Start(sector) | RouteSector::Intermediate(sector) => Some(sector),
+            RouteSector::End(_) => None,
+        }
+    }
 }
 
 /// Type alias for a vector of route sectors that form a contiguous route
 pub type Route<'a> = Vec<RouteSector<'a>>;
 
+/// Summary statistics of a route
+///
+/// # Fields
+/// * `total_distance_meters` - The walking distance along all sectors, i.e., the sum of the
+///   distances of their edges
+/// * `walking_secs` - The number of seconds spent walking
+/// * `waiting_secs` - The number of seconds spent waiting for sights to open
+/// * `service_secs` - The number of seconds spent at sights
+/// * `sights_per_category` - The number of visited sights per sight category
+/// * `collected_score` - The score collected by visiting the sights
+/// * `slack_secs` - The number of seconds left between the arrival at the end and the end time
+#[derive(Serialize, Debug)]
+pub struct RouteSummary {
+    pub total_distance_meters: usize,
+    pub walking_secs: i64,
+    pub waiting_secs: i64,
+    pub service_secs: i64,
+    pub sights_per_category: HashMap<Category, usize>,
+    pub collected_score: usize,
+    pub slack_secs: i64,
+}
+
+impl RouteSummary {
+    /// Summarizes a route that starts at `start_time` and has to end by `end_time`
+    ///
+    /// # Arguments
+    /// * `graph` - The graph on which the route was computed
+    /// * `route` - The route to summarize
+    /// * `start_time` - The start time of the walk
+    /// * `end_time` - The intended end time of the walk
+    /// * `collected_score` - The score collected by the route, as computed by
+    ///   `Algorithm::get_collected_score`
+    pub fn new<T: TimeZone>(graph: &Graph, route: &Route, start_time: &DateTime<T>,
+                            end_time: &DateTime<T>, collected_score: usize) -> Self {
+        let total_distance_meters = route.iter()
+            .map(|route_sector| path_distance(graph, route_sector.nodes()))
+            .sum();
+
+        let mut waiting_secs = 0;
+        let mut service_secs = 0;
+        let mut sights_per_category = HashMap::new();
+        for sector in route.iter().filter_map(|route_sector| route_sector.sight_sector()) {
+            waiting_secs += sector.service_start_time()
+                .signed_duration_since(*sector.time_of_arrival()).num_seconds();
+            service_secs += sector.service_end_time()
+                .signed_duration_since(*sector.service_start_time()).num_seconds();
+            *sights_per_category.entry(sector.sight().category).or_insert(0) += 1;
+        }
+
+        let end_arrival = match route.last() {
+            Some(RouteSector::End(sector)) => sector.time_of_arrival().with_timezone(&Utc),
+            _ => start_time.with_timezone(&Utc),
+        };
+        let total_secs = end_arrival.signed_duration_since(start_time.with_timezone(&Utc))
+            .num_seconds();
+
+        Self {
+            total_distance_meters,
+            walking_secs: total_secs - waiting_secs - service_secs,
+            waiting_secs,
+            service_secs,
+            sights_per_category,
+            collected_score,
+            slack_secs: end_time.with_timezone(&Utc).signed_duration_since(end_arrival).num_seconds(),
+        }
+    }
+}
+
+/// Sums up the distances of the edges along the path through `nodes`, which may contain the shape
+/// points of contracted edges
+fn path_distance(graph: &Graph, nodes: &[&Node]) -> usize {
+    let mut distance = 0;
+    let mut src_id = match nodes.first() {
+        Some(node) => node.id,
+        None => return 0,
+    };
+    for node in &nodes[1..] {
+        // shape points have no edges
+        if graph.get_degree(node.id) == 0 {
+            continue;
+        }
+        if let Some(edge) = graph.get_edge(src_id, node.id) {
+            distance += edge.dist;
+        }
+        src_id = node.id;
+    }
+    distance
+}
+
 /// Algorithm trait to be implemented by concrete algorithm implementations
 trait _Algorithm<'a> {
     /// Create a new algorithm instance
@@ -227,29 +791,44 @@ trait _Algorithm<'a> {
     /// * `end_time` - The intended end time of the walk
     /// * `walking_speed_mps` - The walking speed in meters per second
     /// * `area` - The area in which the walking route should lie
+    /// * `destination` - The location where the walk should end. If `None`, the walk ends where
+    ///   it started, i.e., at the center of `area`.
     /// * `user_prefs` - The users preferences for sight categories and sights, respectively
+    /// * `params` - The tuning parameters of the algorithms
+    /// * `seed` - The seed for all randomness of the route computation. A random seed is chosen
+    ///   if `None`.
     ///
     /// # Returns
     /// * an `Ok` containing a new algorithm instance in case of no errors, or
     /// * an `Err` containing an `AlgorithmError`, otherwise
+    #[allow(clippy::too_many_arguments)]
     fn new(graph: &'a Graph,
            start_time: DateTime<Utc>,
            end_time: DateTime<Utc>,
            walking_speed_mps: f64,
            area: Area,
-           user_prefs: UserPreferences) -> Result<Self, AlgorithmError> where Self: Sized;
+           destination: Option<Coordinate>,
+           user_prefs: UserPreferences,
+           params: AlgorithmParams,
+           seed: Option<u64>) -> Result<Self, AlgorithmError> where Self: Sized;
 
     /// Compute a route on a graph that visits tourist attractions in a specific area based on
-    /// user preferences for these tourist attractions
+    /// user preferences for these tourist attractions.
+    /// Implementations return the best feasible route found so far as soon as `control` asks them
+    /// to stop, and report their progress through `control` while computing.
     ///
     /// # Returns
     /// * an `Ok` containing the computed route in case of no errors, or
     /// * an `Err` containing an `AlgorithmError`, otherwise
-    fn compute_route(&self) -> Result<Route, AlgorithmError>;
+    fn compute_route(&self, control: &ComputeControl) -> Result<Route, AlgorithmError>;
 
     /// Outputs the score collected by a route computed by this algorithm
     fn get_collected_score(&self, route: &Route) -> usize;
 
+    /// Outputs the seed used for the route computation, such that the route can be computed
+    /// again exactly
+    fn get_seed(&self) -> u64;
+
     /// Returns a reference to this concrete implementation of the `_Algorithm` trait
     /// as a generic trait object
     fn as_algorithm(&'a self) -> &'a dyn _Algorithm where Self: Sized {
@@ -260,13 +839,17 @@ trait _Algorithm<'a> {
 pub enum Algorithm<'a> {
     Greedy(GreedyAlgorithm<'a>),
     SimAnnealing(SimAnnealingLinYu<'a>),
+    IteratedLocalSearch(IteratedLocalSearch<'a>),
+    BranchAndBound(BranchAndBound<'a>),
 }
 
 impl<'a> Algorithm<'a> {
     /// List of available algorithms
-    const AVAILABLE_ALGORITHMS: [&'static str; 2] = [
+    const AVAILABLE_ALGORITHMS: [&'static str; 4] = [
         GreedyAlgorithm::ALGORITHM_NAME,
-        SimAnnealingLinYu::ALGORITHM_NAME
+        SimAnnealingLinYu::ALGORITHM_NAME,
+        IteratedLocalSearch::ALGORITHM_NAME,
+        BranchAndBound::ALGORITHM_NAME
     ];
 
     /// Returns a list of available algorithms specified by their respective names
@@ -274,6 +857,18 @@ impl<'a> Algorithm<'a> {
         &Self::AVAILABLE_ALGORITHMS
     }
 
+    /// Returns a short description of the algorithm with the provided `algorithm_name` in a
+    /// `Some` or `None` if the specified name is unknown
+    pub fn algorithm_description(algorithm_name: &str) -> Option<&'static str> {
+        match algorithm_name {
+            GreedyAlgorithm::ALGORITHM_NAME => Some(GreedyAlgorithm::ALGORITHM_DESCRIPTION),
+            SimAnnealingLinYu::ALGORITHM_NAME => Some(SimAnnealingLinYu::ALGORITHM_DESCRIPTION),
+            IteratedLocalSearch::ALGORITHM_NAME => Some(IteratedLocalSearch::ALGORITHM_DESCRIPTION),
+            BranchAndBound::ALGORITHM_NAME => Some(BranchAndBound::ALGORITHM_DESCRIPTION),
+            _ => None
+        }
+    }
+
     /// Create a new algorithm instance with the provided `algorithm_name`
     ///
     /// # Arguments
@@ -283,24 +878,43 @@ impl<'a> Algorithm<'a> {
     /// * `end_time` - The intended end time of the walk
     /// * `walking_speed_mps` - The walking speed in meters per second
     /// * `area` - The area in which the walking route should lie
+    /// * `destination` - The location where the walk should end. If `None`, the walk ends where
+    ///   it started, i.e., at the center of `area`.
     /// * `user_prefs` - The users preferences for sight categories and sights, respectively
+    /// * `params` - The tuning parameters of the algorithms
+    /// * `seed` - The seed for all randomness of the route computation. A random seed is chosen
+    ///   if `None`.
     ///
     /// # Returns
     /// * an `Ok` containing a new algorithm instance with the provided `algorithm_name`
     /// if such an algorithm exists, or
-    /// * an `Err` containing an `AlgorithmError`, if the specified name is unknown
+    /// * an `Err` containing an `AlgorithmError`, if the specified name is unknown or `params`
+    ///   are invalid
+    #[allow(clippy::too_many_arguments)]
     pub fn from_name(algorithm_name: &str,
                      graph: &'a Graph,
                      start_time: DateTime<Utc>,
                      end_time: DateTime<Utc>,
                      walking_speed_mps: f64,
                      area: Area,
-                     user_prefs: UserPreferences) -> Result<Self, AlgorithmError> {
+                     destination: Option<Coordinate>,
+                     user_prefs: UserPreferences,
+                     params: AlgorithmParams,
+                     seed: Option<u64>) -> Result<Self, AlgorithmError> {
+        params.validate()?;
         match algorithm_name {
             GreedyAlgorithm::ALGORITHM_NAME => Ok(Self::Greedy(GreedyAlgorithm::new(
-                graph, start_time, end_time, walking_speed_mps, area, user_prefs)?)),
+                graph, start_time, end_time, walking_speed_mps, area, destination, user_prefs,
+                params, seed)?)),
             SimAnnealingLinYu::ALGORITHM_NAME => Ok(Self::SimAnnealing(SimAnnealingLinYu::new(
-                graph, start_time, end_time, walking_speed_mps, area, user_prefs)?)),
+                graph, start_time, end_time, walking_speed_mps, area, destination, user_prefs,
+                params, seed)?)),
+            IteratedLocalSearch::ALGORITHM_NAME => Ok(Self::IteratedLocalSearch(IteratedLocalSearch::new(
+                graph, start_time, end_time, walking_speed_mps, area, destination, user_prefs,
+                params, seed)?)),
+            BranchAndBound::ALGORITHM_NAME => Ok(Self::BranchAndBound(BranchAndBound::new(
+                graph, start_time, end_time, walking_speed_mps, area, destination, user_prefs,
+                params, seed)?)),
             unknown_name => Err(AlgorithmError::UnknownAlgorithm {
                 unknown_name: unknown_name.to_string(),
             })
@@ -313,34 +927,46 @@ impl<'a> Algorithm<'a> {
         match self {
             Self::Greedy(inner) => inner.as_algorithm(),
             Self::SimAnnealing(inner) => inner.as_algorithm(),
+            Self::IteratedLocalSearch(inner) => inner.as_algorithm(),
+            Self::BranchAndBound(inner) => inner.as_algorithm(),
         }
     }
 
     /// Compute a route on a graph that visits tourist attractions in a specific area based on
-    /// user preferences for these tourist attractions
+    /// user preferences for these tourist attractions.
+    /// Returns the best feasible route found so far as soon as `control` asks to stop.
     ///
     /// # Returns
     /// * an `Ok` containing the computed route in case of no errors, or
     /// * an `Err` containing an `AlgorithmError`, otherwise
-    pub fn compute_route(&self) -> Result<Route, AlgorithmError> {
-        self.inner().compute_route()
+    pub fn compute_route(&self, control: &ComputeControl) -> Result<Route, AlgorithmError> {
+        self.inner().compute_route(control)
     }
 
     /// Outputs the score collected by a route computed by this algorithm
     pub fn get_collected_score(&self, route: &Route) -> usize {
         self.inner().get_collected_score(route)
     }
+
+    /// Outputs the seed used for the route computation, such that the route can be computed
+    /// again exactly
+    pub fn get_seed(&self) -> u64 {
+        self.inner().get_seed()
+    }
 }
 
-/// Compute wait and service time for given sight based on the already used time budget
-fn compute_wait_and_service_time(start_time: &DateTime<Utc>, end_time: &DateTime<Utc>, sight: &Sight,
-                                 used_time_budget: i64, root_travel_time: i64) -> Option<(i64, i64)> {
+/// Compute wait and service time for given sight based on the already used time budget and the
+/// time needed to travel from the sight to the end of the route.
+/// Since opening hours refer to local time, `start_time` and `end_time` are expected in the
+/// timezone of the graphs region.
+fn compute_wait_and_service_time(start_time: &DateTime<Tz>, end_time: &DateTime<Tz>, sight: &Sight,
+                                 used_time_budget: i64, end_travel_time: i64) -> Option<(i64, i64)> {
     let time_window = sight.opening_hours();
 
-    // Determine current time (after given used time budget)
-    let curr_time = start_time.naive_utc() + Duration::seconds(used_time_budget);
-    // Determine latest time such that root is still reachable
-    let latest_time = end_time.naive_utc() - Duration::seconds(root_travel_time);
+    // Determine current local time (after given used time budget)
+    let curr_time = (*start_time + Duration::seconds(used_time_budget)).naive_local();
+    // Determine latest local time such that the end of the route is still reachable
+    let latest_time = (*end_time - Duration::seconds(end_travel_time)).naive_local();
     if curr_time >= latest_time  {
         // Stay at sight not possible due to time restrictions
         return None;
@@ -418,6 +1044,110 @@ fn compute_wait_and_service_time(start_time: &DateTime<Utc>, end_time: &DateTime
     }
 }
 
+/// Maximum number of mandatory sights for which all visiting orders are considered. For more
+/// mandatory sights, only the nearest neighbour order is checked.
+const MAX_EXACT_MANDATORY_SIGHTS: usize = 12;
+
+/// Finds an order in which all `mandatory` sights can be visited within the time budget and
+/// opening hours, if the route already reached the node with id `curr_node_id` after
+/// `used_time_budget` seconds.
+/// Orders are enumerated by dynamic programming over subsets of the mandatory sights, keeping only
+/// the earliest time at which each subset can be visited ending at each sight. Since waiting at a
+/// sight is allowed, arriving earlier is never worse, i.e., no order is missed.
+///
+/// # Arguments
+/// * `start_time` - The start time of the walk in the local time of the graphs region
+/// * `end_time` - The end time of the walk in the local time of the graphs region
+/// * `mandatory` - The mandatory sights that have not been visited yet
+/// * `curr_node_id` - The id of the node where the route currently ends
+/// * `used_time_budget` - The number of seconds passed since the start of the walk
+/// * `end_id` - The id of the node where the route has to end
+/// * `travel_time` - Outputs the number of seconds to walk between two nodes, if connected
+///
+/// # Returns
+/// * `Some` order of the mandatory sights, if all of them can be visited, or
+/// * `None`, otherwise
+fn find_mandatory_order<'a, F>(start_time: &DateTime<Tz>, end_time: &DateTime<Tz>,
+                               mandatory: &[&'a Sight], curr_node_id: usize,
+                               used_time_budget: i64, end_id: usize,
+                               travel_time: F) -> Option<Vec<&'a Sight>>
+    where F: Fn(usize, usize) -> Option<i64> {
+    // Outputs the used time budget after visiting the `next` mandatory sight
+    let visit = |from_id: usize, used_time_budget: i64, next: usize| {
+        let sight = mandatory[next];
+        let sight_travel_time = travel_time(from_id, sight.node_id)?;
+        let end_travel_time = travel_time(sight.node_id, end_id)?;
+        compute_wait_and_service_time(start_time, end_time, sight,
+                                      used_time_budget + sight_travel_time, end_travel_time)
+            .map(|(wait_time, service_time)|
+                used_time_budget + sight_travel_time + wait_time + service_time)
+    };
+
+    let num_mandatory = mandatory.len();
+    if num_mandatory == 0 {
+        return Some(vec![]);
+    }
+    if num_mandatory > MAX_EXACT_MANDATORY_SIGHTS {
+        let mut unvisited = (0..num_mandatory).collect_vec();
+        let mut order = vec![];
+        let (mut curr_node_id, mut used_time_budget) = (curr_node_id, used_time_budget);
+        while !unvisited.is_empty() {
+            let (index, next_used_time_budget) = unvisited.iter().enumerate()
+                .filter_map(|(index, &next)| travel_time(curr_node_id, mandatory[next].node_id)
+                    .map(|sight_travel_time| (index, next, sight_travel_time)))
+                .sorted_unstable_by_key(|&(_, next, sight_travel_time)|
+                    (sight_travel_time, mandatory[next].node_id))
+                .find_map(|(index, next, _)| visit(curr_node_id, used_time_budget, next)
+                    .map(|next_used_time_budget| (index, next_used_time_budget)))?;
+            let next = unvisited.swap_remove(index);
+            order.push(mandatory[next]);
+            curr_node_id = mandatory[next].node_id;
+            used_time_budget = next_used_time_budget;
+        }
+        return Some(order);
+    }
+
+    // earliest[subset][last] holds the earliest used time budget after visiting all sights of
+    // `subset` ending at `last`, together with the sight visited before `last`
+    let num_subsets = 1 << num_mandatory;
+    let mut earliest = vec![vec![None; num_mandatory]; num_subsets];
+    for first in 0..num_mandatory {
+        earliest[1 << first][first] = visit(curr_node_id, used_time_budget, first)
+            .map(|time| (time, None::<usize>));
+    }
+    for subset in 1..num_subsets {
+        for last in 0..num_mandatory {
+            let time = match earliest[subset][last] {
+                Some((time, _)) => time,
+                None => continue,
+            };
+            for next in (0..num_mandatory).filter(|next| subset & (1 << next) == 0) {
+                if let Some(next_time) = visit(mandatory[last].node_id, time, next) {
+                    let entry = &mut earliest[subset | (1 << next)][next];
+                    if !matches!(entry, Some((prev_time, _)) if *prev_time <= next_time) {
+                        *entry = Some((next_time, Some(last)));
+                    }
+                }
+            }
+        }
+    }
+
+    let all = num_subsets - 1;
+    let mut last = (0..num_mandatory)
+        .filter_map(|last| earliest[all][last].map(|(time, _)| (time, last)))
+        .min()
+        .map(|(_, last)| last)?;
+    let mut subset = all;
+    let mut order = vec![mandatory[last]];
+    while let Some((_, Some(prev))) = earliest[subset][last] {
+        subset &= !(1 << last);
+        last = prev;
+        order.push(mandatory[last]);
+    }
+    order.reverse();
+    Some(order)
+}
+
 /// Error type of `algorithm` module
 #[derive(Debug, Display, Error)]
 pub enum AlgorithmError {
@@ -451,16 +1181,69 @@ pub enum AlgorithmError {
     /// Error indicating that no node has been found in the requested area
     #[display(fmt = "No nearest node found in requested area")]
     NoNearestNodeFound,
+    /// Error indicating that sights the user must visit cannot be included in the route, because
+    /// they are out of reach or the time budget or their opening hours do not allow a visit
+    #[display(fmt = "Mandatory sights cannot be visited: {}", "sights.join(\", \")")]
+    MandatorySightsNotFeasible {
+        sights: Vec<String>,
+    },
+    /// Error indicating that an exact algorithm has been requested for an instance with too many
+    /// candidate sights
+    #[display(fmt = "Too many candidate sights for exact algorithm: {} (at most {})", num_candidates, max_candidates)]
+    TooManyCandidates {
+        num_candidates: usize,
+        max_candidates: usize,
+    },
+    /// Error indicating that an algorithm parameter lies outside of its safe range
+    #[display(fmt = "Invalid value {} for algorithm parameter {} (allowed: {} to {})", value, name, min, max)]
+    InvalidParameter {
+        name: String,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// Error indicating that the requested destination is too far away from the start to be
+    /// reached within the requested time interval
+    #[display(fmt = "Destination is not reachable within time interval")]
+    DestinationNotReachable,
+    /// Error indicating that the computation was stopped before any route visiting all mandatory
+    /// sights was found
+    #[display(fmt = "Computation stopped before a feasible route was found ({:?})", reason)]
+    StoppedWithoutFeasibleRoute {
+        #[error(not(source))]
+        reason: TerminationReason,
+    },
+}
+
+impl AlgorithmError {
+    /// Creates a `MandatorySightsNotFeasible` error that names the sights with the given ids
+    fn mandatory_sights_not_feasible(graph: &Graph, sight_ids: &[usize]) -> Self {
+        let sights = sight_ids.iter()
+            .sorted()
+            .map(|&sight_id| match graph.sights.iter().find(|sight| sight.node_id == sight_id) {
+                Some(sight) if !sight.name.is_empty() => format!("{} ({})", sight.name, sight_id),
+                _ => sight_id.to_string(),
+            })
+            .collect();
+        Self::MandatorySightsNotFeasible { sights }
+    }
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
+    use std::collections::HashMap;
     use chrono::{DateTime, Duration, Utc};
     use itertools::Itertools;
     use once_cell::sync::Lazy;
     use opening_hours_syntax::rules::RuleKind;
-    use crate::algorithm::{Algorithm, Area, Route, RouteSector, Sector, SightCategoryPref, UserPreferences};
-    use crate::data::graph::Category;
+    use chrono_tz::Europe::Berlin;
+    use opening_hours::OpeningHours;
+    use geoutils::Location;
+    use crate::algorithm::greedy::GreedyAlgorithm;
+    use crate::algorithm::ils::{IlsParams, IteratedLocalSearch};
+    use crate::algorithm::sa_lin_yu::{SimAnnealingLinYu, SimAnnealingParams};
+    use crate::algorithm::{Algorithm, AlgorithmError, AlgorithmParams, Area, ComputeControl, compute_wait_and_service_time, Coordinate, find_mandatory_order, Route, RouteSector, RouteSummary, SearchSpace, Sector, SightCategoryPref, SightPref, TerminationReason, UserPreferences};
+    use crate::data::graph::{Category, Sight, SightExclusions};
     use crate::init_logging;
     use crate::utils::test_setup;
 
@@ -469,9 +1252,31 @@ mod test {
     /// End time of trip or hike used for testing
     pub const END_TIME: &str = "2022-07-01T20:00:00+01:00";
 
+    /// Seed used for testing, such that failing tests can be reproduced
+    pub const SEED: u64 = 42;
+
     /// https://www.youtube.com/watch?v=ExElCQwN3T8
     pub const WALKING_SPEED_MPS: f64 = 5.0 / 3.6;
 
+    /// Lazily initialized greedy algorithm instance around the hotel, preferring sightseeing
+    static GREEDY_AROUND_HOTEL: Lazy<Algorithm> = Lazy::new(|| {
+        Algorithm::from_name(
+            GreedyAlgorithm::ALGORITHM_NAME, &test_setup::GRAPH,
+            DateTime::parse_from_rfc3339(START_TIME).unwrap().with_timezone(&Utc),
+            DateTime::parse_from_rfc3339(END_TIME).unwrap().with_timezone(&Utc),
+            WALKING_SPEED_MPS, RADISSON_BLU_HOTEL, None,
+            UserPreferences::from_category_and_sight_prefs(
+                vec![SightCategoryPref::new(Category::Sightseeing, 5)], vec![]),
+            AlgorithmParams::default(), Some(SEED)
+        ).unwrap()
+    });
+
+    /// Compute a greedy route around the hotel, used for testing the export of routes
+    pub(crate) fn greedy_route_around_hotel() -> Route<'static> {
+        GREEDY_AROUND_HOTEL.compute_route(&ComputeControl::default())
+            .expect("Error during route computation")
+    }
+
     /// Baba Hotel, ich schwör!!
     const RADISSON_BLU_HOTEL: Area = Area {
         lat: 53.074448,
@@ -479,6 +1284,12 @@ mod test {
         radius: 300.0,
     };
 
+    /// Bremen main station
+    const BREMEN_HBF: Coordinate = Coordinate {
+        lat: 53.083656,
+        lon: 8.813646,
+    };
+
     /// User category preferences used for testing
     const CATEGORY_PREFS: [SightCategoryPref; 3] = [
         SightCategoryPref { category: Category::Sightseeing, pref: 5 },
@@ -495,15 +1306,32 @@ mod test {
         let user_prefs = UserPreferences {
             categories: CATEGORY_PREFS.to_vec(),
             sights: vec![],
+            exclude: SightExclusions::default(),
         };
-        Algorithm::available_algorithms().iter().map(|&algo_name|
-            Algorithm::from_name(
+        Algorithm::available_algorithms().iter().filter_map(|&algo_name|
+            skip_too_many_candidates(algo_name, Algorithm::from_name(
                 algo_name, &test_setup::GRAPH, start_time, end_time,
-                WALKING_SPEED_MPS, RADISSON_BLU_HOTEL, user_prefs.clone()
-            ).unwrap()
+                WALKING_SPEED_MPS, RADISSON_BLU_HOTEL, None, user_prefs.clone(),
+                AlgorithmParams::default(), Some(SEED)
+            ))
         ).collect_vec()
     });
 
+    /// Unwrap the result of creating an instance of the algorithm with given name, returning
+    /// `None` if the instance exceeds the candidate limit of an exact algorithm.
+    /// Branch and bound is skipped this way around the hotel and is checked against a smaller
+    /// area in its own module instead.
+    fn skip_too_many_candidates<'a>(algo_name: &str, result: Result<Algorithm<'a>, AlgorithmError>)
+        -> Option<Algorithm<'a>> {
+        match result {
+            Err(err @ AlgorithmError::TooManyCandidates {..}) => {
+                log::warn!("Skipping {} in test: {}", algo_name, err);
+                None
+            },
+            result => Some(result.unwrap()),
+        }
+    }
+
     /// Run given test with each algorithm instance in `ALGORITHMS`
     fn run_test_with_each_algorithm<T>(test: T) where T: Fn(&Algorithm) {
         init_logging();
@@ -512,7 +1340,7 @@ mod test {
 
     /// Compute a walking route with given algorithm and `panic` if the computed route is empty
     fn compute_route_with_empty_check<'a>(algo: &'a Algorithm) -> Route<'a> {
-        let route = algo.compute_route().expect("Error during route computation");
+        let route = algo.compute_route(&ComputeControl::default()).expect("Error during route computation");
         if route.is_empty() {
             panic!("Route empty");
         }
@@ -558,11 +1386,187 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_route_within_time_budget_after_deadline() {
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        run_test_with_each_algorithm(|algo| {
+            let control = ComputeControl::with_max_compute_ms(0);
+            let route = algo.compute_route(&control).expect("Error during route computation");
+            let route_end_time = match route.last().unwrap() {
+                RouteSector::End(end_sector) => end_sector.time_of_arrival,
+                _ => panic!("Last sector must be end sector")
+            };
+            assert!(route_end_time <= end_time, "Route travel time exceeds available budget");
+            assert_eq!(control.termination_reason(), TerminationReason::DeadlineReached);
+        });
+    }
+
+    #[test]
+    fn test_route_summary_accounts_for_whole_time_budget() {
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap();
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap();
+        run_test_with_each_algorithm(|algo| {
+            let route = compute_route_with_empty_check(algo);
+            let summary = RouteSummary::new(&test_setup::GRAPH, &route, &start_time, &end_time,
+                                            algo.get_collected_score(&route));
+
+            let num_sights = route.iter()
+                .filter(|route_sector| route_sector.sight_sector().is_some())
+                .count();
+            assert_eq!(summary.sights_per_category.values().sum::<usize>(), num_sights);
+            assert_eq!(summary.walking_secs + summary.waiting_secs + summary.service_secs
+                           + summary.slack_secs,
+                       end_time.signed_duration_since(start_time).num_seconds());
+            assert!(summary.walking_secs >= 0 && summary.waiting_secs >= 0
+                && summary.service_secs >= 0 && summary.slack_secs >= 0);
+            assert!(summary.total_distance_meters > 0);
+            // the travel time of each sector is its distance divided by the walking speed,
+            // rounded to full seconds
+            let distance_secs = summary.total_distance_meters as f64 / WALKING_SPEED_MPS;
+            assert!((summary.walking_secs as f64 - distance_secs).abs() <= route.len() as f64,
+                    "Walking time {} s does not match distance {} m", summary.walking_secs,
+                    summary.total_distance_meters);
+        });
+    }
+
+    #[test]
+    fn test_route_within_time_budget_after_cancel() {
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        run_test_with_each_algorithm(|algo| {
+            let control = ComputeControl::default();
+            control.cancel();
+            let route = algo.compute_route(&control).expect("Error during route computation");
+            let route_end_time = match route.last().unwrap() {
+                RouteSector::End(end_sector) => end_sector.time_of_arrival,
+                _ => panic!("Last sector must be end sector")
+            };
+            assert!(route_end_time <= end_time, "Route travel time exceeds available budget");
+            assert_eq!(control.termination_reason(), TerminationReason::Cancelled);
+        });
+    }
+
+    #[test]
+    fn test_reported_best_score_matches_route() {
+        run_test_with_each_algorithm(|algo| {
+            let control = ComputeControl::default();
+            let route = algo.compute_route(&control).expect("Error during route computation");
+            assert_eq!(control.best_score(), algo.get_collected_score(&route));
+        });
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_route() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![],
+            exclude: SightExclusions::default(),
+        };
+        let create_algo = |algo_name, seed| skip_too_many_candidates(algo_name, Algorithm::from_name(
+            algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+            RADISSON_BLU_HOTEL, None, user_prefs.clone(), AlgorithmParams::default(), seed
+        ));
+        let sight_ids = |algo: &Algorithm| algo.compute_route(&ComputeControl::default())
+            .expect("Error during route computation")
+            .iter()
+            .filter_map(|route_sector| route_sector.sight_sector()
+                .map(|sector| sector.sight.node_id))
+            .collect_vec();
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let algo = match create_algo(algo_name, None) {
+                Some(algo) => algo,
+                None => continue,
+            };
+            let replayed_algo = create_algo(algo_name, Some(algo.get_seed())).unwrap();
+            assert_eq!(replayed_algo.get_seed(), algo.get_seed());
+            assert_eq!(sight_ids(&replayed_algo), sight_ids(&algo),
+                       "{} route with seed {} could not be reproduced", algo_name, algo.get_seed());
+        }
+    }
+
+    #[test]
+    fn test_time_limit_reported_as_deadline() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![],
+            exclude: SightExclusions::default(),
+        };
+        let params = AlgorithmParams {
+            sim_annealing: SimAnnealingParams {
+                alpha: 0.99,
+                max_time_ms: 1,
+                n_non_improving: 100,
+                ..SimAnnealingParams::default()
+            },
+            ils: IlsParams {
+                max_non_improving: 10_000,
+                max_time_ms: 1,
+            },
+        };
+
+        // Only these algorithms are limited in their computation time by `params`
+        for algo_name in [SimAnnealingLinYu::ALGORITHM_NAME, IteratedLocalSearch::ALGORITHM_NAME] {
+            let algo = Algorithm::from_name(
+                algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+                RADISSON_BLU_HOTEL, None, user_prefs.clone(), params.clone(), Some(SEED)
+            ).unwrap();
+            let control = ComputeControl::default();
+            algo.compute_route(&control).expect("Error during route computation");
+            assert_eq!(control.termination_reason(), TerminationReason::DeadlineReached,
+                       "{} did not report reaching its time limit", algo_name);
+        }
+    }
+
+    #[test]
+    fn test_opening_hours_evaluated_in_local_time() {
+        let sight = Sight {
+            node_id: 0,
+            lat: RADISSON_BLU_HOTEL.lat,
+            lon: RADISSON_BLU_HOTEL.lon,
+            category: Category::Sightseeing,
+            name: "Museum".to_string(),
+            opening_hours: "Mo-Fr 10:00-18:00".to_string(),
+            opening_hours_parsed: Some(OpeningHours::parse("Mo-Fr 10:00-18:00").unwrap()),
+            duration_of_stay_minutes: 60,
+            wikidata_id: String::new(),
+            tags: HashMap::new(),
+        };
+        // 07:30 UTC is 09:30 in Berlin (CEST), i.e., half an hour before the museum opens
+        let start_time = DateTime::parse_from_rfc3339("2022-07-01T07:30:00Z").unwrap()
+            .with_timezone(&Berlin);
+        let end_time = DateTime::parse_from_rfc3339("2022-07-01T18:00:00Z").unwrap()
+            .with_timezone(&Berlin);
+        let (wait_time, service_time) = compute_wait_and_service_time(
+            &start_time, &end_time, &sight, 0, 0).unwrap();
+        assert_eq!(wait_time, 30 * 60);
+        assert_eq!(service_time, 60 * 60);
+
+        // 15:30 UTC is 17:30 in Berlin, i.e., only half an hour before the museum closes
+        let (wait_time, service_time) = compute_wait_and_service_time(
+            &start_time, &end_time, &sight, 8 * 60 * 60, 0).unwrap();
+        assert_eq!(wait_time, 0);
+        assert_eq!(service_time, 30 * 60);
+    }
+
     #[test]
     fn test_sights_on_route_within_opening_time() {
         let sector_ok = |sector: &Sector| {
-            let service_start_time = sector.service_start_time.naive_utc();
-            let service_end_time = sector.service_end_time.naive_utc();
+            let service_start_time = sector.service_start_time.naive_local();
+            let service_end_time = sector.service_end_time.naive_local();
             let sight_opening_hours = sector.sight.opening_hours();
             let state_at_start = sight_opening_hours.state(
                 service_start_time).unwrap();
@@ -586,4 +1590,226 @@ mod test {
             }
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_route_ends_at_destination() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![],
+            exclude: SightExclusions::default(),
+        };
+        let end_id = test_setup::GRAPH.get_nearest_node(BREMEN_HBF.lat, BREMEN_HBF.lon);
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let algo = match skip_too_many_candidates(algo_name, Algorithm::from_name(
+                algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+                RADISSON_BLU_HOTEL, Some(BREMEN_HBF), user_prefs.clone(),
+                AlgorithmParams::default(), Some(SEED)
+            )) {
+                Some(algo) => algo,
+                None => continue,
+            };
+            let route = compute_route_with_empty_check(&algo);
+            let end_sector = match route.last().unwrap() {
+                RouteSector::End(end_sector) => end_sector,
+                _ => panic!("Last sector must be end sector")
+            };
+            let last_node = end_sector.nodes.last().expect("End sector without nodes");
+            assert_eq!(last_node.id, end_id, "{} route does not end at destination", algo_name);
+            assert!(end_sector.time_of_arrival <= end_time,
+                    "{} route arrives at destination after end time", algo_name);
+        }
+    }
+
+    #[test]
+    fn test_route_with_contraction_hierarchy() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![],
+            exclude: SightExclusions::default(),
+        };
+        let graph = &test_setup::CONTRACTED_GRAPH;
+        assert!(graph.contraction_hierarchy().is_some(), "Test graph has no contraction hierarchy");
+        let root_id = graph.get_nearest_node(RADISSON_BLU_HOTEL.lat, RADISSON_BLU_HOTEL.lon);
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let algo = match skip_too_many_candidates(algo_name, Algorithm::from_name(
+                algo_name, graph, start_time, end_time, WALKING_SPEED_MPS,
+                RADISSON_BLU_HOTEL, None, user_prefs.clone(),
+                AlgorithmParams::default(), Some(SEED)
+            )) {
+                Some(algo) => algo,
+                None => continue,
+            };
+            let route = compute_route_with_empty_check(&algo);
+            // the sectors form a closed walk along edges of the original graph
+            let mut curr_node_id = root_id;
+            for route_sector in &route {
+                let nodes = route_sector.nodes();
+                assert_eq!(nodes.first().unwrap().id, curr_node_id,
+                           "{} route sector does not start where the previous one ends", algo_name);
+                for pair in nodes.windows(2) {
+                    assert!(test_setup::GRAPH.get_edge(pair[0].id, pair[1].id).is_some(),
+                            "{} route uses missing edge from {} to {}", algo_name, pair[0].id, pair[1].id);
+                }
+                curr_node_id = nodes.last().unwrap().id;
+            }
+            assert_eq!(curr_node_id, root_id, "{} route does not return to the start", algo_name);
+        }
+    }
+
+    #[test]
+    fn test_route_contains_mandatory_sight() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        // Pick a nearby sight whose category the user has no preference for
+        let mandatory_sight = test_setup::GRAPH.get_reachable_sights_in_area(
+            RADISSON_BLU_HOTEL.lat, RADISSON_BLU_HOTEL.lon, RADISSON_BLU_HOTEL.radius,
+            RADISSON_BLU_HOTEL.radius * 2.0, &SightExclusions::default())
+            .into_iter()
+            .find(|sight| CATEGORY_PREFS.iter()
+                .all(|category_pref| category_pref.category != sight.category))
+            .expect("No sight without category preference found");
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![SightPref::new_must_visit(mandatory_sight.node_id)],
+            exclude: SightExclusions::default(),
+        };
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let algo = match skip_too_many_candidates(algo_name, Algorithm::from_name(
+                algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+                RADISSON_BLU_HOTEL, None, user_prefs.clone(),
+                AlgorithmParams::default(), Some(SEED)
+            )) {
+                Some(algo) => algo,
+                None => continue,
+            };
+            let route = compute_route_with_empty_check(&algo);
+            let contains_mandatory_sight = route.iter().any(|route_sector| match route_sector {
+                RouteSector::Start(sector) | RouteSector::Intermediate(sector) =>
+                    sector.sight.node_id == mandatory_sight.node_id,
+                _ => false
+            });
+            assert!(contains_mandatory_sight, "{} route does not contain mandatory sight {}",
+                    algo_name, mandatory_sight.node_id);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_mandatory_sight() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        // Pick a sight that is too far away to be visited within the time interval
+        let hotel = Location::new(RADISSON_BLU_HOTEL.lat, RADISSON_BLU_HOTEL.lon);
+        let mandatory_sight = test_setup::GRAPH.sights.iter()
+            .find(|sight| Location::new(sight.lat, sight.lon)
+                .haversine_distance_to(&hotel).meters() > 10_000.0)
+            .expect("No distant sight found");
+        let user_prefs = UserPreferences {
+            categories: CATEGORY_PREFS.to_vec(),
+            sights: vec![SightPref::new_must_visit(mandatory_sight.node_id)],
+            exclude: SightExclusions::default(),
+        };
+
+        for &algo_name in Algorithm::available_algorithms() {
+            let result = Algorithm::from_name(
+                algo_name, &test_setup::GRAPH, start_time, end_time, WALKING_SPEED_MPS,
+                RADISSON_BLU_HOTEL, None, user_prefs.clone(), AlgorithmParams::default(),
+                Some(SEED));
+            match result {
+                Err(AlgorithmError::MandatorySightsNotFeasible { sights }) => {
+                    assert_eq!(sights.len(), 1);
+                    assert!(sights[0].contains(&mandatory_sight.node_id.to_string()));
+                }
+                _ => panic!("{} accepted unreachable mandatory sight", algo_name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_mandatory_order_beyond_nearest_neighbour() {
+        let sight = |node_id: usize, opening_hours: &str, duration_of_stay_minutes: i64| Sight {
+            node_id,
+            lat: RADISSON_BLU_HOTEL.lat,
+            lon: RADISSON_BLU_HOTEL.lon,
+            category: Category::Sightseeing,
+            name: format!("Sight {}", node_id),
+            opening_hours: opening_hours.to_string(),
+            opening_hours_parsed: Some(OpeningHours::parse(opening_hours).unwrap()),
+            duration_of_stay_minutes,
+            wikidata_id: String::new(),
+            tags: HashMap::new(),
+        };
+        // The nearest sight is always open, the farther one only for half an hour
+        let near = sight(1, "24/7", 60);
+        let far = sight(2, "Mo 10:00-10:30", 20);
+        let travel_time = |from_id: usize, to_id: usize| Some(match (from_id.min(to_id), from_id.max(to_id)) {
+            (from, to) if from == to => 0,
+            (0, 1) => 60,
+            _ => 600,
+        });
+        // 2022-07-04 is a Monday, 08:00 UTC is 10:00 in Berlin
+        let start_time = DateTime::parse_from_rfc3339("2022-07-04T08:00:00Z").unwrap()
+            .with_timezone(&Berlin);
+        let end_time = DateTime::parse_from_rfc3339("2022-07-04T12:00:00Z").unwrap()
+            .with_timezone(&Berlin);
+
+        // Visiting the nearest sight first misses the opening hours of the farther one
+        assert!(find_mandatory_order(&start_time, &end_time, &[&far], 1, 61 + 60 * 60, 0,
+                                     travel_time).is_none());
+        let order = find_mandatory_order(&start_time, &end_time, &[&near, &far], 0, 0, 0,
+                                         travel_time)
+            .expect("Mandatory sights are feasible in reverse order");
+        assert_eq!(order.iter().map(|sight| sight.node_id).collect_vec(), vec![2, 1]);
+
+        // Both sights cannot be visited if the walk starts after the farther one closed
+        assert!(find_mandatory_order(&start_time, &end_time, &[&near, &far], 0, 60 * 60, 0,
+                                     travel_time).is_none());
+    }
+
+    #[test]
+    fn test_search_space_within_reachable_radius() {
+        init_logging();
+
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = start_time + Duration::minutes(30);
+        let graph = &test_setup::GRAPH;
+        let search_space = SearchSpace::new(graph, &start_time, &end_time, WALKING_SPEED_MPS,
+                                            &RADISSON_BLU_HOTEL, None, &SightExclusions::default())
+            .expect("Error during search space computation");
+
+        // A round trip of 30 minutes walks at most 15 minutes away from its start
+        let max_walking_dist = WALKING_SPEED_MPS * 15.0 * 60.0;
+        assert!(search_space.edge_area.radius > 0.0
+                    && search_space.edge_area.radius <= max_walking_dist * 1.01,
+                "Unexpected edge area radius {}", search_space.edge_area.radius);
+        let center = Location::new(search_space.edge_area.lat, search_space.edge_area.lon);
+        for sight in &search_space.sights {
+            assert!(center.haversine_distance_to(&Location::new(sight.lat, sight.lon)).meters()
+                        <= search_space.edge_area.radius,
+                    "Sight {} lies outside of the edge area", sight.node_id);
+        }
+    }
+}
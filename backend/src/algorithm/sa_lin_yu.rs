@@ -1,37 +1,117 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use itertools::Itertools;
 use pathfinding::prelude::*;
 use rand::prelude::*;
-use crate::algorithm::{_Algorithm, AlgorithmError, Area, compute_wait_and_service_time, EndSector, Route, RouteSector, ScoreMap, Sector, USER_PREF_MAX, UserPreferences};
-use crate::data::graph::{Graph, Sight};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, ComputeControl, Progress, resolve_seed, Area, check_param_range, compute_wait_and_service_time, Coordinate, find_mandatory_order, EndSector, Route, RouteSector, ScoreMap, SearchSpace, Sector, USER_PREF_MAX, UserPreferences};
+use crate::data::graph::{Graph, Node, Sight};
 use std::time::Instant;
 use crate::utils::dijkstra::run_ota_dijkstra_in_area;
 
 /// Simulated Annealing internal user preference to score mapping
 const USER_PREF_TO_SCORE: [usize; USER_PREF_MAX + 1] = [0, 1, 2, 4, 8, 16];
 
-// Constant parameters
-/// Initial temperature
-const T_0: f64 = 0.7;
-/// Multiplier for iterations on a temperature
-const B: usize = 300;
-/// Factor by which the temperature is cooled down
-const ALPHA: f64 = 0.7;
-/// Maximum allowed computation time
-const MAX_TIME: u128 = 60_000;
-/// Number of cooldowns that do not improve the result
-const N_NON_IMPROVING: usize = 5;
+/// Parameters of the simulated annealing. Missing fields are set to their default values when
+/// deserialized.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SimAnnealingParams {
+    /// Initial temperature
+    pub t_0: f64,
+    /// Multiplier for iterations on a temperature
+    pub b: usize,
+    /// Factor by which the temperature is cooled down
+    pub alpha: f64,
+    /// Maximum allowed computation time in milliseconds
+    pub max_time_ms: u64,
+    /// Number of cooldowns that do not improve the result
+    pub n_non_improving: usize,
+    /// Maximum number of sights to consider
+    pub max_num_sights: usize,
+    /// Weight of the score of a sight when selecting the sights to consider
+    pub score_weight: f64,
+    /// Weight of the distance of a sight to the start when selecting the sights to consider
+    pub dist_weight: f64,
+    /// Number of independent annealing chains that run in parallel
+    pub num_chains: usize,
+}
+
+impl Default for SimAnnealingParams {
+    fn default() -> Self {
+        Self {
+            t_0: 0.7,
+            b: 300,
+            alpha: 0.7,
+            max_time_ms: 60_000,
+            n_non_improving: 5,
+            max_num_sights: 100,
+            score_weight: 1.,
+            dist_weight: 1.,
+            num_chains: 1,
+        }
+    }
+}
+
+impl SimAnnealingParams {
+    /// Checks that all parameters lie within their safe ranges
+    ///
+    /// # Returns
+    /// * an empty `Ok`, if all parameters are valid, or
+    /// * an `Err` containing `AlgorithmError::InvalidParameter` for the first invalid parameter,
+    ///   otherwise
+    pub fn validate(&self) -> Result<(), AlgorithmError> {
+        check_param_range("t_0", self.t_0, 0.01, 10.)?;
+        check_param_range("b", self.b as f64, 1., 10_000.)?;
+        check_param_range("alpha", self.alpha, 0.01, 0.99)?;
+        check_param_range("max_time_ms", self.max_time_ms as f64, 1., 120_000.)?;
+        check_param_range("n_non_improving", self.n_non_improving as f64, 1., 100.)?;
+        check_param_range("max_num_sights", self.max_num_sights as f64, 1., 500.)?;
+        check_param_range("score_weight", self.score_weight, 0., 100.)?;
+        check_param_range("dist_weight", self.dist_weight, 0., 100.)?;
+        check_param_range("num_chains", self.num_chains as f64, 1., 64.)?;
+        // The weights are normalized by their sum
+        check_param_range("score_weight + dist_weight", self.score_weight + self.dist_weight,
+                          f64::MIN_POSITIVE, 200.)
+    }
 
-/// Maximum number of sights to consider
-const MAX_NUM_SIGHTS: usize = 100;
+    /// Returns a copy of these parameters in which every parameter that is set in `overrides`
+    /// is replaced by its overriding value
+    pub fn with_overrides(&self, overrides: &SimAnnealingParamsOverrides) -> Self {
+        Self {
+            t_0: overrides.t_0.unwrap_or(self.t_0),
+            b: overrides.b.unwrap_or(self.b),
+            alpha: overrides.alpha.unwrap_or(self.alpha),
+            max_time_ms: overrides.max_time_ms.unwrap_or(self.max_time_ms),
+            n_non_improving: overrides.n_non_improving.unwrap_or(self.n_non_improving),
+            max_num_sights: overrides.max_num_sights.unwrap_or(self.max_num_sights),
+            score_weight: overrides.score_weight.unwrap_or(self.score_weight),
+            dist_weight: overrides.dist_weight.unwrap_or(self.dist_weight),
+            num_chains: overrides.num_chains.unwrap_or(self.num_chains),
+        }
+    }
+}
 
-const SCORE_WEIGHT: f64 = 1.;
-const DIST_WEIGHT: f64 = 1.;
+/// Overrides for the parameters of the simulated annealing, see `SimAnnealingParams`.
+/// Parameters that are not set keep their configured value.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimAnnealingParamsOverrides {
+    pub t_0: Option<f64>,
+    pub b: Option<usize>,
+    pub alpha: Option<f64>,
+    pub max_time_ms: Option<u64>,
+    pub n_non_improving: Option<usize>,
+    pub max_num_sights: Option<usize>,
+    pub score_weight: Option<f64>,
+    pub dist_weight: Option<f64>,
+    pub num_chains: Option<usize>,
+}
 
 /// Compute scores for tourist attractions based on user preferences for categories or specific
 /// tourist attractions, respectively
-fn compute_scores(sights: &Vec<&Sight>, user_prefs: UserPreferences) -> ScoreMap {
+pub(super) fn compute_scores(sights: &Vec<&Sight>, user_prefs: UserPreferences) -> ScoreMap {
     let start = Instant::now();
 
     let mut scores: ScoreMap = sights.iter()
@@ -69,33 +149,127 @@ fn compute_scores(sights: &Vec<&Sight>, user_prefs: UserPreferences) -> ScoreMap
     scores
 }
 
-/// Build a distance map with distances from relevant nodes, i.e. the root node and all sight nodes
-/// with a non-zero score, to all other nodes
-fn build_distance_map<'a>(graph: &'a Graph,
-                          area: &Area,
-                          edge_radius: f64,
-                          sights: &Vec<&'a Sight>,
-                          root_id: usize,
-                          scores: &ScoreMap) -> HashMap<usize, HashMap<usize, (usize, usize)>> {
+/// Keep mandatory sights and the best `params.max_num_sights` of the given `sights` based on their
+/// score and distance to the root node, if there are more than `params.max_num_sights` sights
+pub(super) fn select_best_sights(graph: &Graph,
+                                 edge_area: &Area,
+                                 sights: &mut Vec<&Sight>,
+                                 root_id: usize,
+                                 scores: &ScoreMap,
+                                 mandatory: &HashSet<usize>,
+                                 params: &SimAnnealingParams) {
+    if sights.len() <= params.max_num_sights {
+        return;
+    }
+
+    let result_from_root = run_ota_dijkstra_in_area(graph, root_id, edge_area.lat,
+                                                    edge_area.lon, edge_area.radius);
+    let max_score = USER_PREF_TO_SCORE[USER_PREF_MAX] as f64;
+    let max_dist = result_from_root.max_dist() as f64;
+    let SimAnnealingParams { score_weight, dist_weight, .. } = *params;
+    sights.sort_unstable_by(|sight1, sight2| {
+        let norm_score1 = scores[&sight1.node_id].0 as f64 / max_score;
+        let norm_score2 = scores[&sight2.node_id].0 as f64 / max_score;
+        // unwrap safety: get_reachable_sights_in_area ensures all sights are reachable
+        let norm_dist1 = 1.0 - result_from_root.dist_to(sight1.node_id).unwrap() as f64 / max_dist;
+        let norm_dist2 = 1.0 - result_from_root.dist_to(sight2.node_id).unwrap() as f64 / max_dist;
+        let metric1 = (score_weight * norm_score1 + dist_weight * norm_dist1)
+            / (score_weight + dist_weight);
+        let metric2 = (score_weight * norm_score2 + dist_weight * norm_dist2)
+            / (score_weight + dist_weight);
+        let mandatory1 = mandatory.contains(&sight1.node_id);
+        let mandatory2 = mandatory.contains(&sight2.node_id);
+        mandatory2.cmp(&mandatory1).then(metric2.total_cmp(&metric1))
+    });
+    sights.truncate(params.max_num_sights.max(mandatory.len()));
+}
+
+/// Walking distances between the relevant nodes of a route computation, i.e. the root node, the
+/// end node and all sight nodes with a non-zero score
+pub(super) enum DistanceMap {
+    /// Results of a Dijkstra from each relevant node to all other nodes in the edge area, which
+    /// map each reached node to its predecessor and distance
+    Dijkstra(HashMap<usize, HashMap<usize, (usize, usize)>>),
+    /// Distance matrix computed with the contraction hierarchy of the graph, whose rows and
+    /// columns are ordered by the indices of the relevant nodes. Its paths may leave the edge area.
+    ContractionHierarchy {
+        indices: HashMap<usize, usize>,
+        dists: Vec<Vec<Option<usize>>>,
+    },
+}
+
+impl DistanceMap {
+    /// Get the distance from the relevant node with id `from` to the node with id `to` in a `Some`
+    /// or `None` if `to` is not reachable from `from`
+    pub(super) fn dist(&self, from: usize, to: usize) -> Option<usize> {
+        if from == to {
+            return Some(0);
+        }
+        match self {
+            DistanceMap::Dijkstra(results) => results[&from].get(&to).map(|&(_, dist)| dist),
+            DistanceMap::ContractionHierarchy { indices, dists } =>
+                dists[indices[&from]][indices[&to]],
+        }
+    }
+
+    /// Get the shortest path from the relevant node with id `from` to the node with id `to`,
+    /// which must be reachable from `from`
+    pub(super) fn path<'a>(&self, graph: &'a Graph, from: usize, to: usize) -> Vec<&'a Node> {
+        match self {
+            DistanceMap::Dijkstra(results) => graph.expand_path(build_path(&to, &results[&from])),
+            DistanceMap::ContractionHierarchy { .. } => graph.contraction_hierarchy()
+                .and_then(|ch| ch.shortest_path(graph, from, to))
+                .expect("Target of path not reachable")
+                .consume_path(),
+        }
+    }
+}
+
+/// Build a distance map with distances from relevant nodes, i.e. the root node, the end node and
+/// all sight nodes with a non-zero score, to all other nodes in the given edge area.
+/// If the graph has a contraction hierarchy, only the distances between the relevant nodes are
+/// computed with it. These paths are not restricted to the edge area, so they may leave it and
+/// are at most as long as the paths within the edge area. The edge area then only determines the
+/// sights that are considered.
+pub(super) fn build_distance_map<'a>(graph: &'a Graph,
+                                     edge_area: &Area,
+                                     sights: &Vec<&'a Sight>,
+                                     root_id: usize,
+                                     end_id: usize,
+                                     scores: &ScoreMap) -> DistanceMap {
     let successors = |node_id: usize|
-        graph.get_outgoing_edges_in_area(node_id, area.lat, area.lon, edge_radius)
+        graph.get_outgoing_edges_in_area(node_id, edge_area.lat, edge_area.lon, edge_area.radius)
             .into_iter()
             .map(|edge| (edge.tgt, edge.dist))
             .collect::<Vec<(usize, usize)>>();
 
     let start = Instant::now();
 
-    let mut distance_map = HashMap::with_capacity(sights.len());
     let mut sights_and_root = sights.iter().map(|&sight| sight.node_id)
         .filter(|sight_id| scores[sight_id].0 > 0).collect_vec();
     sights_and_root.push(root_id);
-
-    for node_id in &sights_and_root {
-        let dijkstra_result = dijkstra_all(
-            node_id,
-            |&node_id| successors(node_id));
-        distance_map.insert(*node_id, dijkstra_result);
+    if end_id != root_id {
+        sights_and_root.push(end_id);
     }
+
+    let distance_map = match graph.contraction_hierarchy() {
+        Some(ch) => DistanceMap::ContractionHierarchy {
+            indices: sights_and_root.iter().enumerate()
+                .map(|(index, &node_id)| (node_id, index))
+                .collect(),
+            dists: ch.many_to_many(&sights_and_root, &sights_and_root),
+        },
+        None => {
+            let mut results = HashMap::with_capacity(sights_and_root.len());
+            for node_id in &sights_and_root {
+                let dijkstra_result = dijkstra_all(
+                    node_id,
+                    |&node_id| successors(node_id));
+                results.insert(*node_id, dijkstra_result);
+            }
+            DistanceMap::Dijkstra(results)
+        }
+    };
     log::debug!("Pre-computed distances from {} relevant nodes in {} ms", sights_and_root.len(),
         start.elapsed().as_millis());
 
@@ -103,8 +277,7 @@ fn build_distance_map<'a>(graph: &'a Graph,
 }
 
 /// Select two indices by random and swap the elements of `current_solution` at these indices
-fn swap<'a>(current_solution: &Vec<&'a Sight>) -> Vec<&'a Sight> {
-    let mut rng = thread_rng();
+fn swap<'a, R: Rng>(current_solution: &Vec<&'a Sight>, rng: &mut R) -> Vec<&'a Sight> {
     let size = current_solution.len();
     let i = rng.gen_range(0..size);
     let j = rng.gen_range(0..size);
@@ -124,8 +297,7 @@ fn determ_insert(current_solution: &mut Vec<&Sight>, i: usize, j: usize) {
 /// Select two indices `i` and `j` by random, insert the element at position `i` in
 /// `current_solution` at position `j` in a new copy of `current_solution` and remove it from its
 /// old position in the copy
-fn insert<'a>(current_solution: &Vec<&'a Sight>) -> Vec<&'a Sight> {
-    let mut rng = thread_rng();
+fn insert<'a, R: Rng>(current_solution: &Vec<&'a Sight>, rng: &mut R) -> Vec<&'a Sight> {
     let size = current_solution.len();
     let i = rng.gen_range(0..size);
     let j = rng.gen_range(0..size);
@@ -137,8 +309,7 @@ fn insert<'a>(current_solution: &Vec<&'a Sight>) -> Vec<&'a Sight> {
 
 /// Select two indices by random and reverse the slice of `current_solution` between these two
 /// indices
-fn reverse<'a>(current_solution: &Vec<&'a Sight>) -> Vec<&'a Sight> {
-    let mut rng = thread_rng();
+fn reverse<'a, R: Rng>(current_solution: &Vec<&'a Sight>, rng: &mut R) -> Vec<&'a Sight> {
     let size = current_solution.len();
     let i = rng.gen_range(0..size);
     let j = rng.gen_range(0..size);
@@ -159,29 +330,58 @@ fn reverse<'a>(current_solution: &Vec<&'a Sight>) -> Vec<&'a Sight> {
 ///
 /// The simulated annealing algorithm tries to find the best route by generating a random initial
 /// solution and improve it with the local operations `swap`, `insert` and `reverse`.
-/// Therefore, we start with the initial temperature `T_0`, which represents the probability with
+/// Therefore, we start with the initial temperature `t_0`, which represents the probability with
 /// which we escape from local maxima.
 /// The temperature will always cool down after a certain amount of iterations and the algorithm
-/// stops and outputs the best solution found so far if it already ran more than `MAX_TIME` seconds.
+/// stops and outputs the best solution found so far if it already ran more than `max_time_ms`
+/// milliseconds. All parameters are given by `SimAnnealingParams`.
+/// If `num_chains` is greater than one, multiple independent annealing chains with different seeds
+/// run in parallel on the rayon thread pool under the same time limit and the best route is
+/// returned.
+/// All random decisions are drawn from generators seeded based on `seed`, so the same seed yields
+/// the same route unless the computation is stopped by the time limit.
 pub struct SimAnnealingLinYu<'a> {
     graph: &'a Graph,
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    /// Start time of the walk in the local time of the graphs region
+    start_time: DateTime<Tz>,
+    /// End time of the walk in the local time of the graphs region
+    end_time: DateTime<Tz>,
     /// Walking speed in meters per second
     walking_speed_mps: f64,
     sights: Vec<&'a Sight>,
     root_id: usize,
+    end_id: usize,
     scores: ScoreMap,
-    distance_map: HashMap<usize, HashMap<usize, (usize, usize)>>,
+    /// Ids of the sights that must be part of the route
+    mandatory: HashSet<usize>,
+    /// Bonus added to the total score of a solution for each mandatory sight it visits. Exceeds
+    /// the sum of all scores, such that solutions visiting more mandatory sights are always better.
+    mandatory_bonus: usize,
+    distance_map: DistanceMap,
+    params: SimAnnealingParams,
+    /// Seed of the random number generator
+    seed: u64,
 }
 
 impl<'a> SimAnnealingLinYu<'a> {
     /// Unique string identifier of this algorithm implementation
     pub const ALGORITHM_NAME: &'static str = "DerAllerbesteste";
+    /// Short description of this algorithm implementation
+    pub const ALGORITHM_DESCRIPTION: &'static str = "Simulated annealing after Lin and Yu (2012) \
+        that improves a random initial route with swap, insert and reverse moves. Slower than \
+        greedy search, but usually collects a higher score";
+
+    /// Outputs the score collected from the sights of a solution, i.e., its total score without
+    /// the bonus for mandatory sights
+    fn collected_score(&self, total_score: usize) -> usize {
+        // the bonus exceeds the sum of all scores
+        total_score % self.mandatory_bonus
+    }
 
     /// Get the total score of `current_solution`.
     /// The total score is computed as the sum of the individual scores of all sights that can be
-    /// included in the route without violating the time budget.
+    /// included in the route without violating the time budget, plus `mandatory_bonus` for each
+    /// of these sights that is mandatory.
     fn get_total_score(&self, current_solution: &Vec<&'a Sight>) -> Result<usize, AlgorithmError> {
         let mut score = 0;
         let total_time_budget = self.end_time.signed_duration_since(self.start_time).num_seconds();
@@ -189,22 +389,23 @@ impl<'a> SimAnnealingLinYu<'a> {
         let mut curr_node_id = self.root_id;
 
         for &sight in current_solution {
-            let curr_distance_map = &self.distance_map[&curr_node_id];
-            let &(_, sight_travel_dist) = curr_distance_map.get(&sight.node_id)
-                .ok_or_else(|| AlgorithmError::NoRouteFound { from: curr_node_id, to: sight.node_id })?;
+            let sight_travel_dist = self.distance_map.dist(curr_node_id, sight.node_id)
+                .ok_or(AlgorithmError::NoRouteFound { from: curr_node_id, to: sight.node_id })?;
             let sight_travel_time = (sight_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
 
-            let sight_distance_map = &self.distance_map[&sight.node_id];
-            let &(_, root_travel_dist) = sight_distance_map.get(&self.root_id)
-                .ok_or_else(|| AlgorithmError::NoRouteFound { from: sight.node_id, to: self.root_id })?;
-            let root_travel_time = (root_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
+            let end_travel_dist = self.distance_map.dist(sight.node_id, self.end_id)
+                .ok_or(AlgorithmError::NoRouteFound { from: sight.node_id, to: self.end_id })?;
+            let end_travel_time = (end_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
 
             let used_time_budget = total_time_budget - left_time_budget + sight_travel_time;
             match compute_wait_and_service_time(
-                &self.start_time, &self.end_time, sight, used_time_budget, root_travel_time) {
+                &self.start_time, &self.end_time, sight, used_time_budget, end_travel_time) {
                 Some((wait_time, service_time)) => {
                     let sight_total_time = sight_travel_time + wait_time + service_time;
                     score += self.scores[&sight.node_id].0;
+                    if self.mandatory.contains(&sight.node_id) {
+                        score += self.mandatory_bonus;
+                    }
                     left_time_budget -= sight_total_time;
                     curr_node_id = sight.node_id;
                 },
@@ -273,174 +474,68 @@ impl<'a> SimAnnealingLinYu<'a> {
         Ok(())
     }
 
-    /// Build a walking route from the best solution found so far
-    fn build_route(&self, best_solution: Vec<&'a Sight>) -> Result<Route, AlgorithmError> {
-        let mut route = Route::new();
-        let total_time_budget = self.end_time.signed_duration_since(self.start_time).num_seconds();
-        let mut left_time_budget = total_time_budget;
-        let mut curr_node_id = self.root_id;
-
-        for sight in best_solution {
-            let curr_distance_map = &self.distance_map[&curr_node_id];
-            let &(_, sight_travel_dist) = curr_distance_map.get(&sight.node_id)
-                .ok_or_else(|| AlgorithmError::NoRouteFound { from: curr_node_id, to: sight.node_id })?;
-            let sight_travel_time = (sight_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
-
-            let sight_distance_map = &self.distance_map[&sight.node_id];
-            let &(_, root_travel_dist) = sight_distance_map.get(&self.root_id)
-                .ok_or_else(|| AlgorithmError::NoRouteFound { from: sight.node_id, to: self.root_id })?;
-            let root_travel_time = (root_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
-
-            let used_time_budget = total_time_budget - left_time_budget + sight_travel_time;
-            match compute_wait_and_service_time(&self.start_time, &self.end_time, sight,
-                                                used_time_budget, root_travel_time) {
-                Some((wait_time, service_time)) => {
-                    let sight_total_time = sight_travel_time + wait_time + service_time;
-                    let path = build_path(&sight.node_id, curr_distance_map)
-                        .into_iter().map(|node_id| self.graph.get_node(node_id)).collect_vec();
-                    let sector = Sector::new(
-                        &self.start_time, total_time_budget - left_time_budget,
-                        sight_travel_time, wait_time, service_time, sight, path);
-                    if route.is_empty() {
-                        route.push(RouteSector::Start(sector));
-                    } else {
-                        route.push(RouteSector::Intermediate(sector));
-                    }
-                    left_time_budget -= sight_total_time;
-                    curr_node_id = sight.node_id;
-                }
-                None => break
-            };
-        }
-        let curr_distance_map = &self.distance_map[&curr_node_id];
-        let &(_, root_travel_dist) = curr_distance_map.get(&self.root_id)
-            .ok_or_else(|| AlgorithmError::NoRouteFound { from: curr_node_id, to: self.root_id })?;
-        let root_travel_time = (root_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
-        let path = build_path(&self.root_id, curr_distance_map)
-            .into_iter().map(|node_id| self.graph.get_node(node_id)).collect_vec();
-        let sector = EndSector::new(
-            &self.start_time, total_time_budget - left_time_budget,
-            root_travel_time, path);
-        route.push(RouteSector::End(sector));
-
-        log::debug!("Built walking route from best found solution");
-
-        Ok(route)
-    }
-}
-
-impl<'a> _Algorithm<'a> for SimAnnealingLinYu<'a> {
-    fn new(graph: &'a Graph,
-           start_time: DateTime<Utc>,
-           end_time: DateTime<Utc>,
-           walking_speed_mps: f64,
-           area: Area,
-           user_prefs: UserPreferences) -> Result<Self, AlgorithmError> where Self: Sized {
-        if end_time < start_time {
-            return Err(AlgorithmError::NegativeTimeInterval);
-        }
-
-        let time_budget = end_time.signed_duration_since(start_time).num_seconds() as f64;
-        let edge_radius = walking_speed_mps * time_budget / std::f64::consts::PI / 2.0;
-        let sights_radius = edge_radius.min(area.radius);
-        let mut sights = graph.get_reachable_sights_in_area(area.lat, area.lon,
-                                                        sights_radius, edge_radius);
-        if sights.is_empty() {
-            return Err(AlgorithmError::NoSightsFound);
-        }
-
-        let root_id = match graph.get_nearest_node_in_area(area.lat, area.lon, sights_radius) {
-            Some(nearest_node) => nearest_node,
-            None => return Err(AlgorithmError::NoNearestNodeFound)
-        };
-
-        let scores = compute_scores(&sights, user_prefs);
-
-        if sights.len() > MAX_NUM_SIGHTS {
-            // Keep best `MAX_NUM_SIGHTS` sights based on their score and distance to root
-            let result_from_root = run_ota_dijkstra_in_area(graph, root_id,
-                                                            area.lat, area.lon, edge_radius);
-            let max_score = USER_PREF_TO_SCORE[USER_PREF_MAX] as f64;
-            let max_dist = result_from_root.max_dist() as f64;
-            sights.sort_unstable_by(|sight1, sight2| {
-                let norm_score1 = scores[&sight1.node_id].0 as f64 / max_score;
-                let norm_score2 = scores[&sight2.node_id].0 as f64 / max_score;
-                // unwrap safety: get_reachable_sights_in_area ensures all sights are reachable
-                let norm_dist1 = 1.0 - result_from_root.dist_to(sight1.node_id).unwrap() as f64 / max_dist;
-                let norm_dist2 = 1.0 - result_from_root.dist_to(sight2.node_id).unwrap() as f64 / max_dist;
-                let metric1 = (SCORE_WEIGHT * norm_score1 + DIST_WEIGHT * norm_dist1)
-                    / (SCORE_WEIGHT + DIST_WEIGHT);
-                let metric2 = (SCORE_WEIGHT * norm_score2 + DIST_WEIGHT * norm_dist2)
-                    / (SCORE_WEIGHT + DIST_WEIGHT);
-                metric2.total_cmp(&metric1)
-            });
-            sights.truncate(MAX_NUM_SIGHTS);
-        }
-
-        let distance_map = build_distance_map(
-            graph, &area, edge_radius, &sights, root_id, &scores);
-
-        Ok(Self {
-            graph,
-            start_time,
-            end_time,
-            walking_speed_mps,
-            sights,
-            root_id,
-            scores,
-            distance_map,
-        })
-    }
-
-    fn compute_route(&self) -> Result<Route, AlgorithmError> {
-        log::debug!("Starting route computation");
-        let start = Instant::now();
-
+    /// Run a single annealing chain whose random decisions are drawn from a generator seeded with
+    /// `seed`. The chain stops at the latest `max_time_ms` milliseconds after `sa_start` or as
+    /// soon as `control` asks to stop.
+    ///
+    /// # Returns
+    /// * an `Ok` containing the best solution found by the chain and its total score, or
+    /// * an `Err` containing an `AlgorithmError`, otherwise
+    fn anneal(&self, seed: u64, sa_start: Instant, control: &ComputeControl,
+              mandatory_order: &[&'a Sight]) -> Result<(Vec<&'a Sight>, usize), AlgorithmError> {
         // Create a random initial route
-        let mut rng = thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
 
         let mut randomized_sights = self.sights.iter()
             .filter(|sight| {
                 let (score, category) = self.scores[&sight.node_id];
-                score > 0 && sight.category == category
+                score > 0 && sight.category == category && !self.mandatory.contains(&sight.node_id)
             })
             .map(|&sight| sight)
             .collect_vec();
-        if randomized_sights.is_empty() {
+        if randomized_sights.is_empty() && mandatory_order.is_empty() {
             return Err(AlgorithmError::NoPreferencesProvided);
         }
         randomized_sights.shuffle(&mut rng);
-        log::debug!("Computed randomized initial solution");
-
-        log::debug!("Starting simulated annealing (T_0: {}, B: {}, ALPHA: {}, MAX_TIME: {}, N_NON_IMPROVING: {})",
-            T_0, B, ALPHA, MAX_TIME, N_NON_IMPROVING);
-        let sa_start = Instant::now();
+        // Visit mandatory sights first, in an order in which all of them fit into the route
+        let randomized_sights = mandatory_order.iter().copied()
+            .chain(randomized_sights)
+            .collect_vec();
+        log::debug!("Computed randomized initial solution (seed: {})", seed);
 
-        let mut t = T_0;
-        let i_iter = randomized_sights.len() * B;
+        let mut t = self.params.t_0;
+        let i_iter = randomized_sights.len() * self.params.b;
         let mut i = 0;
+        let mut iteration = 0;
 
         let mut x = randomized_sights;
         let mut old_score = self.get_total_score(&x)?;
-        log::debug!("Score of initial solution: {}", old_score);
+        log::debug!("Score of initial solution (seed: {}): {}", seed, old_score);
         let mut x_best = x.clone();
         let mut f_best = old_score;
+        control.report_score(self.collected_score(f_best));
         let mut non_improving_count = 0;
 
         loop {
+            if control.should_stop() {
+                log::debug!("Reached deadline (seed: {}, current temperature: {})", seed, t);
+                break;
+            }
+
             let p = rng.gen::<f64>();
 
             let y;
             if p <= 1./3. {
-                y = swap(&x);
+                y = swap(&x, &mut rng);
             } else if p <= 2./3. {
-                y = insert(&x);
+                y = insert(&x, &mut rng);
             } else {
-                y = reverse(&x);
+                y = reverse(&x, &mut rng);
             }
             let new_score = self.get_total_score(&y)?;
 
             i += 1;
+            iteration += 1;
 
             let mut replace_solution = true;
             if old_score > new_score {
@@ -464,36 +559,207 @@ impl<'a> _Algorithm<'a> for SimAnnealingLinYu<'a> {
                     f_best = new_score;
                     x_best = x.clone();
                     non_improving_count = 0;
+                    control.report_score(self.collected_score(f_best));
                 }
             }
 
             if i == i_iter {
-                t *= ALPHA;
+                t *= self.params.alpha;
                 log::trace!("Updated temperature: {}", t);
                 i = 0;
 
                 self.local_search(&mut x_best)?;
                 f_best = self.get_total_score(&x_best)?;
                 log::trace!("Performed local search on best solution (score: {})", f_best);
-
-                let elapsed = sa_start.elapsed().as_millis();
-                if elapsed > MAX_TIME {
-                    log::debug!("Reached time limit (elapsed: {}, current temperature: {})",
-                        elapsed, t);
+                // intermediate routes are only built if anybody listens
+                let route = if control.wants_progress() {
+                    Some(self.build_route(x_best.clone())?)
+                } else {
+                    None
+                };
+                control.report_progress(&Progress {
+                    temperature: Some(t),
+                    iteration,
+                    best_score: self.collected_score(f_best),
+                    route: route.as_deref(),
+                });
+
+                if control.time_limit_reached(sa_start, self.params.max_time_ms) {
+                    log::debug!("Reached time limit (seed: {}, elapsed: {}, current temperature: {})",
+                        seed, sa_start.elapsed().as_millis(), t);
                     break;
                 }
-                if non_improving_count == N_NON_IMPROVING {
-                    log::debug!("Reached non-improving limit (non-improving: {}, current temperature: {})",
-                        non_improving_count, t);
+                if non_improving_count == self.params.n_non_improving {
+                    log::debug!("Reached non-improving limit (seed: {}, non-improving: {}, current temperature: {})",
+                        seed, non_improving_count, t);
                     break;
                 }
                 non_improving_count += 1;
             }
         }
 
+        Ok((x_best, f_best))
+    }
+
+    /// Finds an order in which all mandatory sights can be visited from the start of the route,
+    /// see `find_mandatory_order`
+    ///
+    /// # Returns
+    /// * an `Ok` containing the order of the mandatory sights, or
+    /// * an `Err` containing `AlgorithmError::MandatorySightsNotFeasible`, if there is no such
+    ///   order
+    fn mandatory_order(&self) -> Result<Vec<&'a Sight>, AlgorithmError> {
+        let mandatory = self.sights.iter()
+            .filter(|sight| self.mandatory.contains(&sight.node_id))
+            .copied()
+            .collect_vec();
+        let travel_time = |from_id: usize, to_id: usize| self.distance_map.dist(from_id, to_id)
+            .map(|dist| (dist as f64 / self.walking_speed_mps) as i64 + 1);
+        find_mandatory_order(&self.start_time, &self.end_time, &mandatory, self.root_id, 0,
+                             self.end_id, travel_time)
+            .ok_or_else(|| {
+                let infeasible = self.mandatory.iter().copied().sorted().collect_vec();
+                AlgorithmError::mandatory_sights_not_feasible(self.graph, &infeasible)
+            })
+    }
+
+    /// Repairs `solution` if it misses mandatory sights. The repaired solution visits all
+    /// mandatory sights first in `mandatory_order`, followed by the other sights of `solution`,
+    /// and is improved by a local search.
+    fn repair(&self, solution: Vec<&'a Sight>, mandatory_order: &[&'a Sight]) -> Result<Vec<&'a Sight>, AlgorithmError> {
+        // the bonus exceeds the sum of all scores
+        let num_visited_mandatory = self.get_total_score(&solution)? / self.mandatory_bonus;
+        if num_visited_mandatory == self.mandatory.len() {
+            return Ok(solution);
+        }
+
+        log::debug!("Repairing best solution visiting only {} of {} mandatory sights",
+            num_visited_mandatory, self.mandatory.len());
+        let mut repaired = mandatory_order.to_vec();
+        repaired.extend(solution.into_iter()
+            .filter(|sight| !self.mandatory.contains(&sight.node_id)));
+        self.local_search(&mut repaired)?;
+        Ok(repaired)
+    }
+
+    /// Build a walking route from the best solution found so far
+    fn build_route(&self, best_solution: Vec<&'a Sight>) -> Result<Route, AlgorithmError> {
+        let mut route = Route::new();
+        let total_time_budget = self.end_time.signed_duration_since(self.start_time).num_seconds();
+        let mut left_time_budget = total_time_budget;
+        let mut curr_node_id = self.root_id;
+
+        for sight in best_solution {
+            let sight_travel_dist = self.distance_map.dist(curr_node_id, sight.node_id)
+                .ok_or(AlgorithmError::NoRouteFound { from: curr_node_id, to: sight.node_id })?;
+            let sight_travel_time = (sight_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
+
+            let end_travel_dist = self.distance_map.dist(sight.node_id, self.end_id)
+                .ok_or(AlgorithmError::NoRouteFound { from: sight.node_id, to: self.end_id })?;
+            let end_travel_time = (end_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
+
+            let used_time_budget = total_time_budget - left_time_budget + sight_travel_time;
+            match compute_wait_and_service_time(&self.start_time, &self.end_time, sight,
+                                                used_time_budget, end_travel_time) {
+                Some((wait_time, service_time)) => {
+                    let sight_total_time = sight_travel_time + wait_time + service_time;
+                    let path = self.distance_map.path(self.graph, curr_node_id, sight.node_id);
+                    let sector = Sector::new(
+                        &self.start_time, total_time_budget - left_time_budget,
+                        sight_travel_time, wait_time, service_time, sight, path);
+                    if route.is_empty() {
+                        route.push(RouteSector::Start(sector));
+                    } else {
+                        route.push(RouteSector::Intermediate(sector));
+                    }
+                    left_time_budget -= sight_total_time;
+                    curr_node_id = sight.node_id;
+                }
+                None => break
+            };
+        }
+        let end_travel_dist = self.distance_map.dist(curr_node_id, self.end_id)
+            .ok_or(AlgorithmError::NoRouteFound { from: curr_node_id, to: self.end_id })?;
+        let end_travel_time = (end_travel_dist as f64 / self.walking_speed_mps) as i64 + 1;
+        let path = self.distance_map.path(self.graph, curr_node_id, self.end_id);
+        let sector = EndSector::new(
+            &self.start_time, total_time_budget - left_time_budget,
+            end_travel_time, path);
+        route.push(RouteSector::End(sector));
+
+        log::debug!("Built walking route from best found solution");
+
+        Ok(route)
+    }
+}
+
+impl<'a> _Algorithm<'a> for SimAnnealingLinYu<'a> {
+    fn new(graph: &'a Graph,
+           start_time: DateTime<Utc>,
+           end_time: DateTime<Utc>,
+           walking_speed_mps: f64,
+           area: Area,
+           destination: Option<Coordinate>,
+           user_prefs: UserPreferences,
+           params: AlgorithmParams,
+           seed: Option<u64>) -> Result<Self, AlgorithmError> where Self: Sized {
+        let params = params.sim_annealing;
+        let SearchSpace { edge_area, mut sights, root_id, end_id } = SearchSpace::new(
+            graph, &start_time, &end_time, walking_speed_mps, &area, destination.as_ref(),
+            &user_prefs.exclude)?;
+
+        let mandatory = user_prefs.get_mandatory_sights(graph, &sights)?;
+        let scores = compute_scores(&sights, user_prefs);
+        let mandatory_bonus = scores.values().map(|&(score, _)| score).sum::<usize>() + 1;
+
+        select_best_sights(graph, &edge_area, &mut sights, root_id, &scores, &mandatory, &params);
+
+        let distance_map = build_distance_map(
+            graph, &edge_area, &sights, root_id, end_id, &scores);
+
+        Ok(Self {
+            graph,
+            start_time: start_time.with_timezone(&graph.timezone()),
+            end_time: end_time.with_timezone(&graph.timezone()),
+            walking_speed_mps,
+            sights,
+            root_id,
+            end_id,
+            scores,
+            mandatory,
+            mandatory_bonus,
+            distance_map,
+            params,
+            seed: resolve_seed(seed),
+        })
+    }
+
+    fn compute_route(&self, control: &ComputeControl) -> Result<Route, AlgorithmError> {
+        log::debug!("Starting route computation (seed: {})", self.seed);
+        let start = Instant::now();
+
+        log::debug!("Starting simulated annealing ({:?})", self.params);
+        let sa_start = Instant::now();
+
+        // All chains start from the same feasible order of the mandatory sights
+        let mandatory_order = self.mandatory_order()?;
+
+        // Chains run independently with consecutive seeds. The best solution of the chain with
+        // the smallest seed wins ties, such that the result only depends on `seed`.
+        let (x_best, f_best) = (0..self.params.num_chains as u64).into_par_iter()
+            .map(|chain| self.anneal(self.seed.wrapping_add(chain), sa_start, control,
+                                     &mandatory_order))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, score)| *score)
+            // unwrap safety: validated parameters contain at least one chain
+            .unwrap();
+
+        let x_best = self.repair(x_best, &mandatory_order)?;
         let route = self.build_route(x_best)?;
-        log::debug!("Finished simulated annealing. Computed walking route from node: {} including {} sights with total score: {}.",
-             self.root_id, route.len() - 1, f_best);
+        log::debug!("Finished simulated annealing. Computed walking route from node: {} including {} sights with total score: {} ({:?}).",
+             self.root_id, route.len() - 1, f_best, self.params);
 
         log::debug!("Finished route computation in {} ms", start.elapsed().as_millis());
 
@@ -512,19 +778,50 @@ impl<'a> _Algorithm<'a> for SimAnnealingLinYu<'a> {
             })
             .sum()
     }
+
+    fn get_seed(&self) -> u64 {
+        self.seed
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::sync::{Arc, Mutex};
     use chrono::{DateTime, Utc};
     use once_cell::sync::Lazy;
-    use crate::algorithm::{_Algorithm, Area, RouteSector, Sector, SightCategoryPref, USER_PREF_MAX, UserPreferences};
-    use crate::algorithm::sa_lin_yu::{SimAnnealingLinYu, USER_PREF_TO_SCORE};
-    use crate::algorithm::test::{END_TIME, START_TIME, WALKING_SPEED_MPS};
-    use crate::data::graph::{Category, Graph};
+    use crate::algorithm::{_Algorithm, AlgorithmError, AlgorithmParams, AlgorithmParamsOverrides, Area, ComputeControl, RouteSector, ScoreMap, Sector, SightCategoryPref, TerminationReason, USER_PREF_MAX, UserPreferences};
+    use crate::algorithm::sa_lin_yu::{build_distance_map, DistanceMap, SimAnnealingLinYu, SimAnnealingParams, SimAnnealingParamsOverrides, USER_PREF_TO_SCORE};
+    use crate::algorithm::test::{END_TIME, SEED, START_TIME, WALKING_SPEED_MPS};
+    use crate::data::graph::{Category, Graph, SightExclusions};
     use crate::init_logging;
     use crate::utils::test_setup;
 
+    fn create_algo_with_params(seed: Option<u64>, params: AlgorithmParams) -> SimAnnealingLinYu<'static> {
+        let start_time = DateTime::parse_from_rfc3339(START_TIME).unwrap()
+            .with_timezone(&Utc);
+        let end_time = DateTime::parse_from_rfc3339(END_TIME).unwrap()
+            .with_timezone(&Utc);
+        SimAnnealingLinYu::new(
+            &test_setup::GRAPH,
+            start_time,
+            end_time,
+            WALKING_SPEED_MPS,
+            Area {
+                lat: 53.074448,
+                lon: 8.805105,
+                radius: 300.0,
+            },
+            None,
+            UserPreferences {
+                categories: vec![SightCategoryPref { category: Category::Sightseeing, pref: 5 },
+                                 SightCategoryPref { category: Category::Restaurants, pref: 2 }],
+                sights: vec![],
+                exclude: SightExclusions::default(),
+            },
+            params,
+            seed).unwrap()
+    }
+
     #[test]
     fn test_sights_with_multiple_categories() {
         init_logging();
@@ -545,11 +842,15 @@ mod test {
                 lon: 8.793089,
                 radius: 500.0,
             },
+            None,
             UserPreferences {
                 categories: vec![SightCategoryPref { category: Category::Activities, pref: 5 },
                                  SightCategoryPref { category: Category::Nightlife, pref: 3 }],
                 sights: vec![],
-            }).unwrap();
+                exclude: SightExclusions::default(),
+            },
+            AlgorithmParams::default(),
+            Some(SEED)).unwrap();
 
         let mut last = algo.sights.first().unwrap();
         for sight in &algo.sights[1..algo.sights.len()] {
@@ -563,7 +864,7 @@ mod test {
             }
         }
 
-        let route = algo.compute_route()
+        let route = algo.compute_route(&ComputeControl::default())
             .expect("Error during route computation");
         let check_sector = |sector: &Sector| {
             let sight = sector.sight;
@@ -580,4 +881,177 @@ mod test {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_params_overrides_within_safe_ranges() {
+        let params = AlgorithmParams::default();
+        let overrides = AlgorithmParamsOverrides {
+            sim_annealing: SimAnnealingParamsOverrides {
+                alpha: Some(0.9),
+                max_num_sights: Some(50),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let overridden = params.with_overrides(&overrides).unwrap();
+        assert_eq!(overridden.sim_annealing, SimAnnealingParams {
+            alpha: 0.9,
+            max_num_sights: 50,
+            ..SimAnnealingParams::default()
+        });
+
+        let overrides = AlgorithmParamsOverrides {
+            sim_annealing: SimAnnealingParamsOverrides {
+                max_time_ms: Some(3_600_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        match params.with_overrides(&overrides) {
+            Err(AlgorithmError::InvalidParameter { name, .. }) => assert_eq!(name, "max_time_ms"),
+            _ => panic!("Accepted maximum computation time outside of safe range"),
+        }
+
+        let overrides = AlgorithmParamsOverrides {
+            sim_annealing: SimAnnealingParamsOverrides {
+                score_weight: Some(0.),
+                dist_weight: Some(0.),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(params.with_overrides(&overrides).is_err(), "Accepted weights that sum up to zero");
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_route() {
+        init_logging();
+
+        let create_algo = |seed| create_algo_with_params(seed, AlgorithmParams::default());
+        let sight_ids = |algo: &SimAnnealingLinYu| algo.compute_route(&ComputeControl::default())
+            .expect("Error during route computation")
+            .iter()
+            .filter_map(|route_sector| match route_sector {
+                RouteSector::Start(sector) | RouteSector::Intermediate(sector) =>
+                    Some(sector.sight.node_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let algo = create_algo(None);
+        let replayed_algo = create_algo(Some(algo.get_seed()));
+        assert_eq!(replayed_algo.get_seed(), algo.get_seed());
+        assert_eq!(sight_ids(&replayed_algo), sight_ids(&algo),
+                   "Route with seed {} could not be reproduced", algo.get_seed());
+    }
+
+    #[test]
+    fn test_progress_reported_at_each_temperature() {
+        init_logging();
+
+        let algo = create_algo_with_params(Some(SEED), AlgorithmParams::default());
+
+        // (temperature, best score, whether the intermediate route is complete)
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let listener_reports = reports.clone();
+        let control = ComputeControl::default().with_progress_listener(move |progress| {
+            let route_complete = matches!(progress.route.and_then(|route| route.last()),
+                Some(RouteSector::End(_)));
+            listener_reports.lock().unwrap().push(
+                (progress.temperature.unwrap(), progress.best_score, route_complete));
+        });
+        let route = algo.compute_route(&control).expect("Error during route computation");
+
+        let reports = reports.lock().unwrap();
+        assert!(!reports.is_empty(), "No progress reported");
+        for window in reports.windows(2) {
+            assert!(window[1].0 < window[0].0, "Temperature did not decrease");
+            assert!(window[1].1 >= window[0].1, "Best score decreased");
+        }
+        assert!(reports.iter().all(|&(_, _, route_complete)| route_complete));
+        assert_eq!(reports.last().unwrap().1, algo.get_collected_score(&route));
+    }
+
+    #[test]
+    fn test_multi_start_not_worse_than_single_chain() {
+        init_logging();
+
+        let create_algo = |num_chains| create_algo_with_params(Some(SEED), AlgorithmParams {
+            sim_annealing: SimAnnealingParams {
+                num_chains,
+                ..SimAnnealingParams::default()
+            },
+            ..AlgorithmParams::default()
+        });
+
+        // The first chain uses the same seed as the single chain
+        let single_chain = create_algo(1);
+        let single_chain_route = single_chain.compute_route(&ComputeControl::default())
+            .expect("Error during route computation");
+        let multi_start = create_algo(4);
+        let multi_start_route = multi_start.compute_route(&ComputeControl::default())
+            .expect("Error during route computation");
+        assert!(multi_start.get_collected_score(&multi_start_route)
+                    >= single_chain.get_collected_score(&single_chain_route),
+                "Multi-start route collects smaller score than single chain");
+    }
+
+    #[test]
+    fn test_time_limit_reported_as_deadline() {
+        init_logging();
+
+        let algo = create_algo_with_params(Some(SEED), AlgorithmParams {
+            sim_annealing: SimAnnealingParams {
+                alpha: 0.99,
+                max_time_ms: 1,
+                n_non_improving: 100,
+                ..SimAnnealingParams::default()
+            },
+            ..AlgorithmParams::default()
+        });
+        let control = ComputeControl::default();
+        algo.compute_route(&control).expect("Error during route computation");
+        assert_eq!(control.termination_reason(), TerminationReason::DeadlineReached);
+    }
+
+    #[test]
+    fn test_ch_distances_not_restricted_to_edge_area() {
+        init_logging();
+
+        let (lat, lon) = (53.074448, 8.805105);
+        // the sights lie partly outside of the edge area
+        let edge_area = Area {
+            lat,
+            lon,
+            radius: 100.0,
+        };
+        let sights = test_setup::GRAPH.get_sights_in_area(lat, lon, 300.0);
+        let scores: ScoreMap = sights.iter()
+            .map(|sight| (sight.node_id, (1, sight.category)))
+            .collect();
+        let root_id = sights[0].node_id;
+
+        let area_map = build_distance_map(&test_setup::GRAPH, &edge_area, &sights, root_id,
+                                          root_id, &scores);
+        let ch_map = build_distance_map(&test_setup::CONTRACTED_GRAPH, &edge_area, &sights,
+                                        root_id, root_id, &scores);
+        assert!(matches!(area_map, DistanceMap::Dijkstra(_)));
+        assert!(matches!(ch_map, DistanceMap::ContractionHierarchy { .. }));
+
+        let mut num_leaving_area = 0;
+        for from in &sights {
+            for to in &sights {
+                match (area_map.dist(from.node_id, to.node_id), ch_map.dist(from.node_id, to.node_id)) {
+                    (Some(area_dist), Some(ch_dist)) => assert!(ch_dist <= area_dist,
+                        "Distance from {} to {} with contraction hierarchy exceeds distance within area",
+                        from.node_id, to.node_id),
+                    (None, Some(_)) => num_leaving_area += 1,
+                    (Some(_), None) => panic!("Node {} not reachable from {} with contraction hierarchy",
+                                              to.node_id, from.node_id),
+                    (None, None) => {}
+                }
+            }
+        }
+        assert!(num_leaving_area > 0, "No path leaves the edge area");
+    }
+}
@@ -6,13 +6,17 @@ use chrono::{DateTime, Duration, Utc};
 use itertools::Itertools;
 use pathfinding::prelude::dijkstra_all;
 use rand::{Rng, SeedableRng, rngs::StdRng};
-use trailscout_lib::algorithm::{Algorithm, Area, SightCategoryPref, UserPreferences};
+use trailscout_lib::algorithm::{Algorithm, AlgorithmError, AlgorithmParams, AlgorithmParamsOverrides, Area, ComputeControl, SightCategoryPref, UserPreferences};
 use trailscout_lib::data::graph::{Category, Graph};
 use trailscout_lib::init_logging;
 use trailscout_lib::utils::dijkstra;
 
-/// Benchmarks a dijkstra implementation. Either the Trailscout implementation
-/// (`dijkstra == "self"`) or the one of the `pathfinding` crate (`dijkstra == "pathfinding"`).
+/// Benchmarks a dijkstra implementation. Either the Trailscout one-to-all implementation
+/// (`dijkstra == "self"`), the one of the `pathfinding` crate (`dijkstra == "pathfinding"`)
+/// or one of the Trailscout implementations between a random source and target, i.e.,
+/// the unidirectional Dijkstra (`dijkstra == "unidirectional"`), the bidirectional Dijkstra
+/// (`dijkstra == "bidirectional"`), the A* search (`dijkstra == "astar"`) or the query on the
+/// contraction hierarchy of the graph (`dijkstra == "ch"`).
 fn bench_dijkstra(seed: u64, graph_file: &str, dijkstra: &str, iter_warmup: usize,
                   iter_measure: usize) {
     let graph = Graph::parse_from_file(graph_file)
@@ -47,8 +51,34 @@ fn bench_dijkstra(seed: u64, graph_file: &str, dijkstra: &str, iter_warmup: usiz
                              |&node_id| successors(node_id));
                 start
             }
+            "unidirectional" => {
+                let tgt_id = rng.gen_range(0..graph.num_nodes);
+                let start = Instant::now();
+                dijkstra::run_dijkstra(&graph, src_id, tgt_id);
+                start
+            }
+            "bidirectional" => {
+                let tgt_id = rng.gen_range(0..graph.num_nodes);
+                let start = Instant::now();
+                dijkstra::run_bidirectional_dijkstra(&graph, src_id, tgt_id);
+                start
+            }
+            "astar" => {
+                let tgt_id = rng.gen_range(0..graph.num_nodes);
+                let start = Instant::now();
+                dijkstra::run_astar(&graph, src_id, tgt_id);
+                start
+            }
+            "ch" => {
+                let tgt_id = rng.gen_range(0..graph.num_nodes);
+                let ch = graph.contraction_hierarchy()
+                    .expect("Graph has no contraction hierarchy");
+                let start = Instant::now();
+                ch.shortest_path(&graph, src_id, tgt_id);
+                start
+            }
             _ => panic!("Unknown dijkstra impl.")
-        }.elapsed().as_millis()
+        }.elapsed().as_micros()
     };
 
     // first iter_warmup rounds system warm up
@@ -66,7 +96,11 @@ fn bench_dijkstra(seed: u64, graph_file: &str, dijkstra: &str, iter_warmup: usiz
     }
 
     let avg = measurements.iter().sum::<u128>() / iter_measure as u128;
-    log::info!("Average run time: {avg} ms");
+    // one-to-all searches take milliseconds, searches between two nodes rather microseconds
+    match dijkstra {
+        "self" | "pathfinding" => log::info!("Average run time: {} ms", avg / 1000),
+        _ => log::info!("Average run time: {avg} µs"),
+    }
 }
 
 /// Benchmarks a dijkstra implementation. Either the Trailscout implementation
@@ -128,19 +162,56 @@ fn bench_dijkstra_in_area(seed: u64, graph_file: &str, dijkstra: &str, iter_warm
     log::info!("Average run time: {avg} ms");
 }
 
-/// Benchmarks the score and runtime of given algorithm under the given parameters
-fn bench_algo(graph_file: &str, algo_name: &str, iter_warmup: usize, iter_measure: usize,
-              radius: f64, walking_time: i64, category_prefs: Vec<SightCategoryPref>) {
+/// Benchmarks the score and runtime of given algorithms under the given parameters.
+/// Each algorithm is benchmarked once for every entry of `algo_params`.
+#[allow(clippy::too_many_arguments)]
+fn bench_algos(graph_file: &str, algo_names: &[&str], iter_warmup: usize, iter_measure: usize,
+               radius: f64, walking_time: i64, category_prefs: Vec<SightCategoryPref>,
+               algo_params: &[AlgorithmParams]) {
     let graph = Graph::parse_from_file(graph_file)
         .expect("Failed to parse graph file");
 
+    for algo_name in algo_names {
+        for params in algo_params {
+            bench_algo(&graph, graph_file, algo_name, iter_warmup, iter_measure, radius,
+                       walking_time, category_prefs.clone(), params);
+        }
+    }
+}
+
+/// Creates the parameters for every combination of the given simulated annealing parameter
+/// values, where each sweep consists of a parameter name and the values it should take
+fn sweep_sim_annealing_params(sweeps: &[(String, Vec<String>)]) -> Vec<AlgorithmParams> {
+    sweeps.iter()
+        .map(|(name, values)| values.iter().map(move |value| (name, value)).collect_vec())
+        .multi_cartesian_product()
+        .map(|assignment| {
+            let sim_annealing: serde_json::Map<String, serde_json::Value> = assignment.into_iter()
+                .map(|(name, value)| (name.clone(), serde_json::from_str(value)
+                    .expect("Failed to parse parameter value")))
+                .collect();
+            let overrides: AlgorithmParamsOverrides = serde_json::from_value(
+                serde_json::json!({ "sim_annealing": sim_annealing }))
+                .expect("Unknown parameter");
+            AlgorithmParams::default().with_overrides(&overrides)
+                .expect("Invalid parameter value")
+        })
+        .collect_vec()
+}
+
+/// Benchmarks the score and runtime of given algorithm under the given parameters
+#[allow(clippy::too_many_arguments)]
+fn bench_algo(graph: &Graph, graph_file: &str, algo_name: &str, iter_warmup: usize,
+              iter_measure: usize, radius: f64, walking_time: i64,
+              category_prefs: Vec<SightCategoryPref>, params: &AlgorithmParams) {
     log::info!("Benchmarking {algo_name} algorithm \n\
         on graph {graph_file} \n\
         with {iter_warmup} warm up iterations, \n\
         {iter_measure} measured iterations, \n\
         radius {radius} m, \n\
-        walking time {walking_time} h and \n\
-        category preferences {:?}", &category_prefs);
+        walking time {walking_time} h, \n\
+        category preferences {:?} and \n\
+        parameters {:?}", &category_prefs, params);
 
     let start_time = DateTime::parse_from_rfc3339("2022-07-01T14:00:00+01:00")
         .unwrap().with_timezone(&Utc);
@@ -149,13 +220,22 @@ fn bench_algo(graph_file: &str, algo_name: &str, iter_warmup: usize, iter_measur
     let user_prefs = UserPreferences::from_category_and_sight_prefs(
         category_prefs, vec![]);
 
+    // Exact algorithms are limited to small instances
+    if let Err(AlgorithmError::TooManyCandidates { num_candidates, max_candidates }) =
+        Algorithm::from_name(algo_name, graph, start_time, end_time, 5.0 / 3.6, area.clone(),
+                             None, user_prefs.clone(), params.clone(), None) {
+        log::warn!("Skipping {algo_name} algorithm: {num_candidates} candidate sights exceed \
+            the limit of {max_candidates}");
+        return;
+    }
+
     let do_iteration = || {
         let start = Instant::now();
         let algo = Algorithm::from_name(
-            algo_name, &graph, start_time, end_time, 5.0 / 3.6,
-            area.clone(), user_prefs.clone())
+            algo_name, graph, start_time, end_time, 5.0 / 3.6,
+            area.clone(), None, user_prefs.clone(), params.clone(), None)
             .expect("Unknown algorithm");
-        let route = algo.compute_route().expect("Error during route computation");
+        let route = algo.compute_route(&ComputeControl::default()).expect("Error during route computation");
         let elapsed = start.elapsed().as_millis();
 
         (algo.get_collected_score(&route), elapsed)
@@ -266,15 +346,32 @@ fn main() {
             let iter_measure: usize = args[5].parse().unwrap();
             let radius: f64 = args[6].parse().unwrap();
             let walking_time: i64 = args[7].parse().unwrap();
+            // Remaining arguments are category preference pairs, e.g. "Sightseeing 5", or
+            // simulated annealing parameters to sweep, e.g. "alpha=0.5,0.7,0.9"
             let mut category_prefs = Vec::with_capacity(
                 (args.len() - 8) / 2);
-            for i in (8..args.len()).step_by(2) {
-                let category: Category = args[i].parse().unwrap();
-                let pref: usize = args[i+1].parse().unwrap();
-                category_prefs.push(SightCategoryPref::new(category, pref));
+            let mut param_sweeps = vec![];
+            let mut i = 8;
+            while i < args.len() {
+                if let Some((name, values)) = args[i].split_once('=') {
+                    param_sweeps.push((name.to_string(),
+                                       values.split(',').map(str::to_string).collect_vec()));
+                    i += 1;
+                } else {
+                    let category: Category = args[i].parse().unwrap();
+                    let pref: usize = args[i+1].parse().unwrap();
+                    category_prefs.push(SightCategoryPref::new(category, pref));
+                    i += 2;
+                }
             }
-            bench_algo(graph_file, algo_name, iter_warmup, iter_measure, radius, walking_time,
-                       category_prefs);
+            // "all" benchmarks all available algorithms one after another
+            let algo_names = if algo_name == "all" {
+                Algorithm::available_algorithms().to_vec()
+            } else {
+                vec![algo_name]
+            };
+            bench_algos(graph_file, &algo_names, iter_warmup, iter_measure, radius, walking_time,
+                        category_prefs, &sweep_sim_annealing_params(&param_sweeps));
         }
         "nn" => {
             let seed: u64 = args[2].parse().unwrap();
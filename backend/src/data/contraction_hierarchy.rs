@@ -0,0 +1,572 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use log::{info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::data::graph::{Graph, GRAPH_FILE_VERSION, ParseError};
+use crate::utils::dijkstra::NodeResult;
+
+/// Version of the binary file format of contraction hierarchies. Increase it whenever the format
+/// or the preprocessing changes, such that outdated files are rebuilt.
+pub const CH_FILE_VERSION: u32 = 2;
+
+/// Maximum number of nodes settled by a witness search during the preprocessing. Stopping earlier
+/// only adds unnecessary shortcuts.
+const MAX_WITNESS_SETTLED_NODES: usize = 500;
+
+/// Edge of a contraction hierarchy from a node to a neighbour of higher rank
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct ChEdge {
+    /// The id of the edge's target node
+    tgt: usize,
+    /// The edge's weight, i.e., the length of the shortest path it represents
+    dist: usize,
+    /// The id of the contracted node bypassed by the edge, if it is a shortcut
+    via: Option<usize>,
+}
+
+/// Neighbour of a node that is not contracted yet during the preprocessing
+#[derive(Clone, Copy)]
+struct Neighbour {
+    node_id: usize,
+    dist: usize,
+    via: Option<usize>,
+}
+
+/// Contraction hierarchy of a graph for fast shortest path queries.
+///
+/// The nodes are contracted one after another. When contracting a node, shortcuts are added
+/// between its remaining neighbours unless a path without the node is at least as short.
+/// The position of a node in this order is its rank. A shortest path consists of edges to nodes
+/// of increasing rank followed by edges to nodes of decreasing rank, so queries only have to
+/// search upwards from the source and the target.
+///
+/// Each edge of the graph has a reverse edge with the same weight, so the hierarchy only stores
+/// the edges to higher ranked nodes, which serve both the forward and the backward search.
+pub struct ContractionHierarchy {
+    /// Rank of each node in the contraction order
+    ranks: Vec<usize>,
+    /// Edges to higher ranked nodes, sorted by their source
+    edges: Vec<ChEdge>,
+    /// Offsets of the edges of each node in `edges`
+    offsets: Vec<usize>,
+    /// Number of nodes of the graph the hierarchy was built for
+    num_nodes: usize,
+    /// Number of edges of the graph the hierarchy was built for
+    num_edges: usize,
+    /// Checksum of the nodes and edges of the graph the hierarchy was built for
+    graph_checksum: u64,
+}
+
+/// Nodes settled by an upward search with their distance from the search's source and their
+/// predecessor on the way from the source
+type SearchSpace = HashMap<usize, (usize, usize)>;
+
+impl ContractionHierarchy {
+    /// Build the contraction hierarchy of `graph`. The next node to contract is the one with the
+    /// smallest difference between the number of shortcuts it requires and its degree, increased
+    /// by its number of contracted neighbours to contract nodes evenly across the graph.
+    pub fn build(graph: &Graph) -> Self {
+        info!("Start building the contraction hierarchy!");
+        let time_start = Instant::now();
+
+        // remaining neighbours of each node, parallel edges are merged into the shortest one
+        let mut neighbours: Vec<Vec<Neighbour>> = (0..graph.num_nodes)
+            .map(|node_id| {
+                let mut node_neighbours: Vec<Neighbour> = vec![];
+                for edge in graph.get_outgoing_edges(node_id) {
+                    if edge.tgt == node_id {
+                        continue;
+                    }
+                    match node_neighbours.iter_mut().find(|neighbour| neighbour.node_id == edge.tgt) {
+                        Some(neighbour) => neighbour.dist = neighbour.dist.min(edge.dist),
+                        None => node_neighbours.push(Neighbour { node_id: edge.tgt, dist: edge.dist, via: None }),
+                    }
+                }
+                node_neighbours
+            })
+            .collect();
+
+        let mut witness_search = WitnessSearch::new(graph.num_nodes);
+        let mut num_contracted_neighbours = vec![0; graph.num_nodes];
+        let priority = |node_id: usize, num_shortcuts: usize, neighbours: &Vec<Vec<Neighbour>>,
+                        num_contracted_neighbours: &Vec<usize>| {
+            num_shortcuts as i64 - neighbours[node_id].len() as i64
+                + num_contracted_neighbours[node_id] as i64
+        };
+        let mut pq: BinaryHeap<Reverse<(i64, usize)>> = (0..graph.num_nodes)
+            .map(|node_id| {
+                let num_shortcuts = find_shortcuts(&neighbours, node_id, &mut witness_search).len();
+                Reverse((priority(node_id, num_shortcuts, &neighbours, &num_contracted_neighbours), node_id))
+            })
+            .collect();
+        info!("Computed initial node priorities after {} seconds!",
+            time_start.elapsed().as_millis() as f32 / 1000.0);
+
+        let mut ranks = vec![usize::MAX; graph.num_nodes];
+        let mut up_edges: Vec<Vec<ChEdge>> = vec![vec![]; graph.num_nodes];
+        let mut num_shortcuts = 0;
+        let mut next_rank = 0;
+        while let Some(Reverse((_, node_id))) = pq.pop() {
+            // priorities of remaining nodes change when their neighbours are contracted,
+            // so update the priority and postpone the node if another one comes first now
+            let shortcuts = find_shortcuts(&neighbours, node_id, &mut witness_search);
+            let node_priority = priority(node_id, shortcuts.len(), &neighbours, &num_contracted_neighbours);
+            if let Some(&Reverse((next_priority, _))) = pq.peek() {
+                if node_priority > next_priority {
+                    pq.push(Reverse((node_priority, node_id)));
+                    continue;
+                }
+            }
+
+            ranks[node_id] = next_rank;
+            next_rank += 1;
+            let node_neighbours = std::mem::take(&mut neighbours[node_id]);
+            for neighbour in &node_neighbours {
+                neighbours[neighbour.node_id].retain(|other| other.node_id != node_id);
+                num_contracted_neighbours[neighbour.node_id] += 1;
+            }
+            num_shortcuts += shortcuts.len();
+            for (src_id, tgt_id, dist) in shortcuts {
+                add_or_shorten_edge(&mut neighbours[src_id], tgt_id, dist, node_id);
+                add_or_shorten_edge(&mut neighbours[tgt_id], src_id, dist, node_id);
+            }
+            up_edges[node_id] = node_neighbours.into_iter()
+                .map(|neighbour| ChEdge { tgt: neighbour.node_id, dist: neighbour.dist, via: neighbour.via })
+                .collect();
+        }
+
+        let mut offsets = Vec::with_capacity(graph.num_nodes + 1);
+        offsets.push(0);
+        for node_edges in &up_edges {
+            offsets.push(offsets.last().unwrap() + node_edges.len());
+        }
+        let edges: Vec<ChEdge> = up_edges.into_iter().flatten().collect();
+
+        info!("End building the contraction hierarchy with {} shortcuts after {} seconds!",
+            num_shortcuts, time_start.elapsed().as_millis() as f32 / 1000.0);
+
+        Self {
+            ranks,
+            edges,
+            offsets,
+            num_nodes: graph.num_nodes,
+            num_edges: graph.num_edges,
+            graph_checksum: graph_checksum(graph),
+        }
+    }
+
+    /// Parse the contraction hierarchy stored next to the graph file at `graph_file_path`.
+    /// Returns `None` if there is no such file or if it does not belong to the given graph.
+    pub fn parse_for_graph(graph_file_path: &str, graph: &Graph) -> Option<Self> {
+        let ch_file_path = ch_file_path(graph_file_path);
+        if !ch_file_path.exists() {
+            return None;
+        }
+        match Self::parse_from_file(&ch_file_path) {
+            Ok(ch) if ch.num_nodes == graph.num_nodes && ch.num_edges == graph.num_edges
+                && ch.graph_checksum == graph_checksum(graph) => {
+                info!("Loaded contraction hierarchy from {}", ch_file_path.display());
+                Some(ch)
+            }
+            Ok(_) => {
+                warn!("Ignoring contraction hierarchy {}, which belongs to another graph",
+                    ch_file_path.display());
+                None
+            }
+            Err(err) => {
+                warn!("Ignoring contraction hierarchy {}: {}", ch_file_path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Parse a contraction hierarchy from the binary file at `ch_file_path`
+    fn parse_from_file(ch_file_path: &Path) -> Result<Self, ParseError> {
+        let mut reader = BufReader::new(File::open(ch_file_path)?);
+        let version: u32 = bincode::deserialize_from(&mut reader).unwrap_or_default();
+        if version != CH_FILE_VERSION {
+            return Err(ParseError::Version { found: version, expected: CH_FILE_VERSION });
+        }
+        let graph_version: u32 = bincode::deserialize_from(&mut reader).unwrap_or_default();
+        if graph_version != GRAPH_FILE_VERSION {
+            return Err(ParseError::Version { found: graph_version, expected: GRAPH_FILE_VERSION });
+        }
+        let deserialize_err = |err: bincode::Error| io::Error::new(io::ErrorKind::InvalidData, err);
+        let num_nodes: usize = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        let num_edges: usize = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        let graph_checksum: u64 = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        let ranks: Vec<usize> = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        let edges: Vec<ChEdge> = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        let offsets: Vec<usize> = bincode::deserialize_from(&mut reader).map_err(deserialize_err)?;
+        Ok(Self { ranks, edges, offsets, num_nodes, num_edges, graph_checksum })
+    }
+
+    /// Write the contraction hierarchy next to the graph file at `graph_file_path`
+    pub fn write_for_graph(&self, graph_file_path: &str) -> Result<(), io::Error> {
+        let ch_file_path = ch_file_path(graph_file_path);
+        let mut file = BufWriter::new(File::create(&ch_file_path)?);
+        bincode::serialize_into(&mut file, &CH_FILE_VERSION).expect("Error serializing version");
+        bincode::serialize_into(&mut file, &GRAPH_FILE_VERSION).expect("Error serializing graph version");
+        bincode::serialize_into(&mut file, &self.num_nodes).expect("Error serializing number of nodes");
+        bincode::serialize_into(&mut file, &self.num_edges).expect("Error serializing number of edges");
+        bincode::serialize_into(&mut file, &self.graph_checksum).expect("Error serializing graph checksum");
+        bincode::serialize_into(&mut file, &self.ranks).expect("Error serializing ranks");
+        bincode::serialize_into(&mut file, &self.edges).expect("Error serializing edges");
+        bincode::serialize_into(&mut file, &self.offsets).expect("Error serializing offsets");
+        info!("Wrote contraction hierarchy to {}", ch_file_path.display());
+        Ok(())
+    }
+
+    /// Get the edges from the node with id `node_id` to higher ranked nodes
+    fn get_up_edges(&self, node_id: usize) -> &[ChEdge] {
+        &self.edges[self.offsets[node_id]..self.offsets[node_id + 1]]
+    }
+
+    /// Run a Dijkstra from the node with id `src_id` that only follows edges to higher ranked
+    /// nodes. Nodes that can be reached on a shorter path via a higher ranked neighbour are
+    /// stalled, i.e., they are neither settled nor further explored, as no shortest path leads
+    /// upwards through them.
+    fn upward_search(&self, src_id: usize) -> SearchSpace {
+        let mut tentative: SearchSpace = HashMap::new();
+        let mut settled: SearchSpace = HashMap::new();
+        let mut pq = BinaryHeap::new();
+        tentative.insert(src_id, (0, usize::MAX));
+        pq.push(Reverse((0, src_id)));
+
+        while let Some(Reverse((dist, node_id))) = pq.pop() {
+            let (node_dist, pred_id) = tentative[&node_id];
+            if dist > node_dist {
+                continue;
+            }
+            let edges = self.get_up_edges(node_id);
+            let stalled = edges.iter().any(|edge|
+                matches!(tentative.get(&edge.tgt), Some(&(tgt_dist, _)) if tgt_dist + edge.dist < dist));
+            if stalled {
+                continue;
+            }
+            settled.insert(node_id, (dist, pred_id));
+
+            for edge in edges {
+                let tgt_dist = dist + edge.dist;
+                let improves = match tentative.get(&edge.tgt) {
+                    Some(&(prev_dist, _)) => tgt_dist < prev_dist,
+                    None => true,
+                };
+                if improves {
+                    tentative.insert(edge.tgt, (tgt_dist, node_id));
+                    pq.push(Reverse((tgt_dist, edge.tgt)));
+                }
+            }
+        }
+
+        settled
+    }
+
+    /// Find the node where the upward searches from the source and the target meet on a shortest
+    /// path. Returns the node id and the length of the path in a `Some` or `None` if the target
+    /// is not reachable from the source.
+    fn find_meeting_node(fwd_space: &SearchSpace, bwd_space: &SearchSpace) -> Option<(usize, usize)> {
+        fwd_space.iter()
+            .filter_map(|(&node_id, &(fwd_dist, _))| bwd_space.get(&node_id)
+                .map(|&(bwd_dist, _)| (node_id, fwd_dist + bwd_dist)))
+            .min_by_key(|&(node_id, dist)| (dist, node_id))
+    }
+
+    /// Get the length of the shortest path from the node with id `src_id` to the node with id
+    /// `tgt_id` in a `Some` or `None` if the target is not reachable from the source
+    pub fn dist(&self, src_id: usize, tgt_id: usize) -> Option<usize> {
+        Self::find_meeting_node(&self.upward_search(src_id), &self.upward_search(tgt_id))
+            .map(|(_, dist)| dist)
+    }
+
+    /// Get the shortest path from the node with id `src_id` to the node with id `tgt_id` in
+    /// `graph`, which the hierarchy was built for. Shortcuts are unpacked into the original edges.
+    pub fn shortest_path<'a>(&self, graph: &'a Graph, src_id: usize, tgt_id: usize) -> Option<NodeResult<'a>> {
+        let fwd_space = self.upward_search(src_id);
+        let bwd_space = self.upward_search(tgt_id);
+        let (meeting_node_id, dist) = Self::find_meeting_node(&fwd_space, &bwd_space)?;
+
+        let mut ch_path = vec![];
+        let mut curr_pred = meeting_node_id;
+        while curr_pred < usize::MAX {
+            ch_path.push(curr_pred);
+            curr_pred = fwd_space[&curr_pred].1;
+        }
+        ch_path.reverse();
+        // the predecessors of the backward search lead towards the target
+        let mut curr_succ = bwd_space[&meeting_node_id].1;
+        while curr_succ < usize::MAX {
+            ch_path.push(curr_succ);
+            curr_succ = bwd_space[&curr_succ].1;
+        }
+
+        let mut path = vec![src_id];
+        for pair in ch_path.windows(2) {
+            self.unpack_edge(pair[0], pair[1], &mut path);
+        }
+        Some(NodeResult::new(dist, graph.expand_path(path)))
+    }
+
+    /// Append the original nodes of the edge between the nodes with ids `src_id` and `tgt_id`,
+    /// except its source, to `path`
+    fn unpack_edge(&self, src_id: usize, tgt_id: usize, path: &mut Vec<usize>) {
+        // the edge is stored at the lower ranked node
+        let (lower_id, higher_id) = if self.ranks[src_id] < self.ranks[tgt_id] {
+            (src_id, tgt_id)
+        } else {
+            (tgt_id, src_id)
+        };
+        let edge = self.get_up_edges(lower_id).iter()
+            .find(|edge| edge.tgt == higher_id)
+            .expect("Missing edge in contraction hierarchy");
+        match edge.via {
+            Some(via_id) => {
+                self.unpack_edge(src_id, via_id, path);
+                self.unpack_edge(via_id, tgt_id, path);
+            }
+            None => path.push(tgt_id),
+        }
+    }
+
+    /// Compute the lengths of the shortest paths from each node with id in `src_ids` to each node
+    /// with id in `tgt_ids`. The upward search spaces of the targets are stored in buckets at
+    /// the settled nodes, which the upward searches from the sources scan. The searches are
+    /// distributed over the rayon thread pool.
+    /// Returns the distance matrix, whose `i`-th row holds the distances from the `i`-th source node
+    /// to the target nodes in the order of `tgt_ids`, with `None` for target nodes that are not
+    /// reachable
+    pub fn many_to_many(&self, src_ids: &[usize], tgt_ids: &[usize]) -> Vec<Vec<Option<usize>>> {
+        let tgt_spaces: Vec<SearchSpace> = tgt_ids.par_iter()
+            .map(|&tgt_id| self.upward_search(tgt_id))
+            .collect();
+        // indices of the targets whose search settled a node, with their distance to the node
+        let mut buckets: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        for (tgt_index, tgt_space) in tgt_spaces.iter().enumerate() {
+            for (&node_id, &(dist, _)) in tgt_space {
+                buckets.entry(node_id).or_default().push((tgt_index, dist));
+            }
+        }
+
+        src_ids.par_iter()
+            .map(|&src_id| {
+                let mut row: Vec<Option<usize>> = vec![None; tgt_ids.len()];
+                for (node_id, (src_dist, _)) in self.upward_search(src_id) {
+                    for &(tgt_index, tgt_dist) in buckets.get(&node_id).into_iter().flatten() {
+                        let dist = src_dist + tgt_dist;
+                        if !matches!(row[tgt_index], Some(prev_dist) if prev_dist <= dist) {
+                            row[tgt_index] = Some(dist);
+                        }
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+/// Get the path of the contraction hierarchy file of the graph file at `graph_file_path`
+pub fn ch_file_path(graph_file_path: &str) -> PathBuf {
+    Path::new(graph_file_path).with_extension("chbin")
+}
+
+/// Compute a checksum of the node coordinates and the edges of `graph` with the 64 bit FNV-1a
+/// hash, which does not change between builds, unlike the hashers of the standard library
+fn graph_checksum(graph: &Graph) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    let node_values = graph.nodes().iter()
+        .flat_map(|node| [node.lat.to_bits(), node.lon.to_bits()]);
+    let edge_values = graph.edges.iter()
+        .flat_map(|edge| [edge.src as u64, edge.tgt as u64, edge.dist as u64]);
+    node_values.chain(edge_values)
+        .flat_map(u64::to_le_bytes)
+        .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Parse the graph file at `fmi_file_path`, build its contraction hierarchy and write it next to
+/// the graph file
+pub fn parse_graph_and_write_contraction_hierarchy(fmi_file_path: &str) -> Result<(), io::Error> {
+    let graph = Graph::parse_from_file(fmi_file_path)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    ContractionHierarchy::build(&graph).write_for_graph(fmi_file_path)
+}
+
+/// Get the shortcuts required when contracting the node with id `node_id` as tuples of their
+/// source, target and length. A shortcut is required between two neighbours if the witness
+/// search finds no path between them without the node that is at most as long as the path via
+/// the node.
+fn find_shortcuts(neighbours: &[Vec<Neighbour>], node_id: usize,
+                  witness_search: &mut WitnessSearch) -> Vec<(usize, usize, usize)> {
+    let node_neighbours = &neighbours[node_id];
+    let mut shortcuts = vec![];
+    for (i, src) in node_neighbours.iter().enumerate() {
+        let tgts = &node_neighbours[i + 1..];
+        let max_dist = match tgts.iter().map(|tgt| tgt.dist).max() {
+            Some(max_tgt_dist) => src.dist + max_tgt_dist,
+            None => continue,
+        };
+        witness_search.run(neighbours, src.node_id, node_id, max_dist);
+        for tgt in tgts {
+            let dist = src.dist + tgt.dist;
+            if witness_search.dist_to(tgt.node_id) > dist {
+                shortcuts.push((src.node_id, tgt.node_id, dist));
+            }
+        }
+    }
+    shortcuts
+}
+
+/// Add an edge to the node with id `tgt_id` via the node with id `via_id` to `node_neighbours`
+/// or shorten the existing one, if the new edge is shorter
+fn add_or_shorten_edge(node_neighbours: &mut Vec<Neighbour>, tgt_id: usize, dist: usize, via_id: usize) {
+    match node_neighbours.iter_mut().find(|neighbour| neighbour.node_id == tgt_id) {
+        Some(neighbour) => if dist < neighbour.dist {
+            neighbour.dist = dist;
+            neighbour.via = Some(via_id);
+        },
+        None => node_neighbours.push(Neighbour { node_id: tgt_id, dist, via: Some(via_id) }),
+    }
+}
+
+/// Dijkstra on the nodes that are not contracted yet, which searches for paths that make a
+/// shortcut unnecessary. The distances are kept between runs and only the ones touched in the
+/// previous run are reset.
+struct WitnessSearch {
+    dists: Vec<usize>,
+    touched: Vec<usize>,
+    pq: BinaryHeap<Reverse<(usize, usize)>>,
+}
+
+impl WitnessSearch {
+    /// Create a new `WitnessSearch` for given graph size
+    fn new(num_nodes: usize) -> Self {
+        Self {
+            dists: vec![usize::MAX; num_nodes],
+            touched: vec![],
+            pq: BinaryHeap::new(),
+        }
+    }
+
+    /// Run a Dijkstra from the node with id `src_id` that avoids the node with id `skipped_id`
+    /// and stops at distance `max_dist` or after settling `MAX_WITNESS_SETTLED_NODES` nodes
+    fn run(&mut self, neighbours: &[Vec<Neighbour>], src_id: usize, skipped_id: usize, max_dist: usize) {
+        for node_id in self.touched.drain(..) {
+            self.dists[node_id] = usize::MAX;
+        }
+        self.pq.clear();
+        self.dists[src_id] = 0;
+        self.touched.push(src_id);
+        self.pq.push(Reverse((0, src_id)));
+
+        let mut num_settled = 0;
+        while let Some(Reverse((dist, node_id))) = self.pq.pop() {
+            if dist > self.dists[node_id] {
+                continue;
+            }
+            if dist > max_dist || num_settled == MAX_WITNESS_SETTLED_NODES {
+                break;
+            }
+            num_settled += 1;
+            for neighbour in &neighbours[node_id] {
+                if neighbour.node_id == skipped_id {
+                    continue;
+                }
+                let neighbour_dist = dist + neighbour.dist;
+                if neighbour_dist < self.dists[neighbour.node_id] {
+                    if self.dists[neighbour.node_id] == usize::MAX {
+                        self.touched.push(neighbour.node_id);
+                    }
+                    self.dists[neighbour.node_id] = neighbour_dist;
+                    self.pq.push(Reverse((neighbour_dist, neighbour.node_id)));
+                }
+            }
+        }
+    }
+
+    /// Returns the distance to the node with id `node_id` found by the last run, which is
+    /// `usize::MAX` if the node was not reached
+    fn dist_to(&self, node_id: usize) -> usize {
+        self.dists[node_id]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{Rng, thread_rng};
+    use crate::data::contraction_hierarchy::ContractionHierarchy;
+    use crate::data::graph::Graph;
+    use crate::init_logging;
+    use crate::utils::dijkstra::{run_dijkstra, run_many_to_many_dijkstra};
+    use crate::utils::test_setup;
+
+    #[test]
+    fn test_ch_shortest_path() {
+        init_logging();
+
+        let graph = &test_setup::CONTRACTED_GRAPH;
+        let ch = graph.contraction_hierarchy().expect("Test graph has no contraction hierarchy");
+
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            let src_id = rng.gen_range(0..graph.num_nodes);
+            let tgt_id = rng.gen_range(0..graph.num_nodes);
+
+            let exp_dist = run_dijkstra(graph, src_id, tgt_id).map(|result| result.dist());
+            assert_eq!(ch.dist(src_id, tgt_id), exp_dist, "Distances from {} to {} differ", src_id, tgt_id);
+
+            match ch.shortest_path(graph, src_id, tgt_id) {
+                Some(result) => {
+                    assert_eq!(Some(result.dist()), exp_dist);
+                    // the unpacked path with shape points is a path in the original graph
+                    let path = result.path();
+                    assert_eq!(path.first().unwrap().id, src_id);
+                    assert_eq!(path.last().unwrap().id, tgt_id);
+                    let path_len: usize = path.windows(2)
+                        .map(|pair| test_setup::GRAPH.get_edge(pair[0].id, pair[1].id)
+                            .expect("Path edge missing in original graph").dist)
+                        .sum();
+                    assert_eq!(path_len, result.dist());
+                }
+                None => assert!(exp_dist.is_none()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ch_many_to_many() {
+        init_logging();
+
+        let graph = &test_setup::CONTRACTED_GRAPH;
+        let ch = graph.contraction_hierarchy().expect("Test graph has no contraction hierarchy");
+
+        let mut rng = thread_rng();
+        let src_ids = (0..5).map(|_| rng.gen_range(0..graph.num_nodes)).collect::<Vec<_>>();
+        let mut tgt_ids = (0..6).map(|_| rng.gen_range(0..graph.num_nodes)).collect::<Vec<_>>();
+        // sources may be targets as well
+        tgt_ids.push(src_ids[0]);
+
+        let matrix = ch.many_to_many(&src_ids, &tgt_ids);
+
+        assert_eq!(matrix, run_many_to_many_dijkstra(graph, &src_ids, &tgt_ids));
+        assert_eq!(matrix[0][tgt_ids.len() - 1], Some(0));
+    }
+
+    #[test]
+    fn test_ch_rejected_for_modified_graph() {
+        init_logging();
+
+        // make sure that the graph and its contraction hierarchy are written
+        assert!(test_setup::CONTRACTED_GRAPH.contraction_hierarchy().is_some());
+
+        let mut graph = Graph::parse_from_file(test_setup::CONTRACTED_GRAPH_PATH).unwrap();
+        assert!(ContractionHierarchy::parse_for_graph(test_setup::CONTRACTED_GRAPH_PATH, &graph).is_some());
+        // same number of nodes and edges, but another weight
+        graph.edges[0].dist += 1;
+        assert!(ContractionHierarchy::parse_for_graph(test_setup::CONTRACTED_GRAPH_PATH, &graph).is_none());
+    }
+}
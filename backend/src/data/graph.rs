@@ -1,18 +1,22 @@
 use std::cmp::{min, max};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::fs::File;
 use std::hash::{Hash, Hasher};
-use std::io::{BufReader};
+use std::io::{BufReader, Read, Write};
 use std::num::{ParseFloatError, ParseIntError};
 use std::time::Instant;
 use strum_macros::EnumString;
+use chrono::{Datelike, Utc};
+use chrono_tz::Tz;
 use geoutils::{Distance, Location};
 use log::{debug, trace, info};
 use serde::{Serialize, Deserialize};
 use opening_hours::OpeningHours;
 use crate::data;
 use crate::data::SightsConfig;
+use crate::data::contraction_hierarchy::ContractionHierarchy;
+use crate::data::holidays::{HolidayCalendar, PUBLIC_HOLIDAY_YEARS_AHEAD};
 use crate::utils::dijkstra;
 
 #[derive(strum_macros::Display, EnumString, Deserialize, Serialize, PartialEq, Eq, Debug, Copy, Clone)]
@@ -97,6 +101,28 @@ impl Hash for Node {
     }
 }
 
+/// Magic bytes at the start of fmi binary files, followed by the format version. Files without
+/// them were written before the format was versioned.
+const GRAPH_FILE_MAGIC: &[u8; 4] = b"TSFG";
+
+/// Computes the smallest ratio of the weight of an edge and the haversine distance between its
+/// nodes over all edges with non-zero length. Edge weights are rounded down to whole meters, so the
+/// ratio of short edges lies well below one.
+fn compute_astar_scale(nodes: &[Node], edges: &[Edge]) -> f64 {
+    let location = |node_id: usize| Location::new(nodes[node_id].lat, nodes[node_id].lon);
+    let scale = edges.iter()
+        .filter(|edge| edge.dist > 0)
+        .map(|edge| edge.dist as f64
+            / location(edge.src).haversine_distance_to(&location(edge.tgt)).meters())
+        .fold(f64::INFINITY, f64::min);
+    // graphs without such edges have no paths to estimate
+    if scale.is_finite() { scale } else { 0.0 }
+}
+
+/// Version of the fmi binary file format written by the osm graph creator. Increase it whenever
+/// the format changes, such that outdated graph files are rebuilt.
+pub const GRAPH_FILE_VERSION: u32 = 6;
+
 /// A directed and weighted graph edge
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Edge {
@@ -108,6 +134,11 @@ pub struct Edge {
     pub dist: usize,
     /// The street type of the edge.
     pub edge_type: EdgeType,
+    /// The index of the street name of the edge in the graph, if its way has a name
+    pub name_id: Option<u32>,
+    /// The index of the shape points of the edge in the graph, if it replaces a chain of
+    /// contracted edges
+    pub shape_id: Option<u32>,
 }
 
 impl PartialEq<Self> for Edge {
@@ -139,7 +170,11 @@ pub struct Sight {
     pub opening_hours_parsed: Option<OpeningHours>,
     #[serde(skip_deserializing)]
     pub duration_of_stay_minutes: i64, //default 0 when not overwritten by set_config_duration_of_stay
-    pub wikidata_id: String
+    pub wikidata_id: String,
+    /// All OSM tags of the sight node. They are only used to evaluate exclusions and are not
+    /// part of any response.
+    #[serde(skip_serializing)]
+    pub tags: HashMap<String, String>,
 }
 
 impl Sight{
@@ -156,9 +191,10 @@ impl Sight{
     ///Tries to parse opening hours from osm and then sets opening_hours_parsed.
     ///If osm value cannot be parsed use default value from sights config
     ///Also overwrites opening_hours if default value is used
-    pub fn parse_opening_hours(&mut self, sights_config: &SightsConfig){
+    ///PH and SH rules are resolved with the given holiday calendar
+    pub fn parse_opening_hours(&mut self, sights_config: &SightsConfig, holidays: &HolidayCalendar){
         //Try to parse OSM opening hours
-        let opening_hours_parsed = match OpeningHours::parse(&self.opening_hours){
+        let opening_hours_parsed = match OpeningHours::parse(&holidays.resolve_holidays(&self.opening_hours)){
             Ok(res) => {
                 trace!("Using OSM Opening Times");
                 Some(res)
@@ -167,7 +203,7 @@ impl Sight{
             _ => {
                 trace!("Parsing Default Opening Times");
                 let default_openings = self.get_default_opening_hour(&sights_config);
-                let parse = OpeningHours::parse(&default_openings)
+                let parse = OpeningHours::parse(&holidays.resolve_holidays(&default_openings))
                     .expect("Could not parse default opening hours");
 
                 //override old (invalid) opening times with default
@@ -215,12 +251,76 @@ impl Debug for Sight {
             .field("name", &self.name)
             .field("opening_hours", &self.opening_hours)
             .field("duration_of_stay_minutes", &self.duration_of_stay_minutes)
+            .field("tags", &self.tags)
             .finish()
     }
 }
 
 
 
+/// An OSM tag predicate of the form `key=value`, which matches sights with the given tag, or of
+/// the form `key`, which matches sights that have the tag `key` with any value
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String")]
+pub struct TagPredicate {
+    key: String,
+    value: Option<String>,
+}
+
+impl TagPredicate {
+    /// Whether `sight` is matched by this predicate
+    pub fn matches(&self, sight: &Sight) -> bool {
+        match (sight.tags.get(&self.key), &self.value) {
+            (Some(sight_value), Some(value)) => sight_value == value,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+impl TryFrom<String> for TagPredicate {
+    type Error = String;
+
+    fn try_from(predicate: String) -> Result<Self, Self::Error> {
+        let (key, value) = match predicate.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (predicate.trim(), None),
+        };
+        if key.is_empty() {
+            return Err(format!("Invalid tag predicate: {}", predicate));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value,
+        })
+    }
+}
+
+/// Sights that must not be included in sight queries and routes
+///
+/// # Fields
+/// * `sights` - The node ids of excluded sights
+/// * `categories` - Excluded sight categories
+/// * `tags` - OSM tag predicates. Sights that match any of the predicates are excluded.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SightExclusions {
+    #[serde(default)]
+    pub sights: Vec<usize>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub tags: Vec<TagPredicate>,
+}
+
+impl SightExclusions {
+    /// Whether `sight` is excluded
+    pub fn excludes(&self, sight: &Sight) -> bool {
+        self.sights.contains(&sight.node_id)
+            || self.categories.contains(&sight.category)
+            || self.tags.iter().any(|predicate| predicate.matches(sight))
+    }
+}
+
 /// A directed graph. In addition to nodes and edges, the definition also contains a set of sights
 /// mapped on their nearest nodes, respectively.
 pub struct Graph {
@@ -232,6 +332,19 @@ pub struct Graph {
     pub num_edges: usize,
     pub sights: Vec<Sight>,
     pub num_sights: usize,
+    /// Interned street names referenced by the edges
+    street_names: Vec<String>,
+    /// Ids of the contracted nodes along the edges that replace chains of edges, in the order
+    /// from the edge's source to its target
+    edge_shapes: Vec<Vec<usize>>,
+    timezone: Tz,
+    /// Public and school holidays of the graphs region, which opening hours of sights refer to
+    holidays: HolidayCalendar,
+    /// Contraction hierarchy of the graph, if it was built by the osm graph creator
+    contraction_hierarchy: Option<ContractionHierarchy>,
+    /// Smallest ratio of the weight of an edge with non-zero length and the haversine distance
+    /// between its nodes
+    astar_scale: f64,
 }
 
 
@@ -245,9 +358,15 @@ impl Graph {
         let graph_file = File::open(graph_file_path)?;
         let mut graph_reader = BufReader::new(graph_file);
 
+        let version = read_header(&mut graph_reader);
+        if version != GRAPH_FILE_VERSION {
+            return Err(ParseError::Version { found: version, expected: GRAPH_FILE_VERSION });
+        }
         let nodes:Vec<Node> = bincode::deserialize_from(&mut graph_reader).unwrap();
         let mut sights:Vec<Sight> = bincode::deserialize_from(&mut graph_reader).unwrap();
         let edges:Vec<Edge> = bincode::deserialize_from(&mut graph_reader).unwrap();
+        let street_names:Vec<String> = bincode::deserialize_from(&mut graph_reader).unwrap();
+        let edge_shapes:Vec<Vec<usize>> = bincode::deserialize_from(&mut graph_reader).unwrap();
 
         let num_nodes = nodes.len();
         let num_sights = sights.len();
@@ -271,14 +390,29 @@ impl Graph {
 
         //Parse the opening hours to fill Opening_hours_parsed: Option<OpeningHours>
         //Also read duration_of_stay_minutes from sights config and set the value for the sight
+        //Public and school holidays are resolved with the holiday calendar of the region
+        //Public holidays are computed from the previous year on, school holidays are read from
+        //the holidays file
         let sights_config = data::get_sights_config();
+        let region_config = data::get_region_config();
+
+        //Read the timezone in which opening hours of sights are given from region config
+        let timezone: Tz = region_config.timezone.parse()
+            .expect("Invalid timezone in region config");
+
+        let this_year = Utc::now().with_timezone(&timezone).year();
+        let holidays = HolidayCalendar::load(&region_config.holidays_file, &region_config.region,
+                                             this_year - 1..=this_year + PUBLIC_HOLIDAY_YEARS_AHEAD);
         for sight in &mut sights{
-            sight.parse_opening_hours(&sights_config);
+            sight.parse_opening_hours(&sights_config, &holidays);
             sight.set_config_duration_of_stay(&sights_config);
         }
 
         //create node list sorted by lat
-        let mut node_ids_by_lat:Vec<usize> = (0..num_nodes).collect();
+        //nodes without edges are only shape points of contracted edges and cannot be reached
+        let mut node_ids_by_lat:Vec<usize> = (0..num_nodes)
+            .filter(|&node_id| offsets[node_id + 1] > offsets[node_id])
+            .collect();
         node_ids_by_lat.sort_unstable_by(|x, y|
             nodes.get(*x).unwrap().lat.total_cmp(&nodes.get(*y).unwrap().lat));
         
@@ -286,7 +420,9 @@ impl Graph {
         info!("End graph creation after {} seconds!", time_duration.as_millis() as f32 / 1000.0);
         info!("Graph has {} Nodes, {} Edges, {} Sights ", nodes.len(), edges.len(), sights.len());
 
-        Ok(Self {
+        let astar_scale = compute_astar_scale(&nodes, &edges);
+
+        let mut graph = Self {
             nodes,
             node_ids_by_lat: node_ids_by_lat,
             edges,
@@ -295,7 +431,55 @@ impl Graph {
             num_edges,
             sights,
             num_sights,
-        })
+            street_names,
+            edge_shapes,
+            timezone,
+            holidays,
+            contraction_hierarchy: None,
+            astar_scale,
+        };
+        graph.contraction_hierarchy = ContractionHierarchy::parse_for_graph(graph_file_path, &graph);
+        Ok(graph)
+    }
+
+    /// Get the contraction hierarchy of the graph, if it was built by the osm graph creator
+    pub fn contraction_hierarchy(&self) -> Option<&ContractionHierarchy> {
+        self.contraction_hierarchy.as_ref()
+    }
+
+    /// Get the factor by which the A* search scales the haversine distance to its target, such that
+    /// the estimate never exceeds the weight of an edge with non-zero length
+    pub fn astar_scale(&self) -> f64 {
+        self.astar_scale
+    }
+
+    /// Get the street name of `edge`, if its way has a name
+    pub fn get_street_name(&self, edge: &Edge) -> Option<&str> {
+        edge.name_id.map(|name_id| self.street_names[name_id as usize].as_str())
+    }
+
+    /// Get the ids of the shape points of `edge` in the order from its source to its target.
+    /// Edges that were not contracted have no shape points.
+    pub fn get_edge_shape(&self, edge: &Edge) -> &[usize] {
+        match edge.shape_id {
+            Some(shape_id) => &self.edge_shapes[shape_id as usize],
+            None => &[],
+        }
+    }
+
+    /// Get the nodes of the path through the nodes with the given ids, including the shape
+    /// points of contracted edges between consecutive nodes
+    pub fn expand_path(&self, node_ids: impl IntoIterator<Item = usize>) -> Vec<&Node> {
+        let mut path = Vec::new();
+        let mut prev_id = None;
+        for node_id in node_ids {
+            if let Some(edge) = prev_id.and_then(|prev_id| self.get_edge(prev_id, node_id)) {
+                path.extend(self.get_edge_shape(edge).iter().map(|&shape_id| self.get_node(shape_id)));
+            }
+            path.push(self.get_node(node_id));
+            prev_id = Some(node_id);
+        }
+        path
     }
 
     /// Returns a reference to the vector containing all nodes in this graph
@@ -303,6 +487,17 @@ impl Graph {
         &self.nodes
     }
 
+    /// Returns the IANA timezone of the graphs region. Opening hours of sights refer to the
+    /// local time in this timezone.
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Returns the holiday calendar of the graphs region
+    pub fn holidays(&self) -> &HolidayCalendar {
+        &self.holidays
+    }
+
     /// Get the node with id `node_id`
     pub fn get_node(&self, node_id: usize) -> &Node {
         &self.nodes[node_id]
@@ -320,6 +515,7 @@ impl Graph {
     /// Uses the internal naive implementation.
     pub fn get_nearest_node_naive(&self, lat: f64, lon: f64) -> usize {
         let id_filter = self.sights.iter().map(|sight| sight.node_id)
+            .chain(self.nodes.iter().filter(|node| self.get_degree(node.id) == 0).map(|node| node.id))
             .collect();
         get_nearest_node_naive(&self.nodes, &id_filter, lat, lon)
     }
@@ -357,6 +553,14 @@ impl Graph {
         &self.edges[self.offsets[node_id]..self.offsets[node_id + 1]]
     }
 
+    /// Get the edge from the node with id `src` to the node with id `tgt`, if there is one
+    pub fn get_edge(&self, src: usize, tgt: usize) -> Option<&Edge> {
+        let out_edges = self.get_outgoing_edges(src);
+        // outgoing edges are sorted by their target
+        out_edges.binary_search_by_key(&tgt, |edge| edge.tgt).ok()
+            .map(|index| &out_edges[index])
+    }
+
     /// Get all outgoing edges of a particular node where the edge target lies within given area
     pub fn get_outgoing_edges_in_area(&self, node_id: usize, lat: f64, lon: f64, radius: f64) -> Vec<&Edge> {
         let center = Location::new(lat, lon);
@@ -401,13 +605,16 @@ impl Graph {
     /// Get all reachable sights within a circular area, specified by `radius` (in meters), around a given coordinate
     /// (latitude / longitude).
     /// `reachable_with` specifies within which radius reachability must be tested.
-    pub fn get_reachable_sights_in_area(&self, lat: f64, lon: f64, radius: f64, reachable_within: f64) -> Vec<&Sight> {
+    /// Sights that are excluded by `exclusions` are skipped.
+    pub fn get_reachable_sights_in_area(&self, lat: f64, lon: f64, radius: f64, reachable_within: f64,
+                                        exclusions: &SightExclusions) -> Vec<&Sight> {
         // Get all nodes that are reachable from the node with the lowest distance to the center
         let center_id = self.get_nearest_node(lat, lon);
         let reachable_nodes = dijkstra::run_ota_dijkstra_in_area(
             &self, center_id, lat, lon, reachable_within);
 
         let reachable_sights: Vec<&Sight> = self.get_sights_in_area(lat, lon, radius).into_iter()
+            .filter(|sight| !exclusions.excludes(sight))
             .filter(|sight | reachable_nodes.dist_to(sight.node_id).is_some())
             .collect();
         debug!("Found {} reachable sights within the given area (of a total of {} sights)",
@@ -415,6 +622,83 @@ impl Graph {
 
         reachable_sights
     }
+
+    /// Get all sights within a corridor around the straight line between two coordinates
+    /// (latitude / longitude), i.e. all sights whose distance to this line is at most `radius`
+    /// (in meters)
+    pub fn get_sights_in_corridor(&self, src_lat: f64, src_lon: f64, tgt_lat: f64, tgt_lon: f64,
+                                  radius: f64) -> Vec<&Sight> {
+        debug!("Computing sights in corridor: from: ({}, {}), to: ({}, {}), radius: {}",
+            src_lat, src_lon, tgt_lat, tgt_lon, radius);
+
+        //estimate bounding box with 111111 meters = 1 longitude degree
+        //use binary search to find the range of elements that should be considered
+        let lower_bound = binary_search_sights_vector(
+            &self.sights, src_lat.min(tgt_lat) - radius / 111111.0);
+        let upper_bound = binary_search_sights_vector(
+            &self.sights, src_lat.max(tgt_lat) + radius / 111111.0);
+
+        let slice = &self.sights[lower_bound..upper_bound];
+
+        //iterate through the slice and check every sight whether it's in the target corridor
+        let sights_in_corridor: Vec<&Sight> = slice.iter()
+            .filter(|sight| dist_to_segment(sight.lat, sight.lon,
+                                            src_lat, src_lon, tgt_lat, tgt_lon) <= radius)
+            .collect();
+        debug!("Found {} sights within the given corridor (of a total of {} sights)",
+            sights_in_corridor.len(), self.sights.len());
+
+        sights_in_corridor
+    }
+
+    /// Get all reachable sights within a corridor around the straight line between two
+    /// coordinates (latitude / longitude), i.e. all sights whose distance to this line is at most
+    /// `radius` (in meters).
+    /// `reachable_within` specifies within which radius around the midpoint of the line
+    /// reachability must be tested.
+    /// Sights that are excluded by `exclusions` are skipped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_reachable_sights_in_corridor(&self, src_lat: f64, src_lon: f64, tgt_lat: f64, tgt_lon: f64,
+                                            radius: f64, reachable_within: f64,
+                                            exclusions: &SightExclusions) -> Vec<&Sight> {
+        // Get all nodes that are reachable from the node with the lowest distance to the source
+        let src_id = self.get_nearest_node(src_lat, src_lon);
+        let (center_lat, center_lon) = ((src_lat + tgt_lat) / 2.0, (src_lon + tgt_lon) / 2.0);
+        let reachable_nodes = dijkstra::run_ota_dijkstra_in_area(
+            self, src_id, center_lat, center_lon, reachable_within);
+
+        let reachable_sights: Vec<&Sight> = self.get_sights_in_corridor(
+            src_lat, src_lon, tgt_lat, tgt_lon, radius).into_iter()
+            .filter(|sight| !exclusions.excludes(sight))
+            .filter(|sight | reachable_nodes.dist_to(sight.node_id).is_some())
+            .collect();
+        debug!("Found {} reachable sights within the given corridor (of a total of {} sights)",
+            reachable_sights.len(), self.sights.len());
+
+        reachable_sights
+    }
+}
+
+/// Helper method to approximate the distance (in meters) between a coordinate and the straight
+/// line between two other coordinates.
+/// Uses an equirectangular projection, which is sufficiently exact for distances within a city.
+fn dist_to_segment(lat: f64, lon: f64, src_lat: f64, src_lon: f64, tgt_lat: f64, tgt_lon: f64) -> f64 {
+    const METERS_PER_DEGREE: f64 = 111111.0;
+    let lon_scale = ((src_lat + tgt_lat) / 2.0).to_radians().cos();
+    let to_xy = |lat: f64, lon: f64|
+        ((lon - src_lon) * lon_scale * METERS_PER_DEGREE, (lat - src_lat) * METERS_PER_DEGREE);
+
+    let (px, py) = to_xy(lat, lon);
+    let (tx, ty) = to_xy(tgt_lat, tgt_lon);
+    let segment_len_sq = tx * tx + ty * ty;
+    // Position of the projection of the point onto the segment, clamped to the segment
+    let t = if segment_len_sq > 0.0 {
+        ((px * tx + py * ty) / segment_len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (dx, dy) = (px - t * tx, py - t * ty);
+    (dx * dx + dy * dy).sqrt()
 }
 
 /// Helper method to estimate index bounds within the sights vector for latitude coordinates
@@ -512,6 +796,32 @@ pub enum ParseError {
     IO(std::io::Error),
     ParseInt(ParseIntError),
     ParseFloat(ParseFloatError),
+    /// The graph file was written in another format version
+    Version { found: u32, expected: u32 },
+}
+
+/// Read the format version of the fmi binary file at `graph_file_path`.
+/// Files written before the version was introduced have version 0.
+pub fn read_graph_file_version(graph_file_path: &str) -> std::io::Result<u32> {
+    let mut graph_reader = BufReader::new(File::open(graph_file_path)?);
+    Ok(read_header(&mut graph_reader))
+}
+
+/// Write the header of an fmi binary file, i.e. the magic bytes and the format version
+pub fn write_graph_file_header<W: Write>(writer: &mut W) -> std::io::Result<()> {
+    writer.write_all(GRAPH_FILE_MAGIC)?;
+    // Same encoding as bincode
+    writer.write_all(&GRAPH_FILE_VERSION.to_le_bytes())
+}
+
+/// Read the header of an fmi binary file and return its format version, or 0 if the file does
+/// not start with a valid header
+fn read_header<R: Read>(reader: &mut R) -> u32 {
+    let mut magic = [0; 4];
+    if reader.read_exact(&mut magic).is_err() || &magic != GRAPH_FILE_MAGIC {
+        return 0;
+    }
+    bincode::deserialize_from(reader).unwrap_or_default()
 }
 
 impl std::fmt::Display for ParseError {
@@ -520,6 +830,9 @@ impl std::fmt::Display for ParseError {
             Self::IO(err) => write!(f, "{}", err.to_string()),
             Self::ParseInt(err) => write!(f, "{}", err.to_string()),
             Self::ParseFloat(err) => write!(f, "{}", err.to_string()),
+            Self::Version { found, expected } => write!(
+                f, "Graph file has format version {}, but version {} is required. Rebuild it from the osm data.",
+                found, expected),
         }
     }
 }
@@ -530,6 +843,7 @@ impl std::error::Error for ParseError {
             Self::IO(ref err) => Some(err),
             Self::ParseInt(ref err) => Some(err),
             Self::ParseFloat(ref err) => Some(err),
+            Self::Version { .. } => None,
         }
     }
 }
@@ -555,11 +869,12 @@ impl From<ParseFloatError> for ParseError {
 #[cfg(test)]
 mod test {
     use std::collections::HashSet;
+    use std::fs::File;
     use std::time::Instant;
     use geoutils::{Distance, Location};
     use log::{debug, trace, info};
     use rand::{Rng, thread_rng};
-    use crate::data::graph::{get_nearest_node_naive, Node};
+    use crate::data::graph::{Category, get_nearest_node_naive, Graph, GRAPH_FILE_VERSION, Node, ParseError, SightExclusions, TagPredicate};
     use crate::init_logging;
     use crate::utils::test_setup;
 
@@ -619,4 +934,73 @@ mod test {
         assert_eq!(actual, expected, "Expected nearest node: {} with dist: {}, got: {} with dist: {} from efficient implementation",
                    expected, expected_dist, actual, actual_dist);
     }
+
+    #[test]
+    fn test_parse_tag_predicates() {
+        let exclusions: SightExclusions = serde_json::from_str(
+            r#"{"categories": ["Nightlife"], "tags": ["fee=yes", "wheelchair"]}"#).unwrap();
+        assert!(exclusions.sights.is_empty());
+        assert_eq!(exclusions.categories, vec![Category::Nightlife]);
+        assert_eq!(exclusions.tags, vec![
+            TagPredicate { key: "fee".to_string(), value: Some("yes".to_string()) },
+            TagPredicate { key: "wheelchair".to_string(), value: None },
+        ]);
+        assert!(serde_json::from_str::<SightExclusions>(r#"{"tags": ["=yes"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_reachable_sights_respect_exclusions() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let (lat, lon) = RADISSON_BLU_HOTEL;
+        let sights = graph.get_reachable_sights_in_area(
+            lat, lon, 1000.0, 2000.0, &SightExclusions::default());
+        let sight = sights.first().expect("No reachable sights found");
+
+        let excluded_by_id = SightExclusions {
+            sights: vec![sight.node_id],
+            ..Default::default()
+        };
+        let excluded_by_category = SightExclusions {
+            categories: vec![sight.category],
+            ..Default::default()
+        };
+        let (key, value) = sight.tags.iter().next().expect("Sight without tags");
+        let excluded_by_tag = SightExclusions {
+            tags: vec![TagPredicate::try_from(format!("{}={}", key, value)).unwrap()],
+            ..Default::default()
+        };
+        for exclusions in [excluded_by_id, excluded_by_category, excluded_by_tag] {
+            let remaining = graph.get_reachable_sights_in_area(lat, lon, 1000.0, 2000.0, &exclusions);
+            assert!(remaining.len() < sights.len(), "No sights excluded by {:?}", exclusions);
+            assert!(remaining.iter().all(|remaining_sight| !exclusions.excludes(remaining_sight)),
+                    "Excluded sight returned for {:?}", exclusions);
+        }
+    }
+
+    #[test]
+    fn test_graph_file_without_header_rejected() {
+        let graph_file_path = std::env::temp_dir().join("trailscout_graph_without_header.fmibin");
+        // Graph files without header start with the nodes
+        let nodes: Vec<Node> = vec![];
+        bincode::serialize_into(File::create(&graph_file_path).unwrap(), &nodes).unwrap();
+        match Graph::parse_from_file(graph_file_path.to_str().unwrap()) {
+            Err(ParseError::Version { found, expected }) => {
+                assert_eq!(found, 0);
+                assert_eq!(expected, GRAPH_FILE_VERSION);
+            }
+            _ => panic!("Graph file without header accepted"),
+        }
+    }
+
+    #[test]
+    fn test_sight_tags_not_serialized() {
+        let sight = test_setup::GRAPH.sights.iter()
+            .find(|sight| !sight.tags.is_empty())
+            .expect("No sight with tags");
+        let sight_json = serde_json::to_value(sight).unwrap();
+        assert!(sight_json.get("tags").is_none());
+        assert_eq!(sight_json["node_id"], sight.node_id);
+    }
 }
@@ -0,0 +1,400 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use chrono::{Datelike, Duration, NaiveDate};
+use itertools::Itertools;
+use log::warn;
+use serde::Deserialize;
+
+/// Opening hours selector for public holidays
+const PUBLIC_HOLIDAY: &str = "PH";
+/// Opening hours selector for school holidays
+const SCHOOL_HOLIDAY: &str = "SH";
+
+/// Separator of normal rule sequences in opening hours
+const NORMAL_RULE_SEPARATOR: &str = ";";
+/// Separator of additional rule sequences in opening hours, i.e. a comma followed by a space
+const ADDITIONAL_RULE_SEPARATOR: &str = ",";
+/// Separator of fallback rule sequences in opening hours
+const FALLBACK_RULE_SEPARATOR: &str = "||";
+
+/// Number of years after the current year for which public holidays are computed
+pub const PUBLIC_HOLIDAY_YEARS_AHEAD: i32 = 2;
+
+//Deserialization of the holidays file
+#[derive(Deserialize)]
+struct HolidaysFile {
+    regions: Vec<RegionHolidays>,
+}
+
+#[derive(Deserialize)]
+struct RegionHolidays {
+    region: String,
+    public_holidays: PublicHolidayRules,
+    school_holidays: Vec<DateRange>,
+}
+
+/// Rules from which the public holidays of a region are computed for any year
+#[derive(Deserialize)]
+struct PublicHolidayRules {
+    //Holidays on a fixed day of the year, given as "MM-DD"
+    fixed: Vec<String>,
+    //Holidays relative to Easter Sunday, given as offset in days
+    easter_offsets: Vec<i64>,
+}
+
+impl PublicHolidayRules {
+    /// Computes the public holidays of all `years` in ascending order
+    fn holidays(&self, years: RangeInclusive<i32>) -> Vec<NaiveDate> {
+        years.flat_map(|year| {
+            let fixed = self.fixed.iter().map(move |day| {
+                NaiveDate::parse_from_str(&format!("{}-{}", year, day), "%Y-%m-%d")
+                    .expect("Invalid fixed public holiday in holidays file")
+            });
+            let easter = easter_sunday(year);
+            let movable = self.easter_offsets.iter()
+                .map(move |&offset| easter + Duration::days(offset));
+            fixed.chain(movable).sorted()
+        }).collect()
+    }
+}
+
+/// Computes Easter Sunday of `year` in the Gregorian calendar
+/// (anonymous Gregorian algorithm by Meeus, Jones and Butcher)
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+/// Inclusive range of dates
+#[derive(Deserialize, Clone, Debug)]
+pub struct DateRange {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl DateRange {
+    /// Whether `date` lies within this range
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// Public and school holidays of a region.
+///
+/// The `opening-hours` crate only ships public holidays for a fixed set of regions and ignores
+/// school holidays. Therefore, the calendar resolves `PH` and `SH` selectors in opening hours
+/// into explicit date selectors before the opening hours are parsed.
+/// On dates outside the calendar, resolved holiday selectors never match, just like unresolved
+/// ones.
+#[derive(Default, Debug)]
+pub struct HolidayCalendar {
+    public_holidays: Vec<NaiveDate>,
+    school_holidays: Vec<DateRange>,
+}
+
+impl HolidayCalendar {
+    /// Creates a new holiday calendar from given public and school holidays
+    pub fn new(public_holidays: Vec<NaiveDate>, school_holidays: Vec<DateRange>) -> Self {
+        Self {
+            public_holidays,
+            school_holidays,
+        }
+    }
+
+    /// Loads the holiday calendar of a region from a holidays file.
+    /// Public holidays are computed for all `years`, school holidays are taken from the file.
+    /// Returns an empty calendar if the file contains no holidays for the region.
+    ///
+    /// # Arguments
+    /// * `holidays_file_path` - Path to the JSON file containing the holidays of all regions
+    /// * `region` - The region code, e.g. "DE-BW"
+    /// * `years` - The years for which public holidays are computed
+    pub fn load(holidays_file_path: &str, region: &str, years: RangeInclusive<i32>) -> Self {
+        let data = fs::read_to_string(holidays_file_path).expect("Unable to read file");
+        let holidays_file: HolidaysFile = serde_json::from_str(&data).expect("Unable to parse");
+        match holidays_file.regions.into_iter().find(|holidays| holidays.region == region) {
+            Some(holidays) => Self::new(holidays.public_holidays.holidays(years),
+                                        holidays.school_holidays),
+            None => {
+                warn!("No holidays found for region {}", region);
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether the public holidays of this calendar cover the year of `date`
+    pub fn covers_public_holidays(&self, date: NaiveDate) -> bool {
+        match (self.public_holidays.first(), self.public_holidays.last()) {
+            (Some(first), Some(last)) => first.year() <= date.year() && date.year() <= last.year(),
+            _ => false,
+        }
+    }
+
+    /// Whether the school holidays of this calendar cover `date`, i.e. `date` lies between the
+    /// start of the first and the end of the last school holidays
+    pub fn covers_school_holidays(&self, date: NaiveDate) -> bool {
+        match (self.school_holidays.iter().map(|range| range.start).min(),
+               self.school_holidays.iter().map(|range| range.end).max()) {
+            (Some(start), Some(end)) => DateRange { start, end }.contains(date),
+            _ => false,
+        }
+    }
+
+    /// Logs a warning for each kind of holidays that this calendar does not cover on `date`.
+    /// `PH` and `SH` rules of opening hours do not apply on such dates.
+    pub fn warn_if_not_covered(&self, date: NaiveDate) {
+        if !self.covers_public_holidays(date) {
+            warn!("No public holidays known for {}, PH rules in opening hours are ignored", date);
+        }
+        if !self.covers_school_holidays(date) {
+            warn!("No school holidays known for {}, SH rules in opening hours are ignored", date);
+        }
+    }
+
+    /// Replaces `PH` and `SH` selectors in given opening hours by the dates of this calendar.
+    ///
+    /// A rule sequence whose weekday selector contains holidays, e.g. "Su,PH 10:00-12:00", is
+    /// split into a rule sequence for the remaining weekdays and one for the holiday dates, i.e.
+    /// "Su 10:00-12:00; 2022 Dec 25,2022 Dec 26 10:00-12:00". Both are joined by the separator
+    /// that precedes the original rule sequence, such that the holiday rule keeps its original
+    /// precedence.
+    /// Holiday selectors for which the calendar contains no dates are left untouched.
+    pub fn resolve_holidays(&self, opening_hours: &str) -> String {
+        split_rule_sequences(opening_hours).iter()
+            .enumerate()
+            .map(|(i, &(separator, rule_sequence))| {
+                let resolved = self.resolve_rule_sequence(rule_sequence, separator);
+                if i == 0 {
+                    resolved
+                } else {
+                    format!("{}{}", format_separator(separator), resolved)
+                }
+            })
+            .join("")
+    }
+
+    /// Resolve holiday selectors in a single rule sequence that is preceded by `separator`
+    fn resolve_rule_sequence(&self, rule_sequence: &str, separator: &str) -> String {
+        let rule_sequence = rule_sequence.trim();
+        let (selector, rest) = match rule_sequence.split_once(char::is_whitespace) {
+            Some((selector, rest)) => (selector, rest.trim_start()),
+            None => (rule_sequence, ""),
+        };
+
+        let (holidays, weekdays): (Vec<&str>, Vec<&str>) = selector.split(',')
+            .partition(|&item| self.resolves(item));
+        if holidays.is_empty() {
+            return rule_sequence.to_string();
+        }
+
+        let dates = holidays.iter()
+            .flat_map(|&holiday| self.date_selectors(holiday))
+            .join(",");
+        let holiday_rule_sequence = format!("{} {}", dates, rest);
+        if weekdays.is_empty() {
+            holiday_rule_sequence.trim_end().to_string()
+        } else {
+            let weekday_rule_sequence = format!("{} {}", weekdays.join(","), rest);
+            format!("{}{}{}", weekday_rule_sequence.trim_end(), format_separator(separator),
+                    holiday_rule_sequence.trim_end())
+        }
+    }
+
+    /// Whether `selector` is a holiday selector that can be resolved with this calendar
+    fn resolves(&self, selector: &str) -> bool {
+        match selector {
+            PUBLIC_HOLIDAY => !self.public_holidays.is_empty(),
+            SCHOOL_HOLIDAY => !self.school_holidays.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Get the date selectors for a holiday selector
+    fn date_selectors(&self, holiday: &str) -> Vec<String> {
+        match holiday {
+            PUBLIC_HOLIDAY => self.public_holidays.iter()
+                .map(format_date)
+                .collect(),
+            SCHOOL_HOLIDAY => self.school_holidays.iter()
+                .map(|range| format!("{}-{}", format_date(&range.start), format_date(&range.end)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Format a date as opening hours date selector, e.g. "2022 Dec 25"
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y %b %d").to_string()
+}
+
+/// Format a rule separator for joining rule sequences. The first rule sequence of opening hours
+/// has no separator and is treated as if preceded by a normal rule separator.
+fn format_separator(separator: &str) -> &'static str {
+    match separator {
+        ADDITIONAL_RULE_SEPARATOR => ", ",
+        FALLBACK_RULE_SEPARATOR => " || ",
+        _ => "; ",
+    }
+}
+
+/// Split opening hours into their rule sequences, ignoring separators within comments.
+/// Each rule sequence is returned together with the separator that precedes it, i.e. one of
+/// `;`, `,` (followed by a space) and `||`, or an empty string for the first rule sequence.
+fn split_rule_sequences(opening_hours: &str) -> Vec<(&str, &str)> {
+    let mut rule_sequences = vec![];
+    let mut in_comment = false;
+    let mut start = 0;
+    let mut separator = "";
+    let mut chars = opening_hours.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, next)| next);
+        let next_separator = match (c, next) {
+            ('"', _) => {
+                in_comment = !in_comment;
+                None
+            }
+            _ if in_comment => None,
+            (';', _) => Some(NORMAL_RULE_SEPARATOR),
+            (',', Some(next)) if next.is_whitespace() => Some(ADDITIONAL_RULE_SEPARATOR),
+            ('|', Some('|')) => {
+                chars.next();
+                Some(FALLBACK_RULE_SEPARATOR)
+            }
+            _ => None,
+        };
+        if let Some(next_separator) = next_separator {
+            rule_sequences.push((separator, &opening_hours[start..i]));
+            separator = next_separator;
+            start = i + next_separator.len();
+        }
+    }
+    rule_sequences.push((separator, &opening_hours[start..]));
+    rule_sequences.into_iter()
+        .filter(|(_, rule_sequence)| !rule_sequence.trim().is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use opening_hours::OpeningHours;
+    use opening_hours_syntax::rules::RuleKind;
+    use crate::data::holidays::{DateRange, easter_sunday, HolidayCalendar};
+
+    fn calendar() -> HolidayCalendar {
+        HolidayCalendar::new(
+            vec![NaiveDate::from_ymd_opt(2022, 12, 25).unwrap(),
+                 NaiveDate::from_ymd_opt(2022, 12, 26).unwrap()],
+            vec![DateRange {
+                start: NaiveDate::from_ymd_opt(2022, 12, 23).unwrap(),
+                end: NaiveDate::from_ymd_opt(2023, 1, 6).unwrap(),
+            }])
+    }
+
+    fn state(opening_hours: &str, time: &str) -> RuleKind {
+        let opening_hours = OpeningHours::parse(&calendar().resolve_holidays(opening_hours))
+            .unwrap();
+        let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").unwrap();
+        opening_hours.state(time).unwrap()
+    }
+
+    #[test]
+    fn test_opening_hours_without_holidays_unchanged() {
+        let opening_hours = "Mo-Fr 10:00-18:00; Sa 10:00-14:00 \"by appointment; call first\"";
+        assert_eq!(calendar().resolve_holidays(opening_hours), opening_hours);
+    }
+
+    #[test]
+    fn test_public_holiday_off() {
+        let opening_hours = "Mo-Fr 10:00-18:00; PH off";
+        assert_eq!(calendar().resolve_holidays(opening_hours),
+                   "Mo-Fr 10:00-18:00; 2022 Dec 25,2022 Dec 26 off");
+        // Boxing day 2022 is a Monday
+        assert_eq!(state(opening_hours, "2022-12-26T11:00:00"), RuleKind::Closed);
+        assert_eq!(state(opening_hours, "2022-12-27T11:00:00"), RuleKind::Open);
+    }
+
+    #[test]
+    fn test_public_holiday_in_weekday_selector() {
+        let opening_hours = "Mo-Sa 10:00-18:00; Su,PH 12:00-14:00";
+        assert_eq!(state(opening_hours, "2022-12-26T11:00:00"), RuleKind::Closed);
+        assert_eq!(state(opening_hours, "2022-12-26T13:00:00"), RuleKind::Open);
+        // Christmas day 2022 is a Sunday
+        assert_eq!(state(opening_hours, "2022-12-25T13:00:00"), RuleKind::Open);
+    }
+
+    #[test]
+    fn test_school_holidays() {
+        let opening_hours = "Mo-Fr 08:00-16:00; SH off";
+        assert_eq!(state(opening_hours, "2023-01-05T11:00:00"), RuleKind::Closed);
+        assert_eq!(state(opening_hours, "2023-01-09T11:00:00"), RuleKind::Open);
+    }
+
+    #[test]
+    fn test_empty_calendar_keeps_holiday_selectors() {
+        let opening_hours = "Mo-Fr 10:00-18:00; PH off";
+        assert_eq!(HolidayCalendar::default().resolve_holidays(opening_hours), opening_hours);
+    }
+
+    #[test]
+    fn test_public_holiday_in_additional_rule() {
+        let opening_hours = "Mo-Fr 10:00-18:00, PH off";
+        assert_eq!(calendar().resolve_holidays(opening_hours),
+                   "Mo-Fr 10:00-18:00, 2022 Dec 25,2022 Dec 26 off");
+        assert_eq!(state(opening_hours, "2022-12-26T11:00:00"), RuleKind::Closed);
+        assert_eq!(state(opening_hours, "2022-12-27T11:00:00"), RuleKind::Open);
+    }
+
+    #[test]
+    fn test_public_holiday_in_fallback_rule() {
+        let opening_hours = "Mo-Fr 10:00-18:00 || Su,PH 12:00-14:00";
+        assert_eq!(calendar().resolve_holidays(opening_hours),
+                   "Mo-Fr 10:00-18:00 || Su 12:00-14:00 || 2022 Dec 25,2022 Dec 26 12:00-14:00");
+        // Christmas day 2022 is a Sunday
+        assert_eq!(state(opening_hours, "2022-12-25T13:00:00"), RuleKind::Open);
+        assert_eq!(state(opening_hours, "2022-12-25T11:00:00"), RuleKind::Closed);
+    }
+
+    #[test]
+    fn test_easter_sunday() {
+        assert_eq!(easter_sunday(2019), NaiveDate::from_ymd_opt(2019, 4, 21).unwrap());
+        assert_eq!(easter_sunday(2024), NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+        assert_eq!(easter_sunday(2025), NaiveDate::from_ymd_opt(2025, 4, 20).unwrap());
+        assert_eq!(easter_sunday(2026), NaiveDate::from_ymd_opt(2026, 4, 5).unwrap());
+    }
+
+    #[test]
+    fn test_load_computes_public_holidays_for_requested_years() {
+        let calendar = HolidayCalendar::load("./holidays.json", "DE-BW", 2026..=2027);
+        // Good Friday, Corpus Christi and Epiphany 2026
+        for (month, day) in [(4, 3), (6, 4), (1, 6)] {
+            let date = NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+            assert!(calendar.public_holidays.contains(&date));
+        }
+        assert!(calendar.covers_public_holidays(NaiveDate::from_ymd_opt(2027, 12, 31).unwrap()));
+        assert!(!calendar.covers_public_holidays(NaiveDate::from_ymd_opt(2028, 1, 1).unwrap()));
+        assert!(!calendar.covers_public_holidays(NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()));
+    }
+
+    #[test]
+    fn test_school_holidays_coverage() {
+        assert!(calendar().covers_school_holidays(NaiveDate::from_ymd_opt(2023, 1, 6).unwrap()));
+        assert!(!calendar().covers_school_holidays(NaiveDate::from_ymd_opt(2023, 1, 7).unwrap()));
+        assert!(!HolidayCalendar::default()
+            .covers_school_holidays(NaiveDate::from_ymd_opt(2023, 1, 6).unwrap()));
+    }
+}
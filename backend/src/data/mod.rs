@@ -1,4 +1,6 @@
+pub mod contraction_hierarchy;
 pub mod graph;
+pub mod holidays;
 pub mod osm_graph_creator;
 
 
@@ -10,6 +12,7 @@ use serde::Deserialize;
 
 const SIGHTS_CONFIG_PATH :&str = "./sights_config.json";
 const EDGE_CONFIG_PATH :&str = "./edge_type_config.json";
+const REGION_CONFIG_PATH :&str = "./region_config.json";
 
 //Deserialization of sights_config
 #[derive(Deserialize)]
@@ -44,6 +47,17 @@ pub struct EdgeTypeMap {
     tags: Vec<Tag>,
 }
 
+//Deserialization of region_config
+#[derive(Deserialize)]
+pub struct RegionConfig {
+    //IANA timezone name, e.g. "Europe/Berlin"
+    timezone: String,
+    //Region code used to look up public and school holidays, e.g. "DE-BW"
+    region: String,
+    //Path to the file containing the holidays of all regions
+    holidays_file: String,
+}
+
 
 
 //read config at SIGHTS_CONFIG_PATH and return it
@@ -59,3 +73,9 @@ pub fn get_edge_type_config() -> EdgeTypeConfig {
     let edge_type_config: EdgeTypeConfig = serde_json::from_str(&data).expect("Unable to parse");
     return edge_type_config;
 }
+
+//read config at REGION_CONFIG_PATH and return it
+pub fn get_region_config() -> RegionConfig {
+    let data = fs::read_to_string(REGION_CONFIG_PATH).expect("Unable to read file");
+    serde_json::from_str(&data).expect("Unable to parse")
+}
@@ -12,7 +12,7 @@ use geoutils::{Distance, Location};
 use log::{debug, info, trace};
 use osmpbf::{BlobReader, BlobType, Element, Way};
 use crate::data;
-use crate::data::graph::{Category, EdgeType, get_nearest_node, INode};
+use crate::data::graph::{Category, EdgeType, get_nearest_node, GRAPH_FILE_VERSION, INode, read_graph_file_version, write_graph_file_header};
 use crate::data::{EdgeTypeConfig, SightsConfig};
 
 /// An osm node located at a specific coordinate extraced from the osm data.
@@ -73,6 +73,11 @@ struct OSMEdge {
     dist: usize,
     /// The street type of the edge.
     edge_type: EdgeType,
+    /// The index of the name of the edge's way. While parsing, the index refers to the names of
+    /// the parsed blob, afterwards to the interned street names of the graph.
+    name_id: Option<u32>,
+    /// The index of the shape points of the edge, if it replaces a chain of contracted edges
+    shape_id: Option<u32>,
 }
 
 impl PartialEq<Self> for OSMEdge {
@@ -101,13 +106,21 @@ struct OSMSight {
     category: Category,
     name: String,
     opening_hours: String,
-    wikidata_id: String
+    wikidata_id: String,
+    tags: HashMap<String, String>,
 }
 
-/// Parse given `graph_file`. If it does not exist yet, build it from `source_file` first.
-pub fn checked_create_fmi_graph(graph_file: &str, osm_source_file: &str) -> io::Result<()> {
-    if !Path::new(graph_file).exists() && Path::new(osm_source_file).exists() {
-        parse_and_write_osm_data(osm_source_file, graph_file)?
+/// Parse given `graph_file`. If it does not exist yet or was written in an outdated format,
+/// build it from `source_file` first. Nodes with two neighbours are contracted when building the
+/// graph if `contract_degree_two_nodes` is set.
+pub fn checked_create_fmi_graph(graph_file: &str, osm_source_file: &str, contract_degree_two_nodes: bool) -> io::Result<()> {
+    if !Path::new(osm_source_file).exists() {
+        return Ok(());
+    }
+    let up_to_date = Path::new(graph_file).exists()
+        && read_graph_file_version(graph_file)? == GRAPH_FILE_VERSION;
+    if !up_to_date {
+        parse_and_write_osm_data(osm_source_file, graph_file, contract_degree_two_nodes)?
     }
     Ok(())
 }
@@ -115,11 +128,15 @@ pub fn checked_create_fmi_graph(graph_file: &str, osm_source_file: &str) -> io::
 /// Parse osmpbf data given in `osmpbf_file_path`.
 /// Extract and filter the osm data to create a directed weighted fmi graph containing sights.
 /// The data is filtered by the sights_config and edge_type_config files.
+/// If `contract_degree_two_nodes` is set, chains of nodes with two neighbours are replaced by
+/// single edges that keep the nodes as shape points.
 /// Writes and saves the created graph data in `fmi_file_path`.
-pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) -> Result<(), io::Error> {
+pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str, contract_degree_two_nodes: bool) -> Result<(), io::Error> {
     let mut osm_nodes: Vec<OSMNode> = Vec::new();
     let mut osm_edges: Vec<OSMEdge> = Vec::new();
     let mut osm_sights: Vec<OSMSight> = Vec::new();
+    let mut street_names: Vec<String> = Vec::new();
+    let mut street_name_ids: HashMap<String, u32> = HashMap::new();
 
     let sight_config_orig = data::get_sights_config();
     let edge_type_config_orig = data::get_edge_type_config();
@@ -144,7 +161,7 @@ pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) ->
             let edge_type_config = &edge_type_config_orig;
             let thread_result = s.spawn(move |_| {
                 let data = blob.to_primitiveblock().unwrap();
-                let mut result = (Vec::<OSMNode>::new(), Vec::<OSMEdge>::new(), Vec::<OSMSight>::new());
+                let mut result = (Vec::<OSMNode>::new(), Vec::<OSMEdge>::new(), Vec::<OSMSight>::new(), Vec::<String>::new());
                 //start iterating through the blob elements
                 data.for_each_element(|element| {
                     match element {
@@ -172,6 +189,7 @@ pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) ->
     //join all threads and accumulate results
     for t in threads  {
         let mut result = t.join().unwrap();
+        intern_street_names(&mut result.1, &result.3, &mut street_names, &mut street_name_ids);
         osm_nodes.append(&mut result.0);
         osm_edges.append(&mut result.1);
         osm_sights.append(&mut result.2);
@@ -233,6 +251,17 @@ pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) ->
     let time_duration = time_start.elapsed();
     info!("Finished resorting edges after {} seconds!", time_duration.as_millis() as f32 / 1000.0);
 
+    let edge_shapes = if contract_degree_two_nodes {
+        let edges_before_contraction = osm_edges.len();
+        let edge_shapes = contract_degree_two_nodes_into_edges(&mut osm_edges, osm_nodes.len(), &is_sight_node);
+        let time_duration = time_start.elapsed();
+        info!("Finished contraction of nodes with two neighbours, keeping {} of {} edges, after {} seconds!",
+            osm_edges.len(), edges_before_contraction, time_duration.as_millis() as f32 / 1000.0);
+        edge_shapes
+    } else {
+        Vec::new()
+    };
+
     osm_sights.sort_unstable_by( |s1, s2| {
         if s1.lat > s2.lat {
             Ordering::Greater
@@ -261,9 +290,12 @@ pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) ->
 
     let file = File::create(fmi_file_path)?;
     let mut file = BufWriter::new(file);
+    write_graph_file_header(&mut file)?;
     bincode::serialize_into(&mut file, &osm_nodes).expect("Error serializing nodes");
     bincode::serialize_into(&mut file, &osm_sights).expect("Error serializing sights");
     bincode::serialize_into(&mut file, &osm_edges).expect("Error serializing edges");
+    bincode::serialize_into(&mut file, &street_names).expect("Error serializing street names");
+    bincode::serialize_into(&mut file, &edge_shapes).expect("Error serializing edge shapes");
 
     let time_duration = time_start.elapsed();
 
@@ -274,14 +306,16 @@ pub fn parse_and_write_osm_data (osmpbf_file_path: &str, fmi_file_path: &str) ->
 /// This is used in the parsing process when reading Nodes / Densenodes from the osm data to create an OSMNode and the corresponding Sight.
 /// By using the `sight_config` and the given `tags`, the method detects whether the node is a OSMSight or not.
 /// Only creates OSMSights with a specific tag defined in the `sight_config`.
-fn create_osm_node(osm_id: usize, lat: f64, lon: f64, tags: Vec<(&str, &str)>, sight_config: &SightsConfig, result: &mut (Vec<OSMNode>, Vec<OSMEdge>, Vec<OSMSight>)) {
+fn create_osm_node(osm_id: usize, lat: f64, lon: f64, tags: Vec<(&str, &str)>, sight_config: &SightsConfig, result: &mut (Vec<OSMNode>, Vec<OSMEdge>, Vec<OSMSight>, Vec<String>)) {
     // if sight has no name, osm_id is shown
     let mut osm_name = "None".to_string(); // default
     let mut osm_opening_hours = "empty".to_string(); // default
     let mut categories: HashSet<Category> = HashSet::new();
     let mut osm_wikidata_id = "empty".to_string();
     let mut is_sight = false;
+    let mut osm_tags: HashMap<String, String> = HashMap::new();
     for (key, value) in tags {
+        osm_tags.insert(key.to_string(), value.to_string());
         for cat_tag_map in &sight_config.category_tag_map {
             let category = cat_tag_map.category.parse::<Category>().unwrap();
             for tag in &cat_tag_map.tags {
@@ -317,6 +351,7 @@ fn create_osm_node(osm_id: usize, lat: f64, lon: f64, tags: Vec<(&str, &str)>, s
             let name = osm_name.clone();
             let opening_hours = osm_opening_hours.clone();
             let wikidata_id = osm_wikidata_id.clone();
+            let tags = osm_tags.clone();
             let osm_sight = OSMSight {
                 osm_id,
                 node_id: 0,
@@ -325,7 +360,8 @@ fn create_osm_node(osm_id: usize, lat: f64, lon: f64, tags: Vec<(&str, &str)>, s
                 category,
                 name,
                 opening_hours,
-                wikidata_id
+                wikidata_id,
+                tags
             };
             result.2.push(osm_sight);
         }
@@ -337,13 +373,20 @@ fn create_osm_node(osm_id: usize, lat: f64, lon: f64, tags: Vec<(&str, &str)>, s
 /// A Way consists of several osm ids in a specific order, for example (0, 3, 5, 9, 4, ..., 10).
 /// This method separates this sequence of osm ids into OSMEdges. In this example: (0,3), (3,5), (5,9) and so on.
 /// Since a Way is directed, it creates one OSMEdge for each direction.
-fn create_osm_edges(w: Way, edge_type_config: &EdgeTypeConfig, result: &mut (Vec<OSMNode>, Vec<OSMEdge>, Vec<OSMSight>)) {
+/// The name of the Way is added to the names of the blob and referenced by the OSMEdges.
+fn create_osm_edges(w: Way, edge_type_config: &EdgeTypeConfig, result: &mut (Vec<OSMNode>, Vec<OSMEdge>, Vec<OSMSight>, Vec<String>)) {
+    let mut name_id = None;
+    let mut way_name = w.tags().find(|&(key, _)| key == "name").map(|(_, value)| value);
     let way_tags = w.tags();
     for (key, value) in way_tags {
         for et_tag_map in &edge_type_config.edge_type_tag_map {
             let edge_type = et_tag_map.edge_type.parse::<EdgeType>().unwrap();
             for tag in &et_tag_map.tags {
                 if key == tag.key && value == tag.value {
+                    if let Some(name) = way_name.take() {
+                        result.3.push(name.to_string());
+                        name_id = Some((result.3.len() - 1) as u32);
+                    }
                     let mut way_ref_iter = w.refs();
                     let mut osm_src = way_ref_iter.next().unwrap() as usize;
                     for node_id  in way_ref_iter {
@@ -355,7 +398,9 @@ fn create_osm_edges(w: Way, edge_type_config: &EdgeTypeConfig, result: &mut (Vec
                             src: 0,
                             tgt: 0,
                             dist: 0,
-                            edge_type
+                            edge_type,
+                            name_id,
+                            shape_id: None
                         };
                         result.1.push(out_edge);
 
@@ -365,7 +410,9 @@ fn create_osm_edges(w: Way, edge_type_config: &EdgeTypeConfig, result: &mut (Vec
                             src: 0,
                             tgt: 0,
                             dist: 0,
-                            edge_type
+                            edge_type,
+                            name_id,
+                            shape_id: None
                         };
                         result.1.push(in_edge);
 
@@ -377,6 +424,28 @@ fn create_osm_edges(w: Way, edge_type_config: &EdgeTypeConfig, result: &mut (Vec
     }
 }
 
+/// Replaces the indices into the street names `blob_names` of a parsed blob by the indices into
+/// the interned `street_names` of the graph. New names are added to `street_names` and
+/// `street_name_ids`, which maps each interned name to its index.
+fn intern_street_names(osm_edges: &mut [OSMEdge], blob_names: &[String],
+                       street_names: &mut Vec<String>, street_name_ids: &mut HashMap<String, u32>) {
+    for edge in osm_edges.iter_mut() {
+        if let Some(blob_name_id) = edge.name_id {
+            let name = &blob_names[blob_name_id as usize];
+            let name_id = match street_name_ids.get(name) {
+                Some(&name_id) => name_id,
+                None => {
+                    let name_id = street_names.len() as u32;
+                    street_names.push(name.clone());
+                    street_name_ids.insert(name.clone(), name_id);
+                    name_id
+                }
+            };
+            edge.name_id = Some(name_id);
+        }
+    }
+}
+
 /// Remove Sights when they do not have a name, except when they are of category nature or
 /// PicnicBarbequeSpot (These types of sights rarely have names but are still cool).
 fn handle_sights_without_name(osm_sights: &mut Vec<OSMSight>, sight_config: &SightsConfig){
@@ -514,7 +583,9 @@ fn integrate_sights_into_graph(osm_nodes: &Vec<OSMNode>, osm_edges: &mut Vec<OSM
             src: sight.node_id,
             tgt: nearest_node.id,
             dist: nearest_dist,
-            edge_type: EdgeType::SightEdge
+            edge_type: EdgeType::SightEdge,
+            name_id: None,
+            shape_id: None
         };
         let in_edge = OSMEdge {
             osm_src: 0,
@@ -522,7 +593,9 @@ fn integrate_sights_into_graph(osm_nodes: &Vec<OSMNode>, osm_edges: &mut Vec<OSM
             src: nearest_node.id,
             tgt: sight.node_id,
             dist: nearest_dist,
-            edge_type: EdgeType::SightEdge
+            edge_type: EdgeType::SightEdge,
+            name_id: None,
+            shape_id: None
         };
         osm_edges.push(out_edge);
         osm_edges.push(in_edge);
@@ -544,6 +617,75 @@ fn prune_edges(osm_edges: &mut Vec<OSMEdge>) {
     }
 }
 
+/// Replaces each chain of nodes with exactly two neighbours by a single edge between the nodes at
+/// the ends of the chain, whose weight is the sum of the weights along the chain. The contracted
+/// nodes stay in the graph as shape points of the new edge, such that paths keep their shape, but
+/// lose their edges. Sights and nodes where the street type or street name changes are not
+/// contracted. Of multiple edges between the same nodes, only the one with the lowest dist is kept.
+/// `osm_edges` has to be sorted by src and then by tgt and stays sorted.
+/// Returns the shape points of the new edges, which are referenced by their `shape_id`.
+fn contract_degree_two_nodes_into_edges(osm_edges: &mut Vec<OSMEdge>, num_nodes: usize, is_sight_node: &HashSet<usize>) -> Vec<Vec<usize>> {
+    let mut offsets = vec![0; num_nodes + 1];
+    for edge in osm_edges.iter() {
+        offsets[edge.src + 1] += 1;
+    }
+    for node_id in 0..num_nodes {
+        offsets[node_id + 1] += offsets[node_id];
+    }
+    let outgoing_edges = |node_id: usize| &osm_edges[offsets[node_id]..offsets[node_id + 1]];
+    // every edge has a reverse edge, so the outgoing edges lead to all neighbours
+    let is_contractible = |node_id: usize| {
+        let out_edges = outgoing_edges(node_id);
+        out_edges.len() == 2
+            && !is_sight_node.contains(&node_id)
+            && out_edges[0].tgt != out_edges[1].tgt
+            && out_edges.iter().all(|edge| edge.tgt != node_id)
+            && out_edges[0].edge_type == out_edges[1].edge_type
+            && out_edges[0].name_id == out_edges[1].name_id
+    };
+
+    let mut contracted_edges: Vec<(OSMEdge, Vec<usize>)> = Vec::new();
+    for edge in osm_edges.iter().filter(|edge| !is_contractible(edge.src)) {
+        // follow the chain until a node that is not contracted
+        let mut contracted_edge = *edge;
+        let mut shape = Vec::new();
+        let mut prev_id = edge.src;
+        while is_contractible(contracted_edge.tgt) {
+            let node_id = contracted_edge.tgt;
+            let next_edge = outgoing_edges(node_id).iter()
+                .find(|out_edge| out_edge.tgt != prev_id)
+                .expect("Contractible node without second neighbour");
+            shape.push(node_id);
+            contracted_edge.tgt = next_edge.tgt;
+            contracted_edge.dist += next_edge.dist;
+            prev_id = node_id;
+        }
+        // chains leading back to their start are no shortcut to anywhere
+        if shape.is_empty() || contracted_edge.tgt != contracted_edge.src {
+            contracted_edges.push((contracted_edge, shape));
+        }
+    }
+
+    contracted_edges.sort_unstable_by(|(e1, _), (e2, _)| {
+        e1.src.cmp(&e2.src)
+            .then_with(|| e1.tgt.cmp(&e2.tgt))
+            .then_with(|| e1.dist.cmp(&e2.dist))
+    });
+    contracted_edges.dedup_by(|(e2, _), (e1, _)| e1 == e2);
+
+    let mut edge_shapes = Vec::new();
+    *osm_edges = contracted_edges.into_iter()
+        .map(|(mut edge, shape)| {
+            if !shape.is_empty() {
+                edge.shape_id = Some(edge_shapes.len() as u32);
+                edge_shapes.push(shape);
+            }
+            edge
+        })
+        .collect();
+    edge_shapes
+}
+
 /// function for clustering Picnic Barbeque spots that are in a Range of 500m to one single sightNode
 fn clustering_sights(sights: &mut Vec<OSMSight>) {
     let mut sights_to_combine: Vec<usize> = Vec::new(); //Sights to Flex
@@ -0,0 +1,169 @@
+use serde_json::{json, Map, Value};
+use crate::algorithm::{Route, RouteSector, Sector};
+use crate::data::graph::Sight;
+use crate::utils::isochrone::Isochrone;
+
+/// Serializes sights as GeoJSON `FeatureCollection` with a `Point` feature per sight.
+/// The properties of each feature are the properties of the sight.
+pub fn sights_to_geojson(sights: &[&Sight]) -> Value {
+    let features = sights.iter()
+        .map(|sight| feature(point(sight), sight_properties(sight)))
+        .collect::<Vec<_>>();
+    feature_collection(features)
+}
+
+/// Serializes a route as GeoJSON `FeatureCollection`.
+///
+/// The collection contains a `LineString` feature per route sector with the sector type and
+/// the time of arrival at its target, followed by a `Point` feature per visited sight with the
+/// properties of the sight and the times of its visit.
+pub fn route_to_geojson(route: &Route) -> Value {
+    let sector_features = route.iter()
+        .map(|route_sector| {
+            let (sector_type, time_of_arrival) = match route_sector {
+                RouteSector::Start(sector) => ("Start", sector.time_of_arrival()),
+                RouteSector::Intermediate(sector) => ("Intermediate", sector.time_of_arrival()),
+                RouteSector::End(sector) => ("End", sector.time_of_arrival()),
+            };
+            let coordinates = route_sector.nodes().iter()
+                .map(|node| json!([node.lon, node.lat]))
+                .collect::<Vec<_>>();
+            let mut properties = Map::new();
+            properties.insert("type".to_string(), json!(sector_type));
+            properties.insert("time_of_arrival".to_string(), json!(time_of_arrival.to_rfc3339()));
+            feature(json!({ "type": "LineString", "coordinates": coordinates }), properties)
+        });
+    let sight_features = route.iter()
+        .filter_map(|route_sector| route_sector.sight_sector())
+        .map(|sector| feature(point(sector.sight()), visit_properties(sector)));
+    feature_collection(sector_features.chain(sight_features).collect())
+}
+
+/// Serializes isochrones as GeoJSON `FeatureCollection` with a `MultiPolygon` feature per
+/// isochrone, whose properties are the time limit and the walking distance within it.
+pub fn isochrones_to_geojson(isochrones: &[Isochrone]) -> Value {
+    let features = isochrones.iter()
+        .map(|isochrone| {
+            let polygons = isochrone.polygons.iter()
+                .map(|ring| vec![ring.iter().map(|&(lat, lon)| json!([lon, lat])).collect::<Vec<_>>()])
+                .collect::<Vec<_>>();
+            let mut properties = Map::new();
+            properties.insert("time_limit_secs".to_string(), json!(isochrone.time_limit_secs));
+            properties.insert("max_dist_meters".to_string(), json!(isochrone.max_dist_meters));
+            feature(json!({ "type": "MultiPolygon", "coordinates": polygons }), properties)
+        })
+        .collect();
+    feature_collection(features)
+}
+
+/// Properties of the target sight of `sector` including the times of its visit
+fn visit_properties(sector: &Sector) -> Map<String, Value> {
+    let wait_time = sector.service_start_time().signed_duration_since(*sector.time_of_arrival());
+    let service_time = sector.service_end_time().signed_duration_since(*sector.service_start_time());
+    let mut properties = sight_properties(sector.sight());
+    properties.insert("time_of_arrival".to_string(), json!(sector.time_of_arrival().to_rfc3339()));
+    properties.insert("wait_time_secs".to_string(), json!(wait_time.num_seconds()));
+    properties.insert("service_start_time".to_string(),
+                      json!(sector.service_start_time().to_rfc3339()));
+    properties.insert("service_time_secs".to_string(), json!(service_time.num_seconds()));
+    properties.insert("service_end_time".to_string(),
+                      json!(sector.service_end_time().to_rfc3339()));
+    properties
+}
+
+/// Properties of a sight as serialized by `serde`
+fn sight_properties(sight: &Sight) -> Map<String, Value> {
+    match serde_json::to_value(sight).expect("Unable to serialize sight") {
+        Value::Object(properties) => properties,
+        _ => unreachable!("Sights are serialized as objects"),
+    }
+}
+
+/// `Point` geometry at the location of a sight
+fn point(sight: &Sight) -> Value {
+    json!({ "type": "Point", "coordinates": [sight.lon, sight.lat] })
+}
+
+fn feature(geometry: Value, properties: Map<String, Value>) -> Value {
+    json!({ "type": "Feature", "geometry": geometry, "properties": properties })
+}
+
+fn feature_collection(features: Vec<Value>) -> Value {
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algorithm::test::greedy_route_around_hotel;
+    use crate::export::geojson::{isochrones_to_geojson, route_to_geojson, sights_to_geojson};
+    use crate::init_logging;
+    use crate::utils::isochrone::Isochrone;
+    use crate::utils::test_setup;
+
+    #[test]
+    fn test_sights_as_points_with_properties() {
+        init_logging();
+
+        let sights = test_setup::GRAPH.get_sights_in_area(53.074448, 8.805105, 300.0);
+        let geojson = sights_to_geojson(&sights);
+
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), sights.len());
+        for (feature, sight) in features.iter().zip(&sights) {
+            assert_eq!(feature["geometry"]["type"], "Point");
+            assert_eq!(feature["geometry"]["coordinates"][0], sight.lon);
+            assert_eq!(feature["geometry"]["coordinates"][1], sight.lat);
+            assert_eq!(feature["properties"]["name"], sight.name.as_str());
+            assert_eq!(feature["properties"]["category"], sight.category.to_string());
+        }
+    }
+
+    #[test]
+    fn test_route_as_line_string_per_sector_and_point_per_sight() {
+        init_logging();
+
+        let route = greedy_route_around_hotel();
+        let geojson = route_to_geojson(&route);
+
+        let features = geojson["features"].as_array().unwrap();
+        let line_strings = features.iter()
+            .filter(|feature| feature["geometry"]["type"] == "LineString")
+            .collect::<Vec<_>>();
+        let points = features.iter()
+            .filter(|feature| feature["geometry"]["type"] == "Point")
+            .collect::<Vec<_>>();
+        assert_eq!(line_strings.len(), route.len());
+        for (line_string, route_sector) in line_strings.iter().zip(&route) {
+            assert_eq!(line_string["geometry"]["coordinates"].as_array().unwrap().len(),
+                       route_sector.nodes().len());
+        }
+        let sectors = route.iter()
+            .filter_map(|route_sector| route_sector.sight_sector())
+            .collect::<Vec<_>>();
+        assert!(!sectors.is_empty(), "Route visits no sights");
+        assert_eq!(points.len(), sectors.len());
+        for (point, sector) in points.iter().zip(&sectors) {
+            assert_eq!(point["properties"]["node_id"], sector.sight().node_id);
+            assert_eq!(point["properties"]["service_end_time"],
+                       sector.service_end_time().to_rfc3339());
+            assert!(point["properties"]["wait_time_secs"].as_i64().unwrap() >= 0);
+        }
+    }
+
+    #[test]
+    fn test_isochrone_as_multi_polygon() {
+        let isochrone = Isochrone {
+            time_limit_secs: 600,
+            max_dist_meters: 833,
+            polygons: vec![vec![(53.0, 8.0), (53.0, 8.1), (53.1, 8.1), (53.0, 8.0)]],
+        };
+        let geojson = isochrones_to_geojson(&[isochrone]);
+
+        let feature = &geojson["features"][0];
+        assert_eq!(feature["geometry"]["type"], "MultiPolygon");
+        assert_eq!(feature["geometry"]["coordinates"][0][0][1][0], 8.1);
+        assert_eq!(feature["geometry"]["coordinates"][0][0][1][1], 53.0);
+        assert_eq!(feature["properties"]["time_limit_secs"], 600);
+        assert_eq!(feature["properties"]["max_dist_meters"], 833);
+    }
+}
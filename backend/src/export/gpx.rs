@@ -0,0 +1,104 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use crate::algorithm::{Route, Sector};
+
+/// Namespace of the TrailScout specific GPX extensions, which hold the service times of waypoints
+const EXTENSIONS_NAMESPACE: &str = "urn:trailscout:gpx:1";
+
+/// Serializes a route as GPX 1.1 document.
+///
+/// The document contains a waypoint for each visited sight with its name, category, time of
+/// arrival and service times, and a track with one segment per route sector.
+pub fn route_to_gpx(route: &Route) -> String {
+    let mut gpx = String::new();
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str(&format!(
+        "<gpx version=\"1.1\" creator=\"TrailScout\" xmlns=\"http://www.topografix.com/GPX/1/1\" \
+        xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+        xsi:schemaLocation=\"http://www.topografix.com/GPX/1/1 http://www.topografix.com/GPX/1/1/gpx.xsd\" \
+        xmlns:trailscout=\"{}\">\n", EXTENSIONS_NAMESPACE));
+
+    for sector in route.iter().filter_map(|route_sector| route_sector.sight_sector()) {
+        push_waypoint(&mut gpx, sector);
+    }
+
+    gpx.push_str("  <trk>\n    <name>TrailScout</name>\n");
+    for route_sector in route {
+        gpx.push_str("    <trkseg>\n");
+        for node in route_sector.nodes() {
+            gpx.push_str(&format!("      <trkpt lat=\"{}\" lon=\"{}\"/>\n", node.lat, node.lon));
+        }
+        gpx.push_str("    </trkseg>\n");
+    }
+    gpx.push_str("  </trk>\n</gpx>\n");
+    gpx
+}
+
+/// Appends a waypoint for the target sight of `sector` to `gpx`
+fn push_waypoint(gpx: &mut String, sector: &Sector) {
+    let sight = sector.sight();
+    gpx.push_str(&format!("  <wpt lat=\"{}\" lon=\"{}\">\n", sight.lat, sight.lon));
+    gpx.push_str(&format!("    <time>{}</time>\n", format_time(sector.time_of_arrival())));
+    gpx.push_str(&format!("    <name>{}</name>\n", escape(&sight.name)));
+    gpx.push_str(&format!("    <type>{}</type>\n", sight.category));
+    gpx.push_str("    <extensions>\n");
+    gpx.push_str(&format!("      <trailscout:service_start_time>{}</trailscout:service_start_time>\n",
+                          format_time(sector.service_start_time())));
+    gpx.push_str(&format!("      <trailscout:service_end_time>{}</trailscout:service_end_time>\n",
+                          format_time(sector.service_end_time())));
+    gpx.push_str("    </extensions>\n");
+    gpx.push_str("  </wpt>\n");
+}
+
+/// Formats a date time in UTC, as recommended by the GPX schema
+fn format_time(date_time: &DateTime<Tz>) -> String {
+    date_time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Escapes the characters of `text` that have a special meaning in XML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algorithm::test::greedy_route_around_hotel;
+    use crate::export::gpx::{escape, route_to_gpx};
+    use crate::init_logging;
+
+    #[test]
+    fn test_escape_xml_special_characters() {
+        assert_eq!(escape("Bars & \"Kneipen\" <Bremen>"),
+                   "Bars &amp; &quot;Kneipen&quot; &lt;Bremen&gt;");
+    }
+
+    #[test]
+    fn test_gpx_contains_waypoint_per_sight_and_segment_per_sector() {
+        init_logging();
+
+        let route = greedy_route_around_hotel();
+        let gpx = route_to_gpx(&route);
+
+        let num_sights = route.iter()
+            .filter(|route_sector| route_sector.sight_sector().is_some())
+            .count();
+        let num_nodes: usize = route.iter().map(|route_sector| route_sector.nodes().len()).sum();
+        assert!(num_sights > 0, "Route visits no sights");
+        assert_eq!(gpx.matches("<wpt ").count(), num_sights);
+        assert_eq!(gpx.matches("<trailscout:service_start_time>").count(), num_sights);
+        assert_eq!(gpx.matches("<trkseg>").count(), route.len());
+        assert_eq!(gpx.matches("<trkpt ").count(), num_nodes);
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use crate::algorithm::Route;
+use crate::data::graph::Node;
+
+/// Variant of the encoded polyline format, which differ in the precision of the coordinates
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolylineFormat {
+    /// Coordinates with 5 decimal places, as used by Google
+    Polyline5,
+    /// Coordinates with 6 decimal places, as used by OSRM and Valhalla
+    Polyline6,
+}
+
+impl PolylineFormat {
+    /// Factor by which coordinates are multiplied before they are rounded
+    fn factor(&self) -> f64 {
+        match self {
+            PolylineFormat::Polyline5 => 1e5,
+            PolylineFormat::Polyline6 => 1e6,
+        }
+    }
+}
+
+/// Encodes the path through `nodes` with the encoded polyline algorithm
+pub fn nodes_to_polyline(nodes: &[&Node], format: PolylineFormat) -> String {
+    encode_polyline(nodes.iter().map(|node| (node.lat, node.lon)), format)
+}
+
+/// Encodes the path of each sector of a route as polyline
+pub fn route_to_polylines(route: &Route, format: PolylineFormat) -> Vec<String> {
+    route.iter()
+        .map(|route_sector| nodes_to_polyline(route_sector.nodes(), format))
+        .collect()
+}
+
+/// Encodes coordinates given as (latitude, longitude) pairs with the encoded polyline algorithm.
+/// Each coordinate is stored as difference to the previous one.
+pub fn encode_polyline(coords: impl IntoIterator<Item = (f64, f64)>, format: PolylineFormat) -> String {
+    let factor = format.factor();
+    let mut polyline = String::new();
+    let (mut prev_lat, mut prev_lon) = (0, 0);
+    for (lat, lon) in coords {
+        let lat = (lat * factor).round() as i64;
+        let lon = (lon * factor).round() as i64;
+        encode_value(lat - prev_lat, &mut polyline);
+        encode_value(lon - prev_lon, &mut polyline);
+        prev_lat = lat;
+        prev_lon = lon;
+    }
+    polyline
+}
+
+/// Appends `value` to `polyline` in chunks of 5 bits, starting with the least significant chunk
+fn encode_value(value: i64, polyline: &mut String) {
+    // move the sign to the least significant bit
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 } as u64;
+    while value >= 0x20 {
+        polyline.push((((value & 0x1f) | 0x20) as u8 + 63) as char);
+        value >>= 5;
+    }
+    polyline.push((value as u8 + 63) as char);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::export::polyline::{encode_polyline, PolylineFormat};
+
+    #[test]
+    fn test_encode_polyline() {
+        let coords = [(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)];
+        assert_eq!(encode_polyline(coords, PolylineFormat::Polyline5),
+                   "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!(encode_polyline(coords, PolylineFormat::Polyline6),
+                   "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI");
+        assert_eq!(encode_polyline([], PolylineFormat::Polyline5), "");
+    }
+}
@@ -1,6 +1,7 @@
 use std::io;
 use env_logger::Env;
 use std::env;
+use trailscout_lib::data::contraction_hierarchy::parse_graph_and_write_contraction_hierarchy;
 use trailscout_lib::data::osm_graph_creator::{parse_and_write_osm_data};
 #[macro_use]
 extern crate log;
@@ -15,10 +16,19 @@ pub fn main() -> Result<(), io::Error> {
 
     let in_graph = env::var("i").unwrap_or("./osm_graphs/bremen31-8-22.osm.pbf".to_string());
     let out_graph = env::var("o").unwrap_or("./osm_graphs/bremen31-8-22.fmibin".to_string());
+    //contract nodes with two neighbours into shape points of the edges
+    let contract = env::var("contract").as_deref() == Ok("true");
+    //build a contraction hierarchy next to the output file for fast shortest path queries
+    let contraction_hierarchy = env::var("ch").as_deref() == Ok("true");
 
     println!("Input file is {}.", &in_graph);
     println!("Output file is {}.", &out_graph);
+    println!("Contraction of nodes with two neighbours is {}.", if contract { "on" } else { "off" });
+    println!("Contraction hierarchy is {}.", if contraction_hierarchy { "on" } else { "off" });
 
-    parse_and_write_osm_data(&in_graph, &out_graph)?;
+    parse_and_write_osm_data(&in_graph, &out_graph, contract)?;
+    if contraction_hierarchy {
+        parse_graph_and_write_contraction_hierarchy(&out_graph)?;
+    }
     Ok(())
 }
\ No newline at end of file
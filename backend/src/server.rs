@@ -1,29 +1,51 @@
 mod server_utils;
 
 use actix_cors::Cors;
-use actix_web::{App, http, HttpResponse, HttpServer, post, Result, web};
+use actix_web::{App, delete, get, http, HttpRequest, HttpResponse, HttpServer, post, Result, web};
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::web::Bytes;
+use futures::channel::mpsc;
 use chrono::DateTime;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{env, str};
+use std::convert::Infallible;
 use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use log::{debug, error};
 use serde_json;
 
-use trailscout_lib::algorithm::{Algorithm};
+use trailscout_lib::algorithm::{Algorithm, AlgorithmParams, ComputeControl, RouteSummary};
 use trailscout_lib::data::graph::Graph;
 use trailscout_lib::data::osm_graph_creator;
+use trailscout_lib::export::{geojson, gpx, polyline};
+use trailscout_lib::utils::{dijkstra, isochrone};
 use trailscout_lib;
+use crate::server_utils::compute_pool::ComputePool;
 use crate::server_utils::custom_errors::{match_error, TrailScoutError};
-use crate::server_utils::requests::{RouteProviderReq, RouteProviderRes, SightsRequest};
+use crate::server_utils::requests::{AlgorithmInfo, IsochroneReq, MatrixLocation, MatrixReq, MatrixRes, PathLeg, PathReq, PathRes, RouteFormat, RouteFormatQuery, RouteProviderReq, RouteProviderRes, SightsFormat, SightsFormatQuery, SightsRequest};
+use crate::server_utils::route_jobs::RouteJobs;
 
 
 ///Location of the application config file
 const CONFIG_PATH :&str = "./config.json";
 
+///Maximum number of via-points of path requests
+const MAX_PATH_VIA_POINTS: usize = 25;
+
+///Range of the walking speed in km/h of path, matrix and isochrone requests
+const WALKING_SPEED_KMH: (f64, f64) = (1.0, 10.0);
+
+///Range of the edge length in meters of the grid cells that make up isochrones
+const ISOCHRONE_CELL_SIZE_METERS: (f64, f64) = (10.0, 1000.0);
+
 ///Represents state containing the config and appstate
 struct AppState {
     graph: Graph,
     config: Config,
+    compute_pool: ComputePool,
+    route_jobs: RouteJobs,
 }
 
 ///Deserialization of config file
@@ -35,6 +57,46 @@ struct Config {
     graph_file_path : String,
     routing_algorithm: String,
     source_file: String,
+    ///Default tuning parameters of the routing algorithms
+    #[serde(default)]
+    algorithm_params: AlgorithmParams,
+    ///Maximum time in milliseconds to compute a route. Requests may only lower it.
+    #[serde(default)]
+    max_compute_ms: Option<u64>,
+    ///Number of threads computing routes. Defaults to the number of available CPUs.
+    #[serde(default)]
+    compute_threads: Option<usize>,
+    ///Number of route requests that may wait for a compute thread before requests are rejected
+    #[serde(default = "default_compute_queue_size")]
+    compute_queue_size: usize,
+    ///Number of seconds a finished route job is kept before it expires
+    #[serde(default = "default_job_ttl_secs")]
+    job_ttl_secs: u64,
+    ///Whether nodes with two neighbours are contracted when the graph file is built from the source file
+    #[serde(default)]
+    contract_graph: bool,
+    ///Largest time limit in minutes of isochrone requests
+    #[serde(default = "default_max_isochrone_minutes")]
+    max_isochrone_minutes: u64,
+    ///Largest number of locations of distance matrix requests
+    #[serde(default = "default_max_matrix_locations")]
+    max_matrix_locations: usize,
+}
+
+fn default_compute_queue_size() -> usize {
+    16
+}
+
+fn default_job_ttl_secs() -> u64 {
+    600
+}
+
+fn default_max_isochrone_minutes() -> u64 {
+    120
+}
+
+fn default_max_matrix_locations() -> usize {
+    100
 }
 
 ///read config.json at CONFIG_PATH and return it
@@ -45,35 +107,232 @@ fn get_config() -> Config {
 
     let data = fs::read_to_string(CONFIG_PATH).expect(&format!("Unable to read config at {}", CONFIG_PATH));
     let config: Config = serde_json::from_str(&data).expect("Unable to parse config files");
+    if let Err(error) = config.algorithm_params.validate() {
+        panic!("Invalid algorithm parameters in config: {}", error);
+    }
 
     println!("Read config:\n{:#?}", &config);
 
     return config;
 }
 
-///Responds to post request asking for sights
+///Checks that the requested walking speed lies within `WALKING_SPEED_KMH`
+fn check_walking_speed(walking_speed_kmh: f64) -> Result<(), TrailScoutError> {
+    let (min_speed, max_speed) = WALKING_SPEED_KMH;
+    if !(min_speed..=max_speed).contains(&walking_speed_kmh) {
+        return Err(TrailScoutError::InvalidRequestParameterServer {
+            name: "walking_speed_kmh".to_string(), min: min_speed, max: max_speed });
+    }
+    Ok(())
+}
+
+///Outputs the value of the `Accept` header, if any
+fn accept_header(http_request: &HttpRequest) -> Option<&str> {
+    http_request.headers().get(http::header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+}
+
+///Responds to post request asking for sights.
+///The sights are returned as JSON list, unless GeoJSON is requested by the `format` query
+///parameter or the `Accept` header.
 #[post("/sights")]
-async fn post_sights(request:  web::Json<SightsRequest>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+async fn post_sights(request:  web::Json<SightsRequest>, query: web::Query<SightsFormatQuery>, http_request: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
 
     debug!("Got Sights Request for lat={}, lon={} and radius={}.",
         request.lat, request.lon, request.radius);
 
     //TODO does not yet produce any Result with error to handle
     let sights = data.graph.get_sights_in_area(
-        request.lat, request.lon, request.radius);
+        request.lat, request.lon, request.radius).into_iter()
+        .filter(|sight| !request.exclude.excludes(sight))
+        .collect::<Vec<_>>();
+
+
+    let format = query.format
+        .or_else(|| accept_header(&http_request).and_then(SightsFormat::from_accept))
+        .unwrap_or(SightsFormat::Json);
+    match format {
+        SightsFormat::Json => Ok(HttpResponse::Ok().json(sights)),
+        SightsFormat::GeoJson => Ok(HttpResponse::Ok()
+            .content_type("application/geo+json")
+            .json(geojson::sights_to_geojson(&sights))),
+    }
+}
+
+
+///Responds to post request asking for the shortest walking path from start via the via-points
+///to the end. Each location is snapped to its nearest street node.
+#[post("/path")]
+async fn post_path(request: web::Json<PathReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+
+    debug!("Got Path Request from lat={}, lon={} via {} points to lat={}, lon={}.",
+        request.start.lat(), request.start.lon(), request.via.len(), request.end.lat(), request.end.lon());
+
+    let path_request = request.into_inner();
+    if path_request.via.len() > MAX_PATH_VIA_POINTS {
+        return Err(TrailScoutError::InvalidRequestParameterServer {
+            name: "via".to_string(), min: 0.0, max: MAX_PATH_VIA_POINTS as f64 });
+    }
+    check_walking_speed(path_request.walking_speed_kmh)?;
+
+    //the Dijkstra runs may take long on large graphs, so they run on the compute pool
+    let job_data = data.clone();
+    let result = match data.compute_pool.try_spawn(move || {
+        let graph = &job_data.graph;
+        let node_ids = std::iter::once(&path_request.start)
+            .chain(&path_request.via)
+            .chain(std::iter::once(&path_request.end))
+            .map(|coordinate| graph.get_nearest_node(coordinate.lat(), coordinate.lon()))
+            .collect::<Vec<_>>();
+        let legs = dijkstra::run_dijkstra_via(graph, &node_ids)
+            .ok_or(TrailScoutError::NoRouteFoundServer)?;
+
+        //convert km/h to m/s
+        let speed_mps = path_request.walking_speed_kmh / 3.6;
+        let walking_secs = |dist: usize| (dist as f64 / speed_mps).round() as i64;
+        let distance_meters = legs.iter().map(|leg| leg.dist()).sum();
+        let path_legs = legs.iter()
+            .map(|leg| PathLeg { distance_meters: leg.dist(), walking_secs: walking_secs(leg.dist()) })
+            .collect();
+        let mut path = vec![graph.get_node(node_ids[0])];
+        for leg in legs {
+            //each leg starts at the end of the previous one
+            path.extend(leg.consume_path().into_iter().skip(1));
+        }
+        let polyline = path_request.polyline.map(|format| polyline::nodes_to_polyline(&path, format));
+        Ok(serde_json::to_value(PathRes {
+            path,
+            distance_meters,
+            walking_secs: walking_secs(distance_meters),
+            legs: path_legs,
+            polyline,
+        }).expect("Unable to serialize path"))
+    }) {
+        Some(result) => result,
+        None => {
+            error!("Error in post_path: compute queue is full");
+            return Err(TrailScoutError::ServerBusy);
+        }
+    };
+
+    match result.await {
+        Ok(path) => Ok(HttpResponse::Ok().json(path?)),
+        Err(_) => Err(TrailScoutError::ComputationFailedServer),
+    }
+}
 
 
-    Ok(HttpResponse::Ok().json(sights))
+///Responds to post request asking for the walking distances and times between all pairs of the
+///given locations. Sights are located at their own node, coordinates at their nearest street node.
+#[post("/matrix")]
+async fn post_matrix(request: web::Json<MatrixReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+
+    debug!("Got Matrix Request for {} locations.", request.locations.len());
+
+    let matrix_request = request.into_inner();
+    let max_locations = data.config.max_matrix_locations;
+    if matrix_request.locations.is_empty() || matrix_request.locations.len() > max_locations {
+        return Err(TrailScoutError::InvalidRequestParameterServer {
+            name: "locations".to_string(), min: 1.0, max: max_locations as f64 });
+    }
+    check_walking_speed(matrix_request.walking_speed_kmh)?;
+    let node_ids = matrix_request.locations.iter()
+        .map(|location| match location {
+            MatrixLocation::Sight { sight_id } => data.graph.sights.iter()
+                .find(|sight| sight.node_id == *sight_id)
+                .map(|sight| sight.node_id)
+                .ok_or(TrailScoutError::UnknownSightServer { id: *sight_id }),
+            MatrixLocation::Coordinate(coordinate) =>
+                Ok(data.graph.get_nearest_node(coordinate.lat(), coordinate.lon())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    //the compute pool limits the concurrent requests, the searches are spread over the rayon thread pool
+    let job_data = data.clone();
+    let result = match data.compute_pool.try_spawn(move || {
+        //the contraction hierarchy is much faster, if the graph creator built one
+        let distances_meters = match job_data.graph.contraction_hierarchy() {
+            Some(ch) => ch.many_to_many(&node_ids, &node_ids),
+            None => dijkstra::run_many_to_many_dijkstra(&job_data.graph, &node_ids, &node_ids),
+        };
+        //convert km/h to m/s
+        let speed_mps = matrix_request.walking_speed_kmh / 3.6;
+        let walking_secs = distances_meters.iter()
+            .map(|row| row.iter()
+                .map(|dist| dist.map(|dist| (dist as f64 / speed_mps).round() as i64))
+                .collect())
+            .collect();
+        MatrixRes {
+            distances_meters,
+            walking_secs,
+        }
+    }) {
+        Some(result) => result,
+        None => {
+            error!("Error in post_matrix: compute queue is full");
+            return Err(TrailScoutError::ServerBusy);
+        }
+    };
+
+    match result.await {
+        Ok(matrix) => Ok(HttpResponse::Ok().json(matrix)),
+        Err(_) => Err(TrailScoutError::ComputationFailedServer),
+    }
 }
 
 
-///Responds to post request asking for routing
-#[post("/route")]
-async fn post_route(request:  web::Json<RouteProviderReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
-    debug!("Received route request");
+///Responds to post request asking for the areas reachable on foot within the given time limits.
+///The isochrones are returned as GeoJSON `FeatureCollection` with a `MultiPolygon` per limit.
+#[post("/isochrone")]
+async fn post_isochrone(request: web::Json<IsochroneReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+
+    debug!("Got Isochrone Request for lat={}, lon={} and time limits {:?} minutes.",
+        request.lat, request.lon, request.time_limits_minutes);
+
+    let isochrone_request = request.into_inner();
+    let max_minutes = data.config.max_isochrone_minutes;
+    if isochrone_request.time_limits_minutes.is_empty() || isochrone_request.time_limits_minutes.iter()
+        .any(|&time_limit| time_limit == 0 || time_limit > max_minutes) {
+        return Err(TrailScoutError::InvalidRequestParameterServer {
+            name: "time_limits_minutes".to_string(), min: 1.0, max: max_minutes as f64 });
+    }
+    let (min_cell_size, max_cell_size) = ISOCHRONE_CELL_SIZE_METERS;
+    let cell_size = isochrone_request.cell_size_meters.unwrap_or(isochrone::DEFAULT_CELL_SIZE_METERS);
+    if !(min_cell_size..=max_cell_size).contains(&cell_size) {
+        return Err(TrailScoutError::InvalidRequestParameterServer {
+            name: "cell_size_meters".to_string(), min: min_cell_size, max: max_cell_size });
+    }
+    check_walking_speed(isochrone_request.walking_speed_kmh)?;
+
+    //the Dijkstra run may take long for large time limits, so it runs on the compute pool
+    let job_data = data.clone();
+    let result = match data.compute_pool.try_spawn(move || {
+        let src_id = job_data.graph.get_nearest_node(isochrone_request.lat, isochrone_request.lon);
+        let time_limits_secs = isochrone_request.time_limits_minutes.iter()
+            .map(|time_limit| time_limit * 60)
+            .collect::<Vec<_>>();
+        let isochrones = isochrone::compute_isochrones(
+            &job_data.graph, src_id, isochrone_request.walking_speed_kmh / 3.6, &time_limits_secs, cell_size);
+        geojson::isochrones_to_geojson(&isochrones)
+    }) {
+        Some(result) => result,
+        None => {
+            error!("Error in post_isochrone: compute queue is full");
+            return Err(TrailScoutError::ServerBusy);
+        }
+    };
+
+    match result.await {
+        Ok(isochrones) => Ok(HttpResponse::Ok().content_type("application/geo+json").json(isochrones)),
+        Err(_) => Err(TrailScoutError::ComputationFailedServer),
+    }
+}
 
-    let route_request = request.into_inner();
 
+///Computes the route for a route request and serializes the response with `serialize`.
+///Runs on the compute pool, because the route computation may take long.
+fn compute_route_response<T, F>(data: &AppState, route_request: RouteProviderReq, control: &ComputeControl, serialize: F) -> Result<T, TrailScoutError>
+    where F: FnOnce(RouteProviderRes) -> T {
     //parse start and end from Iso 8601 (rfc3339)
     let start = DateTime::parse_from_rfc3339(&route_request.start)
         .expect("Timer Parse Error");
@@ -83,14 +342,38 @@ async fn post_route(request:  web::Json<RouteProviderReq>, data: web::Data<AppSt
     //convert km/h to m/s
     let speed_mps = route_request.walking_speed_kmh as f64 / 3.6;
 
-    //get configured algorithm
-    let algo_result = Algorithm::from_name(&data.config.routing_algorithm,
+    //get requested algorithm or fall back to configured algorithm
+    let algorithm_name = match &route_request.algorithm {
+        Some(algorithm_name) => {
+            if !Algorithm::available_algorithms().contains(&algorithm_name.as_str()) {
+                error!("Error in post_route: Unknown algorithm requested: {}", algorithm_name);
+                return Err(TrailScoutError::BadAlgoServer);
+            }
+            algorithm_name.clone()
+        }
+        None => data.config.routing_algorithm.clone()
+    };
+
+    //apply requested overrides to the configured algorithm parameters
+    let algorithm_params = match data.config.algorithm_params.with_overrides(
+        &route_request.algorithm_params) {
+        Ok(algorithm_params) => algorithm_params,
+        Err(error) => {
+            error!("Error in post_route: {}", error);
+            return Err(match_error(error));
+        }
+    };
+
+    let algo_result = Algorithm::from_name(&algorithm_name,
                                            &data.graph,
                                            DateTime::from(start),
                                            DateTime::from(end),
                                            speed_mps,
                                            route_request.area,
-                                           route_request.user_prefs);
+                                           route_request.destination,
+                                           route_request.user_prefs,
+                                           algorithm_params.clone(),
+                                           route_request.seed);
 
     let algo = match algo_result {
         Ok(algo) => algo,
@@ -101,7 +384,7 @@ async fn post_route(request:  web::Json<RouteProviderReq>, data: web::Data<AppSt
         }
     };
 
-    let route = match algo.compute_route() {
+    let route = match algo.compute_route(control) {
         Ok(route) => route,
         Err(error) => {
             error!("Error in post_route compute_route: {}", error);
@@ -109,12 +392,194 @@ async fn post_route(request:  web::Json<RouteProviderReq>, data: web::Data<AppSt
         }
     };
 
-    debug!("Computed route with {}. Sending response...", &data.config.routing_algorithm);
-    Ok(HttpResponse::Ok().json(RouteProviderRes {
+    debug!("Computed route with {} ({:?}, seed: {}, termination: {:?}). Sending response...",
+        &algorithm_name, &algorithm_params, algo.get_seed(), control.termination_reason());
+    //the route borrows from the graph, so it is serialized before leaving the compute pool
+    let summary = RouteSummary::new(&data.graph, &route, &start, &end, algo.get_collected_score(&route));
+    let instructions = route_request.language.map(|language| route.iter()
+        .map(|route_sector| route_sector.instructions(&data.graph, language))
+        .collect());
+    let polylines = route_request.polyline.map(|format| polyline::route_to_polylines(&route, format));
+    Ok(serialize(RouteProviderRes {
         route,
+        summary,
+        instructions,
+        polylines,
+        seed: algo.get_seed(),
+        termination: control.termination_reason(),
     }))
 }
 
+///Serializes a route response as JSON value
+fn route_response_to_json(response: RouteProviderRes) -> serde_json::Value {
+    serde_json::to_value(&response).expect("Unable to serialize route")
+}
+
+///Serializes a route response in the given format
+fn route_response_to_format(response: RouteProviderRes, format: RouteFormat) -> String {
+    match format {
+        RouteFormat::Json => serde_json::to_string(&response).expect("Unable to serialize route"),
+        RouteFormat::Gpx => gpx::route_to_gpx(&response.route),
+        RouteFormat::GeoJson => geojson::route_to_geojson(&response.route).to_string(),
+    }
+}
+
+///Creates the control for a route request that is answered directly.
+///The deadline covers the whole request, including the time spent in the queue.
+fn route_request_control(route_request: &RouteProviderReq, config: &Config) -> ComputeControl {
+    match route_request.max_compute_ms.into_iter()
+        .chain(config.max_compute_ms)
+        .min() {
+        Some(max_compute_ms) => ComputeControl::with_max_compute_ms(max_compute_ms),
+        None => ComputeControl::default(),
+    }
+}
+
+///Responds to post request asking for routing.
+///The route is returned as JSON, unless another format is requested by the `format` query
+///parameter or the `Accept` header.
+#[post("/route")]
+async fn post_route(request:  web::Json<RouteProviderReq>, query: web::Query<RouteFormatQuery>, http_request: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+    debug!("Received route request");
+
+    let route_request = request.into_inner();
+    let control = route_request_control(&route_request, &data.config);
+    let format = query.format
+        .or_else(|| accept_header(&http_request).and_then(RouteFormat::from_accept))
+        .unwrap_or(RouteFormat::Json);
+
+    let job_data = data.clone();
+    let result = match data.compute_pool.try_spawn(
+        move || compute_route_response(&job_data, route_request, &control,
+                                       |response| route_response_to_format(response, format))) {
+        Some(result) => result,
+        None => {
+            error!("Error in post_route: compute queue is full");
+            return Err(TrailScoutError::ServerBusy);
+        }
+    };
+
+    match result.await {
+        Ok(response) => Ok(HttpResponse::Ok().content_type(format.content_type()).body(response?)),
+        Err(_) => Err(TrailScoutError::ComputationFailedServer),
+    }
+}
+
+
+///Formats a server-sent event with the given name and JSON data
+fn sse_event<T: Serialize + ?Sized>(event: &str, data: &T) -> Bytes {
+    let data = serde_json::to_string(data).expect("Unable to serialize event");
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+///Responds to post request asking for routing with a stream of server-sent events.
+///Sends a `progress` event for each progress report of the algorithm and finally a `route` event
+///with the route response or an `error` event with the error message.
+#[post("/route/stream")]
+async fn post_route_stream(request:  web::Json<RouteProviderReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+    debug!("Received route stream request");
+
+    let route_request = request.into_inner();
+
+    //the stream ends once the computation is done and all senders are dropped
+    let (sender, receiver) = mpsc::unbounded::<Result<Bytes, Infallible>>();
+    let progress_sender = sender.clone();
+    let control = route_request_control(&route_request, &data.config)
+        .with_progress_listener(move |progress| {
+            //the receiver is gone if the client disconnected, so the event can be dropped
+            let _ = progress_sender.unbounded_send(Ok(sse_event("progress", progress)));
+        });
+
+    let job_data = data.clone();
+    let spawned = data.compute_pool.try_spawn(move || {
+        let event = match compute_route_response(&job_data, route_request, &control, route_response_to_json) {
+            Ok(response) => sse_event("route", &response),
+            Err(error) => sse_event("error", &error.to_string()),
+        };
+        let _ = sender.unbounded_send(Ok(event));
+    });
+    if spawned.is_none() {
+        error!("Error in post_route_stream: compute queue is full");
+        return Err(TrailScoutError::ServerBusy);
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(receiver))
+}
+
+///Starts computing the route for a route request in the background and responds with the id of
+///the route job
+#[post("/route/jobs")]
+async fn post_route_job(request:  web::Json<RouteProviderReq>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+    debug!("Received route job request");
+
+    let route_request = request.into_inner();
+
+    //jobs are meant for long computations, so only the requested limit applies
+    let control = Arc::new(match route_request.max_compute_ms {
+        Some(max_compute_ms) => ComputeControl::with_max_compute_ms(max_compute_ms),
+        None => ComputeControl::default(),
+    });
+    let id = data.route_jobs.create(control.clone());
+
+    let job_data = data.clone();
+    let job_id = id.clone();
+    let spawned = data.compute_pool.try_spawn(move || {
+        if !job_data.route_jobs.start(&job_id) {
+            debug!("Skipping cancelled route job {}", job_id);
+            return;
+        }
+        let result = compute_route_response(&job_data, route_request, &control, route_response_to_json)
+            .map_err(|error| error.to_string());
+        job_data.route_jobs.finish(&job_id, result);
+    });
+    if spawned.is_none() {
+        error!("Error in post_route_job: compute queue is full");
+        data.route_jobs.remove(&id);
+        return Err(TrailScoutError::ServerBusy);
+    }
+
+    match data.route_jobs.get(&id) {
+        Some(job) => Ok(HttpResponse::Accepted().json(job)),
+        None => Err(TrailScoutError::JobNotFoundServer),
+    }
+}
+
+///Responds to get request asking for the state of a route job
+#[get("/route/jobs/{id}")]
+async fn get_route_job(id: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+    match data.route_jobs.get(&id) {
+        Some(job) => Ok(HttpResponse::Ok().json(job)),
+        None => Err(TrailScoutError::JobNotFoundServer),
+    }
+}
+
+///Cancels a route job. The best route found so far stays available as its result.
+#[delete("/route/jobs/{id}")]
+async fn delete_route_job(id: web::Path<String>, data: web::Data<AppState>) -> Result<HttpResponse, TrailScoutError> {
+    debug!("Cancelling route job {}", id);
+    match data.route_jobs.cancel(&id) {
+        Some(job) => Ok(HttpResponse::Ok().json(job)),
+        None => Err(TrailScoutError::JobNotFoundServer),
+    }
+}
+
+
+///Responds to get request asking for the available routing algorithms
+#[get("/algorithms")]
+async fn get_algorithms() -> Result<HttpResponse, TrailScoutError> {
+    let algorithms: Vec<AlgorithmInfo> = Algorithm::available_algorithms().iter()
+        .map(|&name| AlgorithmInfo {
+            name,
+            description: Algorithm::algorithm_description(name).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(algorithms))
+}
+
 
 //server main
 #[actix_web::main]
@@ -128,22 +593,31 @@ async fn main() -> std::io::Result<()> {
 
     //If Source File exists but FMI graph does not, build it
     osm_graph_creator::checked_create_fmi_graph(&config.graph_file_path,
-                                                &config.source_file)?;
+                                                &config.source_file,
+                                                config.contract_graph)?;
 
     debug!("Starting to parsed graph from: {}", &config.graph_file_path);
     let graph = Graph::parse_from_file(&config.graph_file_path).expect("Error parsing graph from file");
     debug!("Parsed graph from: {}", &config.graph_file_path);
 
+    let compute_threads = config.compute_threads.unwrap_or_else(
+        || thread::available_parallelism().map_or(1, |num_threads| num_threads.get()));
+    debug!("Starting compute pool with {} threads and a queue for {} requests",
+        compute_threads, config.compute_queue_size);
+    let compute_pool = ComputePool::new(compute_threads, config.compute_queue_size);
+
     let data = web::Data::new(AppState {
         graph,
         config: config.clone(),
+        compute_pool,
+        route_jobs: RouteJobs::new(Duration::from_secs(config.job_ttl_secs)),
         //rw_lock_graph : Arc::new(RwLock::new(Graph::new())),
     });
 
     HttpServer::new(move|| {
         let cors = Cors::default()
             .allow_any_origin()
-            .allowed_methods(vec!["GET", "POST"])
+            .allowed_methods(vec!["GET", "POST", "DELETE"])
             .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
             .allowed_header(http::header::CONTENT_TYPE)
             .supports_credentials()
@@ -151,7 +625,15 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(cors)
             .service(post_sights)
+            .service(post_isochrone)
+            .service(post_path)
+            .service(post_matrix)
             .service(post_route)
+            .service(post_route_stream)
+            .service(post_route_job)
+            .service(get_route_job)
+            .service(delete_route_job)
+            .service(get_algorithms)
             .app_data(data.clone())
 
     })
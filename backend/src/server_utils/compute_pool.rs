@@ -0,0 +1,55 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::thread;
+use crossbeam::channel::{bounded, Sender, TrySendError};
+use futures::channel::oneshot;
+use log::error;
+
+///A job that runs on the compute pool
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+///Fixed number of threads that run CPU heavy jobs, e.g. route computations, outside of the actix
+///worker threads. Jobs wait in a bounded queue until a thread is free.
+pub struct ComputePool {
+    sender: Sender<Job>,
+}
+
+impl ComputePool {
+    ///Starts `num_threads` compute threads that share a queue for at most `queue_size` waiting jobs
+    pub fn new(num_threads: usize, queue_size: usize) -> Self {
+        let (sender, receiver) = bounded::<Job>(queue_size);
+        for i in 0..num_threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("compute-{}", i))
+                .spawn(move || {
+                    for job in receiver.iter() {
+                        //a panicking job must not take down the compute thread
+                        if catch_unwind(AssertUnwindSafe(job)).is_err() {
+                            error!("Job on compute pool panicked");
+                        }
+                    }
+                })
+                .expect("Unable to start compute thread");
+        }
+        Self {
+            sender,
+        }
+    }
+
+    ///Queues `job` for execution on the compute pool.
+    ///Returns a receiver for the result of the job or `None` if the queue is full.
+    ///The receiver is canceled if the job panics.
+    pub fn try_spawn<F, T>(&self, job: F) -> Option<oneshot::Receiver<T>>
+        where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+        let (result_sender, result_receiver) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            //the receiver is gone if the client disconnected, so the result can be dropped
+            let _ = result_sender.send(job());
+        });
+        match self.sender.try_send(job) {
+            Ok(()) => Some(result_receiver),
+            Err(TrySendError::Full(_)) => None,
+            Err(TrySendError::Disconnected(_)) => panic!("All compute threads terminated"),
+        }
+    }
+}
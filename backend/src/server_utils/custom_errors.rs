@@ -1,7 +1,10 @@
-use actix_web::{http::{header::ContentType, StatusCode}, HttpResponse, ResponseError};
+use actix_web::{http::{header::{ContentType, RETRY_AFTER}, StatusCode}, HttpResponse, ResponseError};
 use derive_more::{Display, Error};
 use trailscout_lib::algorithm::AlgorithmError;
 
+///Number of seconds after which clients should retry requests rejected because of a full queue
+const RETRY_AFTER_SECS: u64 = 5;
+
 ///Custom Error for TrailScout
 #[derive(Debug, Display, Error)]
 pub enum TrailScoutError {
@@ -25,7 +28,49 @@ pub enum TrailScoutError {
     NoNearestNodeFoundServer,
 
     #[display(fmt = "Zeitfenster ist negativ")]
-    NegativeTimeIntervalServer
+    NegativeTimeIntervalServer,
+
+    #[display(fmt = "Ziel ist im Zeitfenster nicht erreichbar")]
+    DestinationNotReachableServer,
+
+    #[display(fmt = "Folgende Pflicht-Sehenswürdigkeiten passen nicht in die Route: {}", sights)]
+    MandatorySightsNotFeasibleServer {
+        sights: String,
+    },
+
+    #[display(fmt = "Zu viele Sehenswürdigkeiten für den exakten Algorithmus")]
+    TooManyCandidatesServer,
+
+    #[display(fmt = "Innerhalb der Rechenzeit wurde keine Route mit allen Pflicht-Sehenswürdigkeiten gefunden")]
+    NoFeasibleRouteInTimeServer,
+
+    #[display(fmt = "Ungültiger Wert für Algorithmus-Parameter {} (erlaubt: {} bis {})", name, min, max)]
+    InvalidParameterServer {
+        name: String,
+        min: f64,
+        max: f64,
+    },
+
+    #[display(fmt = "Server ist ausgelastet, bitte später erneut versuchen")]
+    ServerBusy,
+
+    #[display(fmt = "Routenberechnung fehlgeschlagen")]
+    ComputationFailedServer,
+
+    #[display(fmt = "Routenauftrag nicht gefunden")]
+    JobNotFoundServer,
+
+    #[display(fmt = "Unbekannte Sehenswürdigkeit: {}", id)]
+    UnknownSightServer {
+        id: usize,
+    },
+
+    #[display(fmt = "Ungültiger Wert für {} (erlaubt: {} bis {})", name, min, max)]
+    InvalidRequestParameterServer {
+        name: String,
+        min: f64,
+        max: f64,
+    }
 }
 
 
@@ -33,14 +78,26 @@ impl ResponseError for TrailScoutError {
     fn status_code(&self) -> StatusCode {
         match *self {
             TrailScoutError::NegativeTimeIntervalServer | TrailScoutError::NoPreferencesProvidedServer|
-            TrailScoutError::BadAlgoServer | TrailScoutError::UnknownCategoryServer => StatusCode::BAD_REQUEST,
+            TrailScoutError::BadAlgoServer | TrailScoutError::UnknownCategoryServer |
+            TrailScoutError::DestinationNotReachableServer |
+            TrailScoutError::MandatorySightsNotFeasibleServer {..} |
+            TrailScoutError::TooManyCandidatesServer |
+            TrailScoutError::InvalidParameterServer {..} |
+            TrailScoutError::UnknownSightServer {..} |
+            TrailScoutError::InvalidRequestParameterServer {..} => StatusCode::BAD_REQUEST,
+            TrailScoutError::ServerBusy |
+            TrailScoutError::NoFeasibleRouteInTimeServer => StatusCode::SERVICE_UNAVAILABLE,
+            TrailScoutError::JobNotFoundServer => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
-            .insert_header(ContentType::json())
-            .json(self.to_string())
+        let mut response = HttpResponse::build(self.status_code());
+        response.insert_header(ContentType::json());
+        if let TrailScoutError::ServerBusy = self {
+            response.insert_header((RETRY_AFTER, RETRY_AFTER_SECS));
+        }
+        response.json(self.to_string())
     }
 }
 
@@ -54,5 +111,14 @@ pub fn match_error(x: AlgorithmError) -> TrailScoutError {
         AlgorithmError::NoPreferencesProvided {..} => { TrailScoutError::NoPreferencesProvidedServer },
         AlgorithmError::NoNearestNodeFound {..} => { TrailScoutError::NoNearestNodeFoundServer },
         AlgorithmError::UnknownAlgorithm {..} => {TrailScoutError::BadAlgoServer},
+        AlgorithmError::DestinationNotReachable => {TrailScoutError::DestinationNotReachableServer},
+        AlgorithmError::MandatorySightsNotFeasible { sights } => {
+            TrailScoutError::MandatorySightsNotFeasibleServer { sights: sights.join(", ") }
+        },
+        AlgorithmError::TooManyCandidates {..} => {TrailScoutError::TooManyCandidatesServer},
+        AlgorithmError::StoppedWithoutFeasibleRoute {..} => {TrailScoutError::NoFeasibleRouteInTimeServer},
+        AlgorithmError::InvalidParameter { name, min, max, .. } => {
+            TrailScoutError::InvalidParameterServer { name, min, max }
+        },
     }
 }
\ No newline at end of file
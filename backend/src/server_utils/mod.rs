@@ -1,2 +1,4 @@
+pub mod compute_pool;
 pub mod custom_errors;
 pub mod requests;
+pub mod route_jobs;
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
-use trailscout_lib::algorithm::{Area, Route, UserPreferences};
+use trailscout_lib::algorithm::instructions::{Instruction, Language};
+use trailscout_lib::algorithm::{AlgorithmParamsOverrides, Area, Coordinate, Route, RouteSummary, TerminationReason, UserPreferences};
+use trailscout_lib::data::graph::{Node, SightExclusions};
+use trailscout_lib::export::polyline::PolylineFormat;
 
 
 ///struct to contain parameters from route request
@@ -10,14 +13,218 @@ pub struct RouteProviderReq {
     /// Walking speed in kilometers per hour
     pub walking_speed_kmh: f64,
     pub area: Area,
+    /// Location where the walk should end. The walk is a round trip if not set.
+    pub destination: Option<Coordinate>,
     pub user_prefs: UserPreferences,
+    /// Name of the routing algorithm to use. Falls back to the configured algorithm if not set.
+    pub algorithm: Option<String>,
+    /// Overrides for the configured tuning parameters of the routing algorithms
+    #[serde(default)]
+    pub algorithm_params: AlgorithmParamsOverrides,
+    /// Seed for the route computation. A random seed is chosen if not set.
+    pub seed: Option<u64>,
+    /// Maximum time in milliseconds to compute the route. The best route found until then is
+    /// returned. Capped by the configured maximum.
+    pub max_compute_ms: Option<u64>,
+    /// Language of the walking instructions. No instructions are returned if not set.
+    pub language: Option<Language>,
+    /// Encoded polyline format of the sector paths. No polylines are returned if not set.
+    pub polyline: Option<PolylineFormat>,
 }
 
 
+///Output format of a computed route
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteFormat {
+    ///`RouteProviderRes` as JSON
+    Json,
+    ///GPX 1.1 document with the route as track and the visited sights as waypoints
+    Gpx,
+    ///GeoJSON `FeatureCollection` with the route sectors as lines and the visited sights as points
+    GeoJson,
+}
+
+impl RouteFormat {
+    ///Chooses the format requested by the `Accept` header, if any
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        media_types(accept).find_map(|media_type| match media_type {
+            "application/json" => Some(RouteFormat::Json),
+            "application/gpx+xml" => Some(RouteFormat::Gpx),
+            "application/geo+json" => Some(RouteFormat::GeoJson),
+            _ => None,
+        })
+    }
+
+    ///Content type of responses in this format
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RouteFormat::Json => "application/json",
+            RouteFormat::Gpx => "application/gpx+xml",
+            RouteFormat::GeoJson => "application/geo+json",
+        }
+    }
+}
+
+///Output format of sights
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SightsFormat {
+    ///List of sights as JSON
+    Json,
+    ///GeoJSON `FeatureCollection` with the sights as points
+    GeoJson,
+}
+
+impl SightsFormat {
+    ///Chooses the format requested by the `Accept` header, if any
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        media_types(accept).find_map(|media_type| match media_type {
+            "application/json" => Some(SightsFormat::Json),
+            "application/geo+json" => Some(SightsFormat::GeoJson),
+            _ => None,
+        })
+    }
+}
+
+///Media types of an `Accept` header without their parameters
+fn media_types(accept: &str) -> impl Iterator<Item = &str> {
+    accept.split(',')
+        .map(|media_range| media_range.split(';').next().unwrap_or_default().trim())
+}
+
+///Query parameters of the route request
+#[derive(Deserialize)]
+pub struct RouteFormatQuery {
+    ///Output format of the route. Takes precedence over the `Accept` header.
+    pub format: Option<RouteFormat>,
+}
+
 ///Response for Route request
 #[derive(Serialize)]
 pub struct RouteProviderRes<'a> {
     pub route: Route<'a>,
+    /// Totals of the route, e.g., its distance and the time spent walking
+    pub summary: RouteSummary,
+    /// Walking instructions for each sector of `route`, if a language was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<Vec<Vec<Instruction>>>,
+    /// Path of each sector of `route` as encoded polyline, if a polyline format was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polylines: Option<Vec<String>>,
+    /// Seed that reproduces `route` when sent with the same request
+    pub seed: u64,
+    /// Whether the computation converged or was stopped by the deadline
+    pub termination: TerminationReason,
+}
+
+///State of an asynchronous route job
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+///Response for route job requests
+#[derive(Serialize)]
+pub struct RouteJobRes {
+    pub id: String,
+    pub status: JobStatus,
+    /// Score of the best route found so far
+    pub best_score: usize,
+    /// Route response, once the job is finished or cancelled
+    pub result: Option<serde_json::Value>,
+    /// Error message, if the job failed
+    pub error: Option<String>,
+}
+
+///Name and short description of an available routing algorithm
+#[derive(Serialize)]
+pub struct AlgorithmInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+///Query parameters of the sights request
+#[derive(Deserialize)]
+pub struct SightsFormatQuery {
+    ///Output format of the sights. Takes precedence over the `Accept` header.
+    pub format: Option<SightsFormat>,
+}
+
+///struct to contain parameters from path request
+#[derive(Deserialize)]
+pub struct PathReq {
+    pub start: Coordinate,
+    pub end: Coordinate,
+    /// Locations to pass in the given order between start and end
+    #[serde(default)]
+    pub via: Vec<Coordinate>,
+    /// Walking speed in kilometers per hour
+    pub walking_speed_kmh: f64,
+    /// Encoded polyline format of the path. No polyline is returned if not set.
+    pub polyline: Option<PolylineFormat>,
+}
+
+///Distance and walking time between two consecutive waypoints of a path
+#[derive(Serialize)]
+pub struct PathLeg {
+    pub distance_meters: usize,
+    pub walking_secs: i64,
+}
+
+///struct to contain the shortest walking path from start via the via-points to the end
+#[derive(Serialize)]
+pub struct PathRes<'a> {
+    pub path: Vec<&'a Node>,
+    pub distance_meters: usize,
+    pub walking_secs: i64,
+    /// One leg per pair of consecutive waypoints
+    pub legs: Vec<PathLeg>,
+    /// `path` as encoded polyline, if a polyline format was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polyline: Option<String>,
+}
+
+///Location of a distance matrix, given by the id of a sight or by a coordinate
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum MatrixLocation {
+    Sight { sight_id: usize },
+    Coordinate(Coordinate),
+}
+
+///struct to contain parameters from matrix request
+#[derive(Deserialize)]
+pub struct MatrixReq {
+    /// Locations between which all walking distances are computed
+    pub locations: Vec<MatrixLocation>,
+    /// Walking speed in kilometers per hour
+    pub walking_speed_kmh: f64,
+}
+
+///struct to contain the walking distances and times between all pairs of locations. Entry `[i][j]`
+///refers to the walk from the `i`-th to the `j`-th location and is `null` if there is no such walk.
+#[derive(Serialize)]
+pub struct MatrixRes {
+    pub distances_meters: Vec<Vec<Option<usize>>>,
+    pub walking_secs: Vec<Vec<Option<i64>>>,
+}
+
+///struct to contain parameters from isochrone request
+#[derive(Deserialize)]
+pub struct IsochroneReq {
+    pub lat: f64,
+    pub lon: f64,
+    /// Walking speed in kilometers per hour
+    pub walking_speed_kmh: f64,
+    /// Time limits in minutes. An isochrone is returned for each limit.
+    pub time_limits_minutes: Vec<u64>,
+    /// Edge length in meters of the grid cells that make up the isochrones
+    pub cell_size_meters: Option<f64>,
 }
 
 ///struct to contain parameters from sights request
@@ -25,5 +232,8 @@ pub struct RouteProviderRes<'a> {
 pub struct SightsRequest {
    pub lat: f64,
    pub lon: f64,
-   pub radius: f64
+   pub radius: f64,
+   /// Sights that should not be returned
+   #[serde(default)]
+   pub exclude: SightExclusions,
 }
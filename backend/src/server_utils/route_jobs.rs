@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use rand::{Rng, thread_rng};
+use trailscout_lib::algorithm::ComputeControl;
+use crate::server_utils::requests::{JobStatus, RouteJobRes};
+
+///Route job computed in the background
+struct RouteJob {
+    status: JobStatus,
+    ///Shared with the computation to cancel it and to read its best score
+    control: Arc<ComputeControl>,
+    ///Serialized route response, once the job is done
+    result: Option<serde_json::Value>,
+    ///Error message, if the computation failed
+    error: Option<String>,
+    ///Point in time when the job was done
+    finished_at: Option<Instant>,
+}
+
+impl RouteJob {
+    fn is_done(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    fn to_response(&self, id: &str) -> RouteJobRes {
+        RouteJobRes {
+            id: id.to_string(),
+            status: self.status,
+            best_score: self.control.best_score(),
+            result: self.result.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+///Stores the route jobs by id. Jobs that are done expire after `ttl`.
+pub struct RouteJobs {
+    jobs: Mutex<HashMap<String, RouteJob>>,
+    ttl: Duration,
+}
+
+impl RouteJobs {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    ///Locks the jobs and removes the expired ones
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, RouteJob>> {
+        let mut jobs = self.jobs.lock().expect("Route jobs poisoned");
+        let ttl = self.ttl;
+        jobs.retain(|_, job| match job.finished_at {
+            Some(finished_at) => finished_at.elapsed() < ttl,
+            None => true,
+        });
+        jobs
+    }
+
+    ///Adds a queued job computed with `control` and returns its id
+    pub fn create(&self, control: Arc<ComputeControl>) -> String {
+        let mut jobs = self.lock();
+        let mut id = format!("{:032x}", thread_rng().gen::<u128>());
+        while jobs.contains_key(&id) {
+            id = format!("{:032x}", thread_rng().gen::<u128>());
+        }
+        jobs.insert(id.clone(), RouteJob {
+            status: JobStatus::Queued,
+            control,
+            result: None,
+            error: None,
+            finished_at: None,
+        });
+        id
+    }
+
+    ///Marks the job as running. Returns `false` if the job should not be computed, because it was
+    ///cancelled or has expired.
+    pub fn start(&self, id: &str) -> bool {
+        match self.lock().get_mut(id) {
+            Some(job) if job.status == JobStatus::Queued => {
+                job.status = JobStatus::Running;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    ///Stores the result of the job
+    pub fn finish(&self, id: &str, result: Result<serde_json::Value, String>) {
+        if let Some(job) = self.lock().get_mut(id) {
+            match result {
+                Ok(result) => {
+                    job.status = if job.control.is_cancelled() {
+                        JobStatus::Cancelled
+                    } else {
+                        JobStatus::Finished
+                    };
+                    job.result = Some(result);
+                }
+                Err(error) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(error);
+                }
+            }
+            job.finished_at = Some(Instant::now());
+        }
+    }
+
+    ///Removes a job that could not be queued
+    pub fn remove(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    ///Outputs the current state of the job or `None` if there is no such job
+    pub fn get(&self, id: &str) -> Option<RouteJobRes> {
+        self.lock().get(id).map(|job| job.to_response(id))
+    }
+
+    ///Cancels the job. A running computation stops cooperatively and keeps the best route found
+    ///so far as result. Outputs the state of the job or `None` if there is no such job.
+    pub fn cancel(&self, id: &str) -> Option<RouteJobRes> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(id)?;
+        if !job.is_done() {
+            job.control.cancel();
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(Instant::now());
+            }
+        }
+        Some(job.to_response(id))
+    }
+}
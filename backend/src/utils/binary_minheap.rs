@@ -97,6 +97,11 @@ impl BinaryMinHeap {
         min_key
     }
 
+    /// Returns the minimum key without removing it from the heap or `None` if the heap is empty
+    pub fn peek(&self) -> Option<usize> {
+        self.heap.first().copied()
+    }
+
     /// Decrease the position of a key.
     /// This method must be called iff the priority of a key
     /// decreases after the heap creation.
@@ -124,6 +129,11 @@ impl BinaryMinHeap {
         }
     }
 
+    /// Returns the number of keys in the heap
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
     /// Returns `true` if the heap is empty
     pub fn is_empty(&self) -> bool {
         self.heap.is_empty()
@@ -145,11 +155,14 @@ mod test {
         heap.push(3, &prios);
         heap.push(4, &prios);
 
+        assert_eq!(5, heap.len());
+        assert_eq!(Some(0), heap.peek());
         assert_eq!(0, heap.pop(&prios));
         assert_eq!(4, heap.pop(&prios));
         assert_eq!(2, heap.pop(&prios));
         assert_eq!(1, heap.pop(&prios));
         assert_eq!(3, heap.pop(&prios));
+        assert_eq!(None, heap.peek());
     }
 
     #[test]
@@ -1,3 +1,6 @@
+use std::collections::HashSet;
+use geoutils::Location;
+use rayon::prelude::*;
 use crate::data::graph::{Graph, Node};
 use crate::utils::binary_minheap::BinaryMinHeap;
 
@@ -6,7 +9,7 @@ pub struct NodeResult<'a>(usize, Vec<&'a Node>);
 
 impl<'a> NodeResult<'a> {
     /// Creates a new node result with distance `dist` and path `path` to the associated node
-    fn new(dist: usize, path: Vec<&'a Node>) -> Self {
+    pub(crate) fn new(dist: usize, path: Vec<&'a Node>) -> Self {
         Self(dist, path)
     }
 
@@ -73,11 +76,11 @@ impl DijkstraResult {
         let mut curr_pred = tgt_id;
         // source node has no predecessor
         while curr_pred < usize::MAX {
-            path.push(graph.get_node(curr_pred));
+            path.push(curr_pred);
             curr_pred = self.preds[curr_pred];
         }
         path.reverse();
-        path
+        graph.expand_path(path)
     }
 }
 
@@ -139,6 +142,151 @@ pub fn run_dijkstra(graph: &Graph, src_id: usize, tgt_id: usize) -> Option<NodeR
     result.result_of(graph, tgt_id)
 }
 
+/// Run a bidirectional Dijkstra from the source node with id `src_id` to the target node with id
+/// `tgt_id`. A forward search from the source and a backward search from the target are run
+/// alternately, always advancing the search with fewer nodes in its priority queue, until the sum
+/// of both minimum tentative distances reaches the length of the shortest path found so far.
+/// Each edge of the graph has a reverse edge with the same weight, so the backward search follows
+/// the outgoing edges as well.
+pub fn run_bidirectional_dijkstra(graph: &Graph, src_id: usize, tgt_id: usize) -> Option<NodeResult<'_>> {
+    let (mut fwd_result, mut fwd_pq) = init_result_and_pq(graph, src_id);
+    let (mut bwd_result, mut bwd_pq) = init_result_and_pq(graph, tgt_id);
+    // shortest path length found so far and the node where its forward and backward parts meet
+    let (mut best_dist, mut meeting_node_id) = if src_id == tgt_id {
+        (0, src_id)
+    } else {
+        (usize::MAX, usize::MAX)
+    };
+
+    while let (Some(fwd_min_id), Some(bwd_min_id)) = (fwd_pq.peek(), bwd_pq.peek()) {
+        if fwd_result.dists[fwd_min_id] + bwd_result.dists[bwd_min_id] >= best_dist {
+            break;
+        }
+
+        let (result, pq, other_result) = if fwd_pq.len() <= bwd_pq.len() {
+            (&mut fwd_result, &mut fwd_pq, &bwd_result)
+        } else {
+            (&mut bwd_result, &mut bwd_pq, &fwd_result)
+        };
+        let node_id = pq.pop(&result.dists);
+        let node_dist = result.dists[node_id];
+        for edge in graph.get_outgoing_edges(node_id) {
+            let dist = node_dist + edge.dist;
+
+            if dist < result.dists[edge.tgt] {
+                result.dists[edge.tgt] = dist;
+                result.preds[edge.tgt] = node_id;
+
+                pq.insert_or_update(edge.tgt, &result.dists);
+
+                let other_dist = other_result.dists[edge.tgt];
+                if other_dist < usize::MAX && dist + other_dist < best_dist {
+                    best_dist = dist + other_dist;
+                    meeting_node_id = edge.tgt;
+                }
+            }
+        }
+    }
+
+    match best_dist {
+        usize::MAX => None,
+        dist => {
+            let mut path = vec![];
+            let mut curr_pred = meeting_node_id;
+            while curr_pred < usize::MAX {
+                path.push(curr_pred);
+                curr_pred = fwd_result.preds[curr_pred];
+            }
+            path.reverse();
+            // the predecessors of the backward search lead towards the target
+            let mut curr_succ = bwd_result.preds[meeting_node_id];
+            while curr_succ < usize::MAX {
+                path.push(curr_succ);
+                curr_succ = bwd_result.preds[curr_succ];
+            }
+            Some(NodeResult::new(dist, graph.expand_path(path)))
+        }
+    }
+}
+
+/// Run an A* search from the source node with id `src_id` to the target node with id `tgt_id`.
+/// The nodes are explored in the order of their distance from the source plus the estimated
+/// distance to the target, which is the haversine distance scaled by `Graph::astar_scale`.
+/// Since the scaled haversine distance between the nodes of an edge never exceeds its weight, the
+/// estimate never exceeds the weight of an edge plus the estimate at its target, so the found path
+/// is a shortest path.
+pub fn run_astar(graph: &Graph, src_id: usize, tgt_id: usize) -> Option<NodeResult<'_>> {
+    let tgt = graph.get_node(tgt_id);
+    let tgt_loc = Location::new(tgt.lat, tgt.lon);
+    let estimate_dist_to_tgt = |node_id: usize| {
+        let node = graph.get_node(node_id);
+        let dist = Location::new(node.lat, node.lon).haversine_distance_to(&tgt_loc).meters();
+        (dist * graph.astar_scale()) as usize
+    };
+
+    let mut result = DijkstraResult::new(graph.num_nodes);
+    result.dists[src_id] = 0;
+    // priorities of the nodes, i.e., their distance from the source plus their estimate
+    let mut keys = vec![usize::MAX; graph.num_nodes];
+    keys[src_id] = estimate_dist_to_tgt(src_id);
+    let mut pq = BinaryMinHeap::with_capacity(graph.num_nodes);
+    pq.push(src_id, &keys);
+
+    while !pq.is_empty() {
+        let node_id = pq.pop(&keys);
+        if node_id == tgt_id {
+            break;
+        }
+        let node_dist = result.dists[node_id];
+        for edge in graph.get_outgoing_edges(node_id) {
+            let dist = node_dist + edge.dist;
+
+            if dist < result.dists[edge.tgt] {
+                let estimate = match result.dists[edge.tgt] {
+                    usize::MAX => estimate_dist_to_tgt(edge.tgt),
+                    prev_dist => keys[edge.tgt] - prev_dist,
+                };
+                result.dists[edge.tgt] = dist;
+                result.preds[edge.tgt] = node_id;
+                keys[edge.tgt] = dist + estimate;
+
+                pq.insert_or_update(edge.tgt, &keys);
+            }
+        }
+    }
+
+    result.result_of(graph, tgt_id)
+}
+
+/// Run a Dijkstra between each pair of consecutive nodes with ids in `node_ids`, i.e., from the
+/// first node via the intermediate nodes to the last node. Returns the results of these legs in a
+/// `Some` or `None` if any leg's target is not reachable from its source
+pub fn run_dijkstra_via<'a>(graph: &'a Graph, node_ids: &[usize]) -> Option<Vec<NodeResult<'a>>> {
+    node_ids.windows(2)
+        .map(|leg| run_dijkstra(graph, leg[0], leg[1]))
+        .collect()
+}
+
+/// Run a Dijkstra from each source node with id in `src_ids` until all target nodes with ids in
+/// `tgt_ids` are reached. The runs are distributed over the rayon thread pool.
+/// Returns the distance matrix, whose `i`-th row holds the distances from the `i`-th source node to
+/// the target nodes in the order of `tgt_ids`, with `None` for target nodes that are not reachable
+pub fn run_many_to_many_dijkstra(graph: &Graph, src_ids: &[usize], tgt_ids: &[usize]) -> Vec<Vec<Option<usize>>> {
+    let tgt_id_set: HashSet<usize> = tgt_ids.iter().copied().collect();
+    src_ids.par_iter()
+        .map(|&src_id| {
+            let mut num_unreached_tgts = tgt_id_set.len();
+            let result = run_partial_dijkstra(graph, src_id, |node_id, _| {
+                if tgt_id_set.contains(&node_id) {
+                    num_unreached_tgts -= 1;
+                }
+                num_unreached_tgts == 0
+            });
+            tgt_ids.iter().map(|&tgt_id| result.dist_to(tgt_id)).collect()
+        })
+        .collect()
+}
+
 /// Run a Dijkstra from the source node with id `src_id` to all other nodes
 pub fn run_ota_dijkstra(graph: &Graph, src_id: usize) -> DijkstraResult {
     let (mut result, mut pq) = init_result_and_pq(graph, src_id);
@@ -223,10 +371,12 @@ pub fn run_partial_dijkstra_in_area<F>(graph: &Graph, src_id: usize, mut stop_co
 
 #[cfg(test)]
 mod test {
+    use geoutils::Location;
     use pathfinding::prelude::{dijkstra, dijkstra_all, dijkstra_partial};
     use rand::{Rng, thread_rng};
+    use crate::data::graph::{Graph, Node};
     use crate::init_logging;
-    use crate::utils::dijkstra::{run_dijkstra, run_ota_dijkstra, run_ota_dijkstra_in_area, run_partial_dijkstra};
+    use crate::utils::dijkstra::{run_astar, run_bidirectional_dijkstra, run_dijkstra, run_dijkstra_via, run_many_to_many_dijkstra, run_ota_dijkstra, run_ota_dijkstra_in_area, run_partial_dijkstra};
     use crate::utils::test_setup;
 
     #[test]
@@ -396,6 +546,175 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_bidirectional_dijkstra() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        check_point_to_point_search(graph, |src_id, tgt_id|
+            run_bidirectional_dijkstra(graph, src_id, tgt_id).map(|result| (result.dist(), result.consume_path())));
+    }
+
+    #[test]
+    fn test_astar() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        check_point_to_point_search(graph, |src_id, tgt_id|
+            run_astar(graph, src_id, tgt_id).map(|result| (result.dist(), result.consume_path())));
+    }
+
+    #[test]
+    fn test_astar_estimate_within_edge_weights() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let scale = graph.astar_scale();
+        assert!(scale > 0.0 && scale <= 1.01, "Unexpected A* scale {}", scale);
+        for edge in graph.edges.iter().filter(|edge| edge.dist > 0) {
+            let (src, tgt) = (graph.get_node(edge.src), graph.get_node(edge.tgt));
+            let dist = Location::new(src.lat, src.lon)
+                .haversine_distance_to(&Location::new(tgt.lat, tgt.lon)).meters();
+            assert!(dist * scale <= edge.dist as f64 + 1e-9,
+                    "Estimate exceeds weight of edge from {} to {}", edge.src, edge.tgt);
+        }
+    }
+
+    /// Compares the distances found by `search` between random nodes to the ones of the
+    /// `pathfinding` Dijkstra and checks that the found paths have the reported length
+    fn check_point_to_point_search<'a, F>(graph: &'a Graph, search: F)
+        where F: Fn(usize, usize) -> Option<(usize, Vec<&'a Node>)>
+    {
+        let successors = |node_id: usize|
+            graph.get_outgoing_edges(node_id)
+                .iter()
+                .map(|edge| (edge.tgt, edge.dist))
+                .collect::<Vec<(usize, usize)>>();
+
+        let mut rng = thread_rng();
+        for _ in 0..5 {
+            let src_id = rng.gen_range(0..graph.num_nodes);
+            let tgt_id = rng.gen_range(0..graph.num_nodes);
+
+            let result = search(src_id, tgt_id);
+            let exp_result = dijkstra(&src_id,
+                                      |&node_id| successors(node_id),
+                                      |&node_id| node_id == tgt_id);
+
+            match (result, exp_result) {
+                (Some((actual_dist, path)), Some((_, exp_dist))) => {
+                    assert_eq!(actual_dist, exp_dist, "Distances from {} to {} differ: actual: {}, expected: {}",
+                               src_id, tgt_id, actual_dist, exp_dist);
+                    assert_eq!(path.first().unwrap().id, src_id);
+                    assert_eq!(path.last().unwrap().id, tgt_id);
+                    let path_len: usize = path.windows(2)
+                        .map(|pair| graph.get_edge(pair[0].id, pair[1].id)
+                            .expect("Path edge missing in graph").dist)
+                        .sum();
+                    assert_eq!(path_len, actual_dist);
+                }
+                (None, None) => {}
+                _ => panic!("Reachability of {} from {} differs", tgt_id, src_id),
+            }
+        }
+        // the path from a node to itself solely consists of the node
+        let node_id = rng.gen_range(0..graph.num_nodes);
+        let (dist, path) = search(node_id, node_id).unwrap();
+        assert_eq!(dist, 0);
+        assert_eq!(path.len(), 1);
+    }
+
+    #[test]
+    fn test_dijkstra_via() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+
+        let src_id = graph.get_nearest_node(53.074448, 8.805105);
+        let via_id = graph.get_nearest_node(53.075860, 8.807190);
+        let tgt_id = graph.get_nearest_node(53.076580, 8.809150);
+
+        let legs = run_dijkstra_via(graph, &[src_id, via_id, tgt_id])
+            .expect("Via node or target not reachable");
+        assert_eq!(legs.len(), 2);
+        for (leg, (leg_src, leg_tgt)) in legs.iter().zip([(src_id, via_id), (via_id, tgt_id)]) {
+            assert_eq!(leg.path().first().unwrap().id, leg_src);
+            assert_eq!(leg.path().last().unwrap().id, leg_tgt);
+            assert_eq!(leg.dist(), run_dijkstra(graph, leg_src, leg_tgt).unwrap().dist());
+        }
+        let direct_dist = run_dijkstra(graph, src_id, tgt_id).unwrap().dist();
+        assert!(legs.iter().map(|leg| leg.dist()).sum::<usize>() >= direct_dist);
+
+        assert!(run_dijkstra_via(graph, &[src_id]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_many_to_many_dijkstra() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+
+        let mut rng = thread_rng();
+        let src_ids = (0..3).map(|_| rng.gen_range(0..graph.num_nodes)).collect::<Vec<_>>();
+        let mut tgt_ids = (0..4).map(|_| rng.gen_range(0..graph.num_nodes)).collect::<Vec<_>>();
+        // sources may be targets as well
+        tgt_ids.push(src_ids[0]);
+
+        let matrix = run_many_to_many_dijkstra(graph, &src_ids, &tgt_ids);
+
+        assert_eq!(matrix.len(), src_ids.len());
+        for (row, &src_id) in matrix.iter().zip(&src_ids) {
+            assert_eq!(row.len(), tgt_ids.len());
+            for (&dist, &tgt_id) in row.iter().zip(&tgt_ids) {
+                let exp_dist = run_dijkstra(graph, src_id, tgt_id).map(|result| result.dist());
+                assert_eq!(dist, exp_dist, "Distances from {} to {} differ", src_id, tgt_id);
+            }
+        }
+        assert_eq!(matrix[0][tgt_ids.len() - 1], Some(0));
+    }
+
+    #[test]
+    fn test_contracted_graph_keeps_dists_and_paths() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let contracted_graph = &test_setup::CONTRACTED_GRAPH;
+        assert_eq!(contracted_graph.num_nodes, graph.num_nodes);
+        assert!(contracted_graph.num_edges < graph.num_edges, "No nodes were contracted");
+
+        // contracted nodes have no edges anymore
+        let mut rng = thread_rng();
+        let mut random_node_id = || loop {
+            let node_id = rng.gen_range(0..contracted_graph.num_nodes);
+            if contracted_graph.get_degree(node_id) > 0 {
+                break node_id;
+            }
+        };
+        for _ in 0..5 {
+            let src_id = random_node_id();
+            let tgt_id = random_node_id();
+
+            let result = run_dijkstra(graph, src_id, tgt_id);
+            let contracted_result = run_dijkstra(contracted_graph, src_id, tgt_id);
+            match (result, contracted_result) {
+                (Some(result), Some(contracted_result)) => {
+                    assert_eq!(contracted_result.dist(), result.dist());
+                    // the path with shape points is a path in the original graph
+                    let path = contracted_result.path();
+                    assert_eq!(path.first().unwrap().id, src_id);
+                    assert_eq!(path.last().unwrap().id, tgt_id);
+                    let path_len: usize = path.windows(2)
+                        .map(|pair| graph.get_edge(pair[0].id, pair[1].id)
+                            .expect("Path edge missing in original graph").dist)
+                        .sum();
+                    assert_eq!(path_len, result.dist());
+                }
+                (None, None) => {}
+                _ => panic!("Reachability of {} from {} differs after contraction", tgt_id, src_id),
+            }
+        }
+    }
+
     #[test]
     fn test_ota_dijkstra_in_area() {
         init_logging();
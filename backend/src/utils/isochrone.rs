@@ -0,0 +1,389 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use geoutils::Location;
+use crate::data::graph::{Graph, Node};
+use crate::utils::dijkstra::run_partial_dijkstra;
+
+/// Default edge length in meters of the grid cells that make up isochrone polygons
+pub const DEFAULT_CELL_SIZE_METERS: f64 = 50.0;
+
+/// Meters per degree of latitude, and of longitude at the equator
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Area reachable on foot within a time limit
+///
+/// # Fields
+/// * `time_limit_secs` - The time limit in seconds
+/// * `max_dist_meters` - The walking distance in meters within the time limit
+/// * `polygons` - The polygons covering the streets reachable within the time limit. Each polygon
+///   is given by its counterclockwise outer ring of (latitude, longitude) pairs, whose first and
+///   last coordinate are equal.
+#[derive(Debug)]
+pub struct Isochrone {
+    pub time_limit_secs: u64,
+    pub max_dist_meters: usize,
+    pub polygons: Vec<Vec<(f64, f64)>>,
+}
+
+/// Computes the areas reachable on foot from the node with id `src_id` within each of the time
+/// limits in `time_limits_secs`.
+///
+/// A single Dijkstra run determines the walking distances up to the largest limit. For each limit,
+/// the reachable streets, including the reachable parts of edges leading out of the area, are
+/// rasterized into a grid with cells of `cell_size_meters`. Cells enclosed by reachable cells are
+/// added, such that the polygons have no holes. The outlines of the cells form the polygons.
+///
+/// # Arguments
+/// * `graph` - The graph to walk on
+/// * `src_id` - The id of the node where the walk starts
+/// * `walking_speed_mps` - The walking speed in meters per second
+/// * `time_limits_secs` - The time limits in seconds, one isochrone is computed per limit
+/// * `cell_size_meters` - The edge length of the grid cells in meters
+pub fn compute_isochrones(graph: &Graph, src_id: usize, walking_speed_mps: f64,
+                          time_limits_secs: &[u64], cell_size_meters: f64) -> Vec<Isochrone> {
+    let max_dists = time_limits_secs.iter()
+        .map(|&time_limit| (walking_speed_mps * time_limit as f64) as usize)
+        .collect::<Vec<_>>();
+    let reached_nodes = reachable_nodes(graph, src_id,
+                                        max_dists.iter().copied().max().unwrap_or(0));
+
+    let src_node = graph.get_node(src_id);
+    time_limits_secs.iter().zip(max_dists)
+        .map(|(&time_limit_secs, max_dist)| {
+            let mut grid = Grid::new(src_node.lat, src_node.lon, cell_size_meters);
+            for &(node_id, dist) in reached_nodes.iter().filter(|&&(_, dist)| dist <= max_dist) {
+                let node = graph.get_node(node_id);
+                grid.mark(grid.project(node));
+                // walk along each edge as far as the distance left allows
+                for edge in graph.get_outgoing_edges(node_id) {
+                    let edge_nodes = std::iter::once(node)
+                        .chain(graph.get_edge_shape(edge).iter().map(|&shape_id| graph.get_node(shape_id)))
+                        .chain(std::iter::once(graph.get_node(edge.tgt)));
+                    grid.mark_path(edge_nodes, (max_dist - dist) as f64);
+                }
+            }
+            Isochrone {
+                time_limit_secs,
+                max_dist_meters: max_dist,
+                polygons: grid.into_polygons(),
+            }
+        })
+        .collect()
+}
+
+/// Computes the largest straight-line distance in meters from the node with id `src_id` to any
+/// node within a walking distance of `max_dist_meters`.
+/// In contrast to estimates from the walking distance alone, the radius follows the actual street
+/// network, e.g., to pick the radius in which sights are searched.
+pub fn reachable_radius(graph: &Graph, src_id: usize, max_dist_meters: usize) -> f64 {
+    let src_node = graph.get_node(src_id);
+    let src_loc = Location::new(src_node.lat, src_node.lon);
+    reachable_nodes(graph, src_id, max_dist_meters).into_iter()
+        .map(|(node_id, _)| {
+            let node = graph.get_node(node_id);
+            src_loc.haversine_distance_to(&Location::new(node.lat, node.lon)).meters()
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Ids of the nodes within a walking distance of `max_dist` from the node with id `src_id`
+/// together with their walking distances
+fn reachable_nodes(graph: &Graph, src_id: usize, max_dist: usize) -> Vec<(usize, usize)> {
+    // popped nodes are always reached
+    let result = run_partial_dijkstra(graph, src_id,
+                                      |node_id, result| result.dist_to(node_id) > Some(max_dist));
+    (0..graph.num_nodes)
+        .filter_map(|node_id| match result.dist_to(node_id) {
+            Some(dist) if dist <= max_dist => Some((node_id, dist)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Grid of square cells on a local equirectangular projection around a center coordinate.
+/// Cell `(i, j)` covers the projected coordinates from `(i, j)` to `(i + 1, j + 1)` times the cell
+/// size, where x points east and y points north.
+struct Grid {
+    center_lat: f64,
+    center_lon: f64,
+    /// Meters per degree of longitude at the latitude of the center
+    meters_per_degree_lon: f64,
+    cell_size: f64,
+    cells: HashSet<(i64, i64)>,
+}
+
+impl Grid {
+    fn new(center_lat: f64, center_lon: f64, cell_size: f64) -> Self {
+        Self {
+            center_lat,
+            center_lon,
+            meters_per_degree_lon: METERS_PER_DEGREE * center_lat.to_radians().cos(),
+            cell_size,
+            cells: HashSet::new(),
+        }
+    }
+
+    /// Projected coordinates of `node` in meters
+    fn project(&self, node: &Node) -> (f64, f64) {
+        ((node.lon - self.center_lon) * self.meters_per_degree_lon,
+         (node.lat - self.center_lat) * METERS_PER_DEGREE)
+    }
+
+    /// (latitude, longitude) of the grid corner `(i, j)`
+    fn unproject(&self, (i, j): (i64, i64)) -> (f64, f64) {
+        (self.center_lat + (j as f64 * self.cell_size) / METERS_PER_DEGREE,
+         self.center_lon + (i as f64 * self.cell_size) / self.meters_per_degree_lon)
+    }
+
+    /// Marks the cell containing the projected point `(x, y)`
+    fn mark(&mut self, (x, y): (f64, f64)) {
+        self.cells.insert(((x / self.cell_size).floor() as i64, (y / self.cell_size).floor() as i64));
+    }
+
+    /// Marks the cells along the path through `nodes` up to a length of `max_length` meters
+    fn mark_path<'a>(&mut self, nodes: impl Iterator<Item = &'a Node>, max_length: f64) {
+        let mut length_left = max_length;
+        let mut points = nodes.map(|node| self.project(node)).collect::<Vec<_>>().into_iter();
+        let mut prev = match points.next() {
+            Some(point) => point,
+            None => return,
+        };
+        for point in points {
+            if length_left <= 0.0 {
+                break;
+            }
+            let segment_length = ((point.0 - prev.0).powi(2) + (point.1 - prev.1).powi(2)).sqrt();
+            let walked = segment_length.min(length_left);
+            // sample at half the cell size, such that no cell along the segment is skipped
+            let num_samples = (walked / (self.cell_size / 2.0)).ceil() as usize;
+            for sample in 1..=num_samples {
+                let t = walked * sample as f64 / num_samples as f64 / segment_length;
+                self.mark((prev.0 + t * (point.0 - prev.0), prev.1 + t * (point.1 - prev.1)));
+            }
+            length_left -= segment_length;
+            prev = point;
+        }
+    }
+
+    /// Outer rings of the marked cells with enclosed cells filled, as (latitude, longitude) pairs
+    fn into_polygons(self) -> Vec<Vec<(f64, f64)>> {
+        cells_to_rings(&self.cells).into_iter()
+            .map(|ring| ring.into_iter().map(|corner| self.unproject(corner)).collect())
+            .collect()
+    }
+}
+
+/// Traces the outlines of the union of `cells` after filling the cells enclosed by them.
+/// Returns the counterclockwise rings of grid corners, whose first and last corner are equal.
+/// Cells that only touch at a corner belong to separate rings.
+fn cells_to_rings(cells: &HashSet<(i64, i64)>) -> Vec<Vec<(i64, i64)>> {
+    if cells.is_empty() {
+        return Vec::new();
+    }
+    let cells = fill_enclosed_cells(cells);
+
+    // boundary sides of the cells, directed such that the cell lies to the left
+    let mut boundary: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+    for &(i, j) in &cells {
+        let sides = [
+            ((i, j - 1), (i, j), (i + 1, j)),
+            ((i + 1, j), (i + 1, j), (i + 1, j + 1)),
+            ((i, j + 1), (i + 1, j + 1), (i, j + 1)),
+            ((i - 1, j), (i, j + 1), (i, j)),
+        ];
+        for (neighbour, from, to) in sides {
+            if !cells.contains(&neighbour) {
+                boundary.entry(from).or_default().push(to);
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+    while let Some(&start) = boundary.keys().next() {
+        let mut ring = vec![start];
+        let mut corner = start;
+        let mut direction: Option<(i64, i64)> = None;
+        loop {
+            let targets = boundary.get_mut(&corner).expect("Boundary of cells is not closed");
+            // where cells touch at a corner, turn left to stay at the current cell
+            let index = match direction {
+                Some((dx, dy)) if targets.len() > 1 => targets.iter()
+                    .position(|&(x, y)| (x - corner.0, y - corner.1) == (-dy, dx))
+                    .unwrap_or(0),
+                _ => 0,
+            };
+            let next = targets.swap_remove(index);
+            if targets.is_empty() {
+                boundary.remove(&corner);
+            }
+            let next_direction = (next.0 - corner.0, next.1 - corner.1);
+            // drop corners in the middle of straight lines
+            if direction == Some(next_direction) {
+                ring.pop();
+            }
+            ring.push(next);
+            direction = Some(next_direction);
+            corner = next;
+            if corner == start {
+                break;
+            }
+        }
+        // the start may lie in the middle of a straight line as well
+        if ring.len() > 2 && is_collinear(ring[ring.len() - 2], ring[0], ring[1]) {
+            ring.remove(0);
+            let last = ring.len() - 1;
+            ring[last] = ring[0];
+        }
+        rings.push(ring);
+    }
+    rings
+}
+
+/// Whether `b` lies on the straight line from `a` to `c`
+fn is_collinear(a: (i64, i64), b: (i64, i64), c: (i64, i64)) -> bool {
+    (b.0 - a.0) * (c.1 - b.1) == (b.1 - a.1) * (c.0 - b.0)
+}
+
+/// Adds the cells enclosed by `cells` to them. A cell is enclosed, if it cannot be reached from
+/// outside the bounding box of `cells` via cells sharing a side.
+fn fill_enclosed_cells(cells: &HashSet<(i64, i64)>) -> HashSet<(i64, i64)> {
+    // bounding box with a margin of one cell, such that the margin is connected outside
+    let min_i = cells.iter().map(|&(i, _)| i).min().unwrap() - 1;
+    let max_i = cells.iter().map(|&(i, _)| i).max().unwrap() + 1;
+    let min_j = cells.iter().map(|&(_, j)| j).min().unwrap() - 1;
+    let max_j = cells.iter().map(|&(_, j)| j).max().unwrap() + 1;
+
+    let mut outside = HashSet::new();
+    let mut queue = VecDeque::new();
+    outside.insert((min_i, min_j));
+    queue.push_back((min_i, min_j));
+    while let Some((i, j)) = queue.pop_front() {
+        for neighbour in [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)] {
+            if neighbour.0 >= min_i && neighbour.0 <= max_i && neighbour.1 >= min_j && neighbour.1 <= max_j
+                && !cells.contains(&neighbour) && outside.insert(neighbour) {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    (min_i + 1..max_i)
+        .flat_map(|i| (min_j + 1..max_j).map(move |j| (i, j)))
+        .filter(|cell| !outside.contains(cell))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use geoutils::Location;
+    use crate::init_logging;
+    use crate::utils::dijkstra::run_ota_dijkstra;
+    use crate::utils::isochrone::{cells_to_rings, compute_isochrones, reachable_radius, DEFAULT_CELL_SIZE_METERS};
+    use crate::utils::test_setup;
+
+    /// Baba Hotel, ich schwör!!
+    const RADISSON_BLU_HOTEL: (f64, f64) = (53.074448, 8.805105);
+
+    /// Whether `point` lies inside the polygon with outer ring `ring`
+    fn contains(ring: &[(f64, f64)], (lat, lon): (f64, f64)) -> bool {
+        let mut inside = false;
+        for side in ring.windows(2) {
+            let ((lat1, lon1), (lat2, lon2)) = (side[0], side[1]);
+            if (lat1 > lat) != (lat2 > lat)
+                && lon < lon1 + (lat - lat1) / (lat2 - lat1) * (lon2 - lon1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    #[test]
+    fn test_rings_of_cells() {
+        // L-shape
+        let cells = HashSet::from([(0, 0), (1, 0), (0, 1)]);
+        let rings = cells_to_rings(&cells);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rotate_to(rings[0].clone(), (0, 0)),
+                   vec![(0, 0), (2, 0), (2, 1), (1, 1), (1, 2), (0, 2), (0, 0)]);
+
+        // ring of cells around a hole, which is filled
+        let cells = (0..3).flat_map(|i| (0..3).map(move |j| (i, j)))
+            .filter(|&cell| cell != (1, 1))
+            .collect::<HashSet<_>>();
+        let rings = cells_to_rings(&cells);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rings[0].len(), 5);
+
+        // cells touching at a corner
+        let cells = HashSet::from([(0, 0), (1, 1)]);
+        let rings = cells_to_rings(&cells);
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|ring| ring.len() == 5 && ring.first() == ring.last()));
+    }
+
+    /// Area of the polygon with outer ring `ring` in square degrees
+    fn area(ring: &[(f64, f64)]) -> f64 {
+        ring.windows(2)
+            .map(|side| side[0].1 * side[1].0 - side[1].1 * side[0].0)
+            .sum::<f64>() / 2.0
+    }
+
+    /// Rotates the closed `ring` such that it starts at `start`
+    fn rotate_to(mut ring: Vec<(i64, i64)>, start: (i64, i64)) -> Vec<(i64, i64)> {
+        ring.pop();
+        let index = ring.iter().position(|&corner| corner == start).unwrap();
+        ring.rotate_left(index);
+        ring.push(start);
+        ring
+    }
+
+    #[test]
+    fn test_isochrones_cover_reachable_nodes() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let (lat, lon) = RADISSON_BLU_HOTEL;
+        let src_id = graph.get_nearest_node(lat, lon);
+        let walking_speed_mps = 5.0 / 3.6;
+        let isochrones = compute_isochrones(graph, src_id, walking_speed_mps, &[300, 900],
+                                            DEFAULT_CELL_SIZE_METERS);
+
+        assert_eq!(isochrones.len(), 2);
+        let result = run_ota_dijkstra(graph, src_id);
+        for isochrone in &isochrones {
+            assert!(!isochrone.polygons.is_empty());
+            assert!(isochrone.polygons.iter().all(|ring| ring.first() == ring.last()));
+            // check some nodes, the isochrone around the hotel covers thousands of nodes
+            let reachable_nodes = (0..graph.num_nodes)
+                .filter(|&node_id| matches!(result.dist_to(node_id),
+                    Some(dist) if dist <= isochrone.max_dist_meters))
+                .step_by(10);
+            for node_id in reachable_nodes {
+                let node = graph.get_node(node_id);
+                assert!(isochrone.polygons.iter().any(|ring| contains(ring, (node.lat, node.lon))),
+                        "Node {} reachable within {} m is not covered", node_id, isochrone.max_dist_meters);
+            }
+        }
+        let area = |index: usize| isochrones[index].polygons.iter().map(|ring| area(ring)).sum::<f64>();
+        assert!(area(1) > area(0));
+    }
+
+    #[test]
+    fn test_reachable_radius_below_walking_distance() {
+        init_logging();
+
+        let graph = &test_setup::GRAPH;
+        let (lat, lon) = RADISSON_BLU_HOTEL;
+        let src_id = graph.get_nearest_node(lat, lon);
+        let radius = reachable_radius(graph, src_id, 1000);
+
+        // edge weights are whole meters rounded down, so the walking distance is underestimated
+        assert!(radius > 0.0 && radius <= 1050.0, "Unexpected radius {}", radius);
+        let src_node = graph.get_node(src_id);
+        let src_loc = Location::new(src_node.lat, src_node.lon);
+        let result = run_ota_dijkstra(graph, src_id);
+        for node in graph.nodes().iter()
+            .filter(|node| matches!(result.dist_to(node.id), Some(dist) if dist <= 1000)) {
+            assert!(src_loc.haversine_distance_to(&Location::new(node.lat, node.lon)).meters() <= radius);
+        }
+    }
+}
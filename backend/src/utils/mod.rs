@@ -1,3 +1,4 @@
 pub mod dijkstra;
+pub mod isochrone;
 pub mod test_setup;
 mod binary_minheap;
\ No newline at end of file
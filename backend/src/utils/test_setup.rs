@@ -1,19 +1,29 @@
 use log::info;
 use once_cell::sync::Lazy;
-use crate::{data::{osm_graph_creator::parse_and_write_osm_data, graph::Graph}};
+use crate::{data::{contraction_hierarchy::parse_graph_and_write_contraction_hierarchy, osm_graph_creator::parse_and_write_osm_data, graph::Graph}};
 
 pub const GRAPH_PATH:(&str, &str) = ("./tests_data/bremen-latest.osm.pbf", "./tests_data/output/test-bremen-latest.fmibin");
 //pub const GRAPH_PATH:(&str, &str) = ("./tests_data/stgcenter.pbf", "./tests_data/output/test-stgcenter.fmibin");
 
+/// Output file of the test graph with contracted nodes of degree two
+pub const CONTRACTED_GRAPH_PATH: &str = "./tests_data/output/test-bremen-latest-contracted.fmibin";
+
 pub static GRAPH: Lazy<Graph> = Lazy::new(|| {
     parse_pbf_to_fmi_file();
     Graph::parse_from_file(GRAPH_PATH.1).unwrap()
 });
 
+/// Test graph with contracted nodes of degree two and a contraction hierarchy
+pub static CONTRACTED_GRAPH: Lazy<Graph> = Lazy::new(|| {
+    parse_and_write_osm_data(GRAPH_PATH.0, CONTRACTED_GRAPH_PATH, true).ok();
+    parse_graph_and_write_contraction_hierarchy(CONTRACTED_GRAPH_PATH).ok();
+    Graph::parse_from_file(CONTRACTED_GRAPH_PATH).unwrap()
+});
+
 fn parse_pbf_to_fmi_file() {
         info!("starting test setup");
         info!("current working dir: {}",std::env::current_dir().unwrap().to_str().unwrap());
         let in_graph = GRAPH_PATH.0;
         let out_graph = GRAPH_PATH.1;
-        parse_and_write_osm_data(in_graph, out_graph).ok();
+        parse_and_write_osm_data(in_graph, out_graph, false).ok();
 }
\ No newline at end of file